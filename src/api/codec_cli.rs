@@ -0,0 +1,226 @@
+use crate::service::algorithms::{
+    gzip::{is_gzip, Gzip},
+    lz4::{is_lz4, Lz4},
+    snappy::{is_snappy, Snappy},
+    zlib::{is_zlib, Zlib},
+    Algorithm,
+};
+use crate::service::io::container::Container;
+use crate::service::io::file::File;
+use crate::service::io::new_codec;
+use crate::service::io::stream::{is_stream, StreamDecoder, StreamEncoder};
+use crate::service::pkg::traits::{Codec, Reader, Writer};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// compress_file compresses the input file into a container written to the output file
+pub fn compress_file(
+    input: &str,
+    output: &str,
+    algorithm: Algorithm,
+) -> Result<(), Box<dyn Error>> {
+    let mut file = File::new(input, output);
+    let data = file.read()?;
+
+    // formats read by other tools are written without a container
+    if algorithm.is_standalone() {
+        let encoded = standalone_codec(input, algorithm)?.encode(&data)?;
+        return file.write(&encoded);
+    }
+
+    let container = Container::compress(&data, algorithm)?;
+    file.write_container(&container)
+}
+
+/// standalone_codec returns the codec of a format read by other tools. gzip keeps the name
+/// and the modification time of the input file, so gunzip can restore them
+fn standalone_codec(input: &str, algorithm: Algorithm) -> Result<Box<dyn Codec>, Box<dyn Error>> {
+    match algorithm {
+        Algorithm::Gzip => {
+            let filename = Path::new(input)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let modified = fs::metadata(input)?
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| u32::try_from(elapsed.as_secs()).unwrap_or(0));
+            Ok(Box::new(Gzip::with_header(&filename, modified)))
+        }
+        algorithm => Ok(new_codec(algorithm).unwrap()),
+    }
+}
+
+/// compress_file_stream compresses the input file block by block into a stream,
+/// so only one block of the file is held in memory at a time
+pub fn compress_file_stream(
+    input: &str,
+    output: &str,
+    algorithm: Algorithm,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(fs::File::open(input)?);
+    let writer = BufWriter::new(fs::File::create(output)?);
+
+    let mut encoder = StreamEncoder::new(writer, algorithm)?;
+    io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// decompress_file restores the original file from the container, stream, gzip, zlib, lz4 or
+/// snappy file in the input file
+pub fn decompress_file(input: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(fs::File::open(input)?);
+
+    // peek at the magic without consuming it to tell a stream from a container
+    if is_stream(reader.fill_buf()?) {
+        let mut decoder = StreamDecoder::new(reader)?;
+        let mut writer = BufWriter::new(fs::File::create(output)?);
+        io::copy(&mut decoder, &mut writer)?;
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    // files written by gzip, zlib, lz4 and snappy tools are recognized by their header
+    let data = if is_gzip(&bytes) {
+        Gzip::new().decode(&bytes)?
+    } else if is_zlib(&bytes) {
+        Zlib::new().decode(&bytes)?
+    } else if is_lz4(&bytes) {
+        Lz4::new().decode(&bytes)?
+    } else if is_snappy(&bytes) {
+        Snappy::new().decode(&bytes)?
+    } else {
+        Container::from_bytes(&bytes)?.decompress()?
+    };
+    File::new(input, output).write(&data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compress_decompress_file_works() {
+        let dir = std::env::temp_dir();
+        let original = dir.join("text_compressor_cli_test.txt");
+        let compressed = dir.join("text_compressor_cli_test.tcz");
+        let restored = dir.join("text_compressor_cli_test.out");
+        let text = "TOBEORNOTTOBEORTOBEORNOT abracadabra! ".repeat(100);
+        fs::write(&original, &text).unwrap();
+
+        compress_file(
+            original.to_str().unwrap(),
+            compressed.to_str().unwrap(),
+            Algorithm::Lzw,
+        )
+        .unwrap();
+        assert!(fs::metadata(&compressed).unwrap().len() < text.len() as u64);
+
+        decompress_file(compressed.to_str().unwrap(), restored.to_str().unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&restored).unwrap(), text);
+
+        // a file that is not a container is rejected
+        assert!(decompress_file(original.to_str().unwrap(), restored.to_str().unwrap()).is_err());
+
+        for path in [original, compressed, restored] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn compress_decompress_file_stream_works() {
+        let dir = std::env::temp_dir();
+        let original = dir.join("text_compressor_cli_stream_test.txt");
+        let compressed = dir.join("text_compressor_cli_stream_test.tcz");
+        let restored = dir.join("text_compressor_cli_stream_test.out");
+        let text = "TOBEORNOTTOBEORTOBEORNOT abracadabra! ".repeat(1000);
+        fs::write(&original, &text).unwrap();
+
+        compress_file_stream(
+            original.to_str().unwrap(),
+            compressed.to_str().unwrap(),
+            Algorithm::Huffman,
+        )
+        .unwrap();
+        assert!(is_stream(&fs::read(&compressed).unwrap()));
+
+        decompress_file(compressed.to_str().unwrap(), restored.to_str().unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&restored).unwrap(), text);
+
+        for path in [original, compressed, restored] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn standalone_formats_work() {
+        let dir = std::env::temp_dir();
+        let original = dir.join("text_compressor_cli_gzip_test.txt");
+        let compressed = dir.join("text_compressor_cli_gzip_test.txt.gz");
+        let restored = dir.join("text_compressor_cli_gzip_test.out");
+        let text = "TOBEORNOTTOBEORTOBEORNOT abracadabra! ".repeat(100);
+        fs::write(&original, &text).unwrap();
+
+        let algorithms = [
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+        ];
+        for algorithm in algorithms {
+            compress_file(
+                original.to_str().unwrap(),
+                compressed.to_str().unwrap(),
+                algorithm.clone(),
+            )
+            .unwrap();
+            let bytes = fs::read(&compressed).unwrap();
+            assert_eq!(is_gzip(&bytes), algorithm == Algorithm::Gzip);
+            assert_eq!(is_zlib(&bytes), algorithm == Algorithm::Zlib);
+            assert_eq!(is_lz4(&bytes), algorithm == Algorithm::Lz4);
+            assert_eq!(is_snappy(&bytes), algorithm == Algorithm::Snappy);
+
+            decompress_file(compressed.to_str().unwrap(), restored.to_str().unwrap()).unwrap();
+            assert_eq!(fs::read_to_string(&restored).unwrap(), text);
+        }
+
+        // gzip keeps the name of the file
+        compress_file(
+            original.to_str().unwrap(),
+            compressed.to_str().unwrap(),
+            Algorithm::Gzip,
+        )
+        .unwrap();
+        let mut gzip = Gzip::new();
+        gzip.decode(&fs::read(&compressed).unwrap()).unwrap();
+        assert_eq!(
+            gzip.filename.as_deref(),
+            Some("text_compressor_cli_gzip_test.txt")
+        );
+        assert!(gzip.modified > 0);
+
+        // a file written by gzip
+        let gzipped = dir.join("text_compressor_cli_gzip_test.gz");
+        fs::write(
+            &gzipped,
+            include_bytes!("../../test_data/gzip/lines.txt.gz"),
+        )
+        .unwrap();
+        decompress_file(gzipped.to_str().unwrap(), restored.to_str().unwrap()).unwrap();
+        assert_eq!(
+            fs::read(&restored).unwrap(),
+            include_bytes!("../../test_data/deflate/dynamic.txt")
+        );
+
+        for path in [original, compressed, restored, gzipped] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::errors::app_error::{AppError, ErrorKind};
+use crate::models::dto::request_dto::{
+    DecodeRequest, DecodeResponse, EncodeRequest, EncodeResponse,
+};
+use crate::models::dto::APIResponse;
+use crate::server;
+use crate::service::{algorithms::Algorithm, io::new_codec};
+use actix_web::{
+    post,
+    web::{self, Json},
+    HttpResponse, Responder,
+};
+
+// linear_time swaps the bwt backend sorting rotations in O(n^2 log n) for SA-IS, which writes
+// the same payload in linear time, so a long repetitive request cannot tie up a worker
+fn linear_time(algorithm: Algorithm) -> Algorithm {
+    match algorithm {
+        Algorithm::Bwt => Algorithm::BwtSais,
+        Algorithm::Pipeline(stages) => {
+            Algorithm::Pipeline(stages.into_iter().map(linear_time).collect())
+        }
+        algorithm => algorithm,
+    }
+}
+
+#[post("/v1/compress")]
+pub async fn compress(request: Json<EncodeRequest>) -> impl Responder {
+    let (algorithm, data) = match request.validate() {
+        Ok(validated) => validated,
+        Err(err) => return err.to_responder(),
+    };
+
+    // every algorithm with an id has a codec, so the output is the raw format of the algorithm
+    let mut codec = new_codec(linear_time(algorithm.clone())).unwrap();
+    let encoded = match codec.encode(&data) {
+        Ok(encoded) => encoded,
+        Err(err) => return AppError::from(err).to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "data compressed successfully",
+        EncodeResponse::new(algorithm, data.len(), &encoded),
+    ))
+}
+
+#[post("/v1/decompress")]
+pub async fn decompress(
+    app_data: web::Data<server::AppState>,
+    request: Json<DecodeRequest>,
+) -> impl Responder {
+    let (algorithm, encoded) = match request.validate() {
+        Ok(validated) => validated,
+        Err(err) => return err.to_responder(),
+    };
+
+    // the encoded data comes from the client, so data that does not decode, or decodes to
+    // more than the server allows, is its fault
+    let mut codec = new_codec(algorithm.clone()).unwrap();
+    let decoded = match codec.decode_limited(&encoded, app_data.max_decoded_size) {
+        Ok(decoded) => decoded,
+        Err(err) => return AppError::new(&err.message, ErrorKind::FailedAction).to_responder(),
+    };
+
+    HttpResponse::Ok().json(APIResponse::success(
+        "data decompressed successfully",
+        DecodeResponse::new(algorithm, &decoded),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::server::{AppState, ServiceManager, DEFAULT_MAX_DECODED_SIZE};
+    use crate::utils::utils::{from_hex, to_hex};
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::{json, Value};
+
+    const LINES: &[u8] = include_bytes!("../../test_data/deflate/dynamic.txt");
+
+    fn app_state(max_decoded_size: usize) -> web::Data<AppState> {
+        web::Data::new(AppState::new(ServiceManager::new(), max_decoded_size))
+    }
+
+    #[actix_web::test]
+    async fn compress_decompress_works() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(DEFAULT_MAX_DECODED_SIZE))
+                .service(compress)
+                .service(decompress),
+        )
+        .await;
+
+        for algorithm in ["snappy", "snappyraw", "lz4", "gzip", "huffman"] {
+            let request = test::TestRequest::post()
+                .uri("/v1/compress")
+                .set_json(json!({"algorithm": algorithm, "data": to_hex(LINES)}))
+                .to_request();
+            let response: Value = test::call_and_read_body_json(&app, request).await;
+            let encoded = response["data"]["encoded"].as_str().unwrap();
+            assert!(from_hex(encoded).unwrap().len() < LINES.len());
+
+            let request = test::TestRequest::post()
+                .uri("/v1/decompress")
+                .set_json(json!({"algorithm": algorithm, "encoded": encoded}))
+                .to_request();
+            let response: Value = test::call_and_read_body_json(&app, request).await;
+            assert_eq!(response["data"]["decoded"], to_hex(LINES));
+        }
+    }
+
+    #[actix_web::test]
+    async fn any_data_round_trips() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(DEFAULT_MAX_DECODED_SIZE))
+                .service(compress)
+                .service(decompress),
+        )
+        .await;
+
+        // empty and binary data, and a long run the quadratic bwt backend would take ages on
+        let samples = [
+            ("huffman", vec![]),
+            ("lz4", vec![]),
+            ("bwt", vec![]),
+            (
+                "snappy",
+                include_bytes!("../../test_data/lz4/random.bin").to_vec(),
+            ),
+            ("deflate", vec![0xff, 0xfe, 0x00, 0x80, 0xc3, 0x28]),
+            ("bwt", vec![b'a'; 200_000]),
+            ("bwt|mtf|rle|huffman", vec![b'a'; 200_000]),
+        ];
+
+        for (algorithm, data) in samples {
+            let request = test::TestRequest::post()
+                .uri("/v1/compress")
+                .set_json(json!({"algorithm": algorithm, "data": to_hex(&data)}))
+                .to_request();
+            let response: Value = test::call_and_read_body_json(&app, request).await;
+            assert_eq!(response["data"]["input_size"], data.len(), "{}", algorithm);
+            let encoded = response["data"]["encoded"].as_str().unwrap();
+
+            let request = test::TestRequest::post()
+                .uri("/v1/decompress")
+                .set_json(json!({"algorithm": algorithm, "encoded": encoded}))
+                .to_request();
+            let response: Value = test::call_and_read_body_json(&app, request).await;
+            assert_eq!(response["data"]["decoded"], to_hex(&data), "{}", algorithm);
+        }
+    }
+
+    #[actix_web::test]
+    async fn snappy_files_are_decompressed() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(DEFAULT_MAX_DECODED_SIZE))
+                .service(decompress),
+        )
+        .await;
+        let fixtures: [(&str, &[u8]); 2] = [
+            ("snappy", include_bytes!("../../test_data/snappy/lines.sz")),
+            (
+                "snappyraw",
+                include_bytes!("../../test_data/snappy/lines.snappy"),
+            ),
+        ];
+
+        for (algorithm, encoded) in fixtures {
+            let request = test::TestRequest::post()
+                .uri("/v1/decompress")
+                .set_json(json!({"algorithm": algorithm, "encoded": to_hex(encoded)}))
+                .to_request();
+            let response: Value = test::call_and_read_body_json(&app, request).await;
+            assert_eq!(response["data"]["decoded_size"], LINES.len());
+            assert_eq!(response["data"]["decoded"], to_hex(LINES));
+        }
+    }
+
+    #[actix_web::test]
+    async fn binary_data_is_decompressed() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(DEFAULT_MAX_DECODED_SIZE))
+                .service(decompress),
+        )
+        .await;
+        let original = include_bytes!("../../test_data/lz4/random.bin");
+        let encoded = include_bytes!("../../test_data/lz4/random.lz4");
+        assert!(String::from_utf8(original.to_vec()).is_err());
+
+        let request = test::TestRequest::post()
+            .uri("/v1/decompress")
+            .set_json(json!({"algorithm": "lz4", "encoded": to_hex(encoded)}))
+            .to_request();
+        let response: Value = test::call_and_read_body_json(&app, request).await;
+        let decoded = response["data"]["decoded"].as_str().unwrap();
+        assert_eq!(from_hex(decoded).unwrap(), original);
+    }
+
+    #[actix_web::test]
+    async fn bad_requests_are_rejected() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(DEFAULT_MAX_DECODED_SIZE))
+                .service(compress)
+                .service(decompress),
+        )
+        .await;
+        let requests = [
+            ("/v1/compress", json!({"algorithm": "zip", "data": "00"})),
+            (
+                "/v1/compress",
+                json!({"algorithm": "snappy", "data": "xyz"}),
+            ),
+            (
+                "/v1/compress",
+                json!({"algorithm": "snappy", "text": "text"}),
+            ),
+            (
+                "/v1/decompress",
+                json!({"algorithm": "all", "encoded": "00"}),
+            ),
+            (
+                "/v1/decompress",
+                json!({"algorithm": "snappy", "encoded": "0011"}),
+            ),
+            (
+                "/v1/decompress",
+                json!({"algorithm": "snappy", "encoded": "xyz"}),
+            ),
+        ];
+
+        for (uri, body) in requests {
+            let request = test::TestRequest::post()
+                .uri(uri)
+                .set_json(body)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn oversized_data_is_rejected() {
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state(1000))
+                .service(compress)
+                .service(decompress),
+        )
+        .await;
+
+        // data that really decodes to more than the limit
+        let request = test::TestRequest::post()
+            .uri("/v1/compress")
+            .set_json(json!({"algorithm": "rle", "data": "61".repeat(1001)}))
+            .to_request();
+        let response: Value = test::call_and_read_body_json(&app, request).await;
+        let encoded = response["data"]["encoded"].as_str().unwrap().to_string();
+
+        // huffman and ans headers claiming 2^35 bytes, which ans decodes from no input at all
+        let mut ans = vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        ans.extend([0; 12]);
+        ans.push(1 << (b'a' % 8));
+        ans.extend([0; 19]);
+        ans.extend([0x80, 0x20, 0x00, 0x80, 0x00, 0x00]);
+        let requests = [
+            ("rle", encoded),
+            ("huffman", "8080808080200000".to_string()),
+            ("rans", to_hex(&ans)),
+            ("tans", to_hex(&ans)),
+        ];
+
+        for (algorithm, encoded) in requests {
+            let request = test::TestRequest::post()
+                .uri("/v1/decompress")
+                .set_json(json!({"algorithm": algorithm, "encoded": encoded}))
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", algorithm);
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use actix_web::web;
 
-pub mod threader_router;
+pub mod codec_cli;
+pub mod codec_router;
 pub mod threader_cli;
+pub mod threader_router;
 
 // init configures routes for the application
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(threader_router::benchmark_single_thread);
     cfg.service(threader_router::benchmark_multi_thread);
+    cfg.service(codec_router::compress);
+    cfg.service(codec_router::decompress);
 }
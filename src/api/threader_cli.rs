@@ -1,22 +1,29 @@
-use std::io::{Error, ErrorKind};
-use crate::models::dto::request_dto::{CompressRequest, CompressResponse};
+use crate::models::dto::request_dto::CompressResponse;
 use crate::models::threader::ThreadType;
 use crate::server::ServiceManager;
+use std::io::{Error, ErrorKind};
+
+pub fn benchmark_single_thread(data: Vec<u8>) -> Result<Vec<CompressResponse>, Error> {
+    benchmark(data, ThreadType::SingleThreaded)
+}
 
+pub fn benchmark_multi_thread(data: Vec<u8>) -> Result<Vec<CompressResponse>, Error> {
+    benchmark(data, ThreadType::MultiThreaded)
+}
 
-pub fn benchmark_multi_thread(
-    request: CompressRequest,
-) -> Result<Vec<CompressResponse>, Error> {
-    if let Err(err) = request.validate() {
-        return Err(Error::new(ErrorKind::InvalidInput, err.message));
+fn benchmark(data: Vec<u8>, thread_type: ThreadType) -> Result<Vec<CompressResponse>, Error> {
+    if data.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "data cannot be empty"));
     }
 
     let service_manager = ServiceManager::new();
-    let metrics = service_manager.threader.benchmark_algorithms(request.text, ThreadType::MultiThreaded);
+    let metrics = service_manager
+        .threader
+        .benchmark_algorithms(data, thread_type)?;
     let compress_responses = metrics
         .into_iter()
-        .map(|metric| CompressResponse::from(metric))
+        .map(CompressResponse::from)
         .collect::<Vec<CompressResponse>>();
 
     Ok(compress_responses)
-}
\ No newline at end of file
+}
@@ -1,3 +1,4 @@
+use crate::errors::app_error::AppError;
 use crate::models::dto::request_dto::CompressResponse;
 use crate::models::dto::APIResponse;
 use crate::models::{dto::request_dto::CompressRequest, threader::ThreadType};
@@ -7,7 +8,6 @@ use actix_web::{
     web::{self, Json},
     HttpResponse, Responder,
 };
-use log::error;
 
 #[post("/v1/single-thread")]
 pub async fn benchmark_single_thread(
@@ -20,13 +20,16 @@ pub async fn benchmark_single_thread(
         return err.to_responder();
     }
 
-    let metrics = app_data
-        .service_manager
-        .threader
-        .benchmark_algorithms(request.text.clone(), ThreadType::SingleThreaded);
+    let metrics = match app_data.service_manager.threader.benchmark_algorithms(
+        request.text.clone().into_bytes(),
+        ThreadType::SingleThreaded,
+    ) {
+        Ok(metrics) => metrics,
+        Err(err) => return AppError::from(err).to_responder(),
+    };
     let compress_responses = metrics
         .into_iter()
-        .map(|metric| CompressResponse::from(metric))
+        .map(CompressResponse::from)
         .collect::<Vec<CompressResponse>>();
 
     println!("Compression Metrics: {:?}", compress_responses);
@@ -47,13 +50,17 @@ pub async fn benchmark_multi_thread(
         return err.to_responder();
     }
 
-    let metrics = app_data
+    let metrics = match app_data
         .service_manager
         .threader
-        .benchmark_algorithms(request.text.clone(), ThreadType::MultiThreaded);
+        .benchmark_algorithms(request.text.clone().into_bytes(), ThreadType::MultiThreaded)
+    {
+        Ok(metrics) => metrics,
+        Err(err) => return AppError::from(err).to_responder(),
+    };
     let compress_responses = metrics
         .into_iter()
-        .map(|metric| CompressResponse::from(metric))
+        .map(CompressResponse::from)
         .collect::<Vec<CompressResponse>>();
 
     // return the metric as a response to the client
@@ -1,3 +1,4 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
 use actix_web::HttpResponse;
 use serde::Serialize;
 use std::error::Error;
@@ -33,6 +34,7 @@ impl AppError {
         }
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_responder(self) -> HttpResponse {
         match self.kind {
             ErrorKind::InternalServerError => HttpResponse::InternalServerError().finish(),
@@ -55,3 +57,14 @@ impl Error for AppError {
         &self.message
     }
 }
+
+// invalid input is the fault of the request, any other codec error is a server failure
+impl From<CodecError> for AppError {
+    fn from(err: CodecError) -> Self {
+        let kind = match err.kind {
+            CodecErrorKind::InvalidInput => ErrorKind::FailedAction,
+            _ => ErrorKind::InternalServerError,
+        };
+        AppError::new(&err.message, kind)
+    }
+}
@@ -0,0 +1,45 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecErrorKind {
+    InvalidInput,
+    CorruptedData,
+    InvalidMagic,
+    UnsupportedVersion,
+    UnknownAlgorithm,
+    ChecksumMismatch,
+    Internal,
+}
+
+// CodecError is the error returned when a codec fails to encode or decode data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError {
+    pub message: String,
+    pub kind: CodecErrorKind,
+}
+
+impl CodecError {
+    pub fn new(msg: &str, err_kind: CodecErrorKind) -> Self {
+        Self {
+            message: msg.to_string(),
+            kind: err_kind,
+        }
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CodecError {}
+
+// a codec error surfaced through std::io keeps its kind by being wrapped as invalid data
+impl From<CodecError> for io::Error {
+    fn from(err: CodecError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod api;
+pub mod errors;
+pub mod models;
+pub mod server;
+pub mod service;
+pub mod threading;
+pub mod utils;
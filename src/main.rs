@@ -1,34 +1,32 @@
-use crate::server::{start_cli, start_server};
 use clap::Parser;
 use dotenv::dotenv;
-use log::info;
 use std::env;
 use std::error::Error;
-use crate::service::io::args::Argument;
-
-mod api;
-mod errors;
-pub mod models;
-mod server;
-mod service;
-mod threading;
-mod utils;
+use text_compressor_rs::server::{start_cli, start_server, DEFAULT_MAX_DECODED_SIZE};
+use text_compressor_rs::service::io::args::Argument;
 
 #[actix_web::main]
-async fn main() -> Result<(), std::io::Error> {
+async fn main() -> Result<(), Box<dyn Error>> {
     // initialize the environment variable reader
     dotenv().ok();
 
+    let args = Argument::parse();
+    args.validate()?;
+
+    // run the command and exit when one is given
+    if let Some(command) = args.command() {
+        return start_cli(command);
+    }
+
     // set up env variables for the server
     let host = env::var("HOST").expect("HOST must be set in env");
     let port = env::var("PORT")
         .expect("PORT must be set in env")
         .parse::<u16>()
         .expect("invalid port number");
-    let file_name = env::var("FILE_NAME").ok();
+    let max_decoded_size = env::var("MAX_DECODED_SIZE")
+        .map(|size| size.parse::<usize>().expect("invalid max decoded size"))
+        .unwrap_or(DEFAULT_MAX_DECODED_SIZE);
 
-    match file_name {
-        Some(file_name) => start_cli(file_name),
-        None => start_server(&host, port).await
-    }
+    Ok(start_server(&host, port, max_decoded_size).await?)
 }
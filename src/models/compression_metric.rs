@@ -1,37 +1,38 @@
 use crate::service::algorithms::Algorithm;
+use crate::utils::utils::shannon_entropy;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct CompressionMetric {
     pub algorithm: String,
     pub input_size: u64,
-    pub encoded: String,
-    pub decoded: String,
+    pub encoded: Vec<u8>,
+    pub decoded: Vec<u8>,
     pub time_taken: Duration,
     pub compression_ratio: f64,
     pub memory_used: u64,
     pub bit_rate: f64,
+    pub entropy: f64,
+    pub throughput: f64,
 }
 
 impl CompressionMetric {
     pub fn new(
         algorithm: Algorithm,
-        encoded: String,
-        decoded: String,
+        encoded: Vec<u8>,
+        decoded: Vec<u8>,
         start_time: Instant,
     ) -> Self {
-        // if encoded.is_empty() || decoded.is_empty() {
-        //     panic!("encoded and decoded text should not be empty");
-        // }
-
         let n_encoded = encoded.len() as f64;
         let n_decoded = decoded.len() as f64;
 
-        let compression_ratio = n_decoded / n_encoded; // ratio of original to encoded text
-        let bit_rate = n_encoded / n_decoded; // ratio of encoded text to the original text
+        let compression_ratio = n_decoded / n_encoded; // ratio of original to encoded bytes
+        let bit_rate = n_encoded / n_decoded; // ratio of encoded bytes to the original bytes
         let memory_used = encoded.len() as u64; // amount of bytes used in encoding
         let time_taken = start_time.elapsed(); // amount of time taken to encode and decoded the string
         let input_size = decoded.len() as u64;
+        let entropy = shannon_entropy(&decoded); // order-0 bound in bits per byte, to compare with 8 * bit_rate
+        let throughput = Self::throughput(input_size, time_taken);
 
         Self {
             algorithm: algorithm.format(),
@@ -42,6 +43,46 @@ impl CompressionMetric {
             compression_ratio,
             memory_used,
             bit_rate,
+            entropy,
+            throughput,
+        }
+    }
+
+    /// throughput returns the megabytes of input encoded and decoded per second,
+    /// or zero when the timer is too coarse to measure the run
+    fn throughput(input_size: u64, time_taken: Duration) -> f64 {
+        match time_taken.as_secs_f64() {
+            seconds if seconds > 0.0 => input_size as f64 / seconds / 1e6,
+            _ => 0.0,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ratios_use_encoded_byte_sizes() {
+        let metric = CompressionMetric::new(
+            Algorithm::Huffman,
+            vec![0u8; 25],
+            vec![b'a'; 100],
+            Instant::now(),
+        );
+
+        assert_eq!(metric.input_size, 100);
+        assert_eq!(metric.memory_used, 25);
+        assert_eq!(metric.compression_ratio, 4.0);
+        assert_eq!(metric.bit_rate, 0.25);
+        assert_eq!(metric.entropy, 0.0);
+        assert!(metric.throughput >= 0.0);
+    }
+
+    #[test]
+    fn throughput_is_in_megabytes_per_second() {
+        let throughput = CompressionMetric::throughput(3_000_000, Duration::from_millis(500));
+        assert_eq!(throughput, 6.0);
+        assert_eq!(CompressionMetric::throughput(100, Duration::ZERO), 0.0);
+    }
+}
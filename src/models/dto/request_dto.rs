@@ -1,5 +1,7 @@
 use crate::errors::app_error::{AppError, ErrorKind};
 use crate::models::compression_metric::CompressionMetric;
+use crate::service::algorithms::Algorithm;
+use crate::utils::utils::{from_hex, to_hex};
 use serde::{Deserialize, Serialize};
 
 // CompressRequest represents the request for compressing a string
@@ -11,10 +13,7 @@ pub struct CompressRequest {
 
 impl CompressRequest {
     pub fn new(text: String, multithread: bool) -> Self {
-        Self {
-            text,
-            multithread
-        }
+        Self { text, multithread }
     }
 
     pub fn validate(&self) -> Result<(), AppError> {
@@ -38,6 +37,8 @@ pub struct CompressResponse {
     pub compression_ratio: f64,
     pub memory_used: u64,
     pub bit_rate: f64,
+    pub entropy: f64,
+    pub throughput: f64,
 }
 
 impl From<CompressionMetric> for CompressResponse {
@@ -45,12 +46,102 @@ impl From<CompressionMetric> for CompressResponse {
         Self {
             algorithm: value.algorithm,
             input_size: value.input_size,
-            encoded: value.encoded,
-            decoded: value.decoded,
+            encoded: to_hex(&value.encoded),
+            decoded: String::from_utf8_lossy(&value.decoded).into_owned(),
             time_taken: value.time_taken.as_nanos(),
             compression_ratio: value.compression_ratio,
             memory_used: value.memory_used,
             bit_rate: value.bit_rate,
+            entropy: value.entropy,
+            throughput: value.throughput,
+        }
+    }
+}
+
+// parse_algorithm returns the algorithm of a request, if it is one that encodes data
+fn parse_algorithm(name: &str) -> Result<Algorithm, AppError> {
+    let algorithm = Algorithm::from(name.to_string());
+    if algorithm.id().is_none() {
+        return Err(AppError::new(
+            &format!("unknown algorithm: {}", name),
+            ErrorKind::FailedAction,
+        ));
+    }
+    Ok(algorithm)
+}
+
+// EncodeRequest represents the request for compressing hex encoded data with one algorithm
+#[derive(Deserialize, Debug)]
+pub struct EncodeRequest {
+    pub algorithm: String,
+    pub data: String,
+}
+
+impl EncodeRequest {
+    // validate returns the algorithm and the bytes of the data, which may be empty
+    pub fn validate(&self) -> Result<(Algorithm, Vec<u8>), AppError> {
+        let algorithm = parse_algorithm(&self.algorithm)?;
+        let data = from_hex(&self.data).ok_or_else(|| {
+            AppError::new("data must be a hexadecimal string", ErrorKind::FailedAction)
+        })?;
+        Ok((algorithm, data))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EncodeResponse {
+    pub algorithm: String,
+    pub input_size: u64,
+    pub encoded_size: u64,
+    pub encoded: String,
+}
+
+impl EncodeResponse {
+    pub fn new(algorithm: Algorithm, input_size: usize, encoded: &[u8]) -> Self {
+        Self {
+            algorithm: algorithm.to_string(),
+            input_size: input_size as u64,
+            encoded_size: encoded.len() as u64,
+            encoded: to_hex(encoded),
+        }
+    }
+}
+
+// DecodeRequest represents the request for decompressing hex encoded data with one algorithm
+#[derive(Deserialize, Debug)]
+pub struct DecodeRequest {
+    pub algorithm: String,
+    pub encoded: String,
+}
+
+impl DecodeRequest {
+    // validate returns the algorithm and the bytes of the encoded data
+    pub fn validate(&self) -> Result<(Algorithm, Vec<u8>), AppError> {
+        let algorithm = parse_algorithm(&self.algorithm)?;
+        let encoded = from_hex(&self.encoded).ok_or_else(|| {
+            AppError::new(
+                "encoded data must be a hexadecimal string",
+                ErrorKind::FailedAction,
+            )
+        })?;
+        Ok((algorithm, encoded))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodeResponse {
+    pub algorithm: String,
+    pub decoded_size: u64,
+    pub decoded: String,
+}
+
+impl DecodeResponse {
+    pub fn new(algorithm: Algorithm, decoded: &[u8]) -> Self {
+        Self {
+            algorithm: algorithm.to_string(),
+            decoded_size: decoded.len() as u64,
+            // the decoded data may be binary, so it is sent as hex like the encoded data
+            decoded: to_hex(decoded),
         }
     }
 }
@@ -1,2 +1,2 @@
 #[derive(Debug)]
-pub struct Part(pub(crate) usize, pub(crate) String);
+pub struct Part(pub(crate) usize, pub(crate) Vec<u8>);
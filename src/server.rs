@@ -1,23 +1,32 @@
 use crate::api;
+use crate::api::codec_cli::{compress_file, compress_file_stream, decompress_file};
+use crate::api::threader_cli::{benchmark_multi_thread, benchmark_single_thread};
+use crate::service::io::args::Command;
+use crate::service::io::file::File;
+use crate::service::pkg::traits::Reader;
 use crate::threading::Threader;
 use actix_cors::Cors;
 use actix_web::{http, middleware, web, App, HttpServer};
-use log::info;
-use crate::api::threader_cli::benchmark_multi_thread;
-use crate::models::dto::request_dto::CompressRequest;
-use crate::service::io::file::File;
-use crate::service::pkg::traits::{Reader, Writer};
+use std::error::Error;
 use std::fs::File as StdFile;
 
+// DEFAULT_MAX_DECODED_SIZE is the most bytes a request decompresses to when the
+// environment sets no limit
+pub const DEFAULT_MAX_DECODED_SIZE: usize = 16 << 20;
+
 // AppState holds the state of the application
 pub struct AppState {
     pub service_manager: ServiceManager,
+    pub max_decoded_size: usize,
 }
 
 // contains methods for managing the application state
 impl AppState {
-    pub fn new(service_manager: ServiceManager) -> Self {
-        Self { service_manager }
+    pub fn new(service_manager: ServiceManager, max_decoded_size: usize) -> Self {
+        Self {
+            service_manager,
+            max_decoded_size,
+        }
     }
 }
 
@@ -33,25 +42,58 @@ impl ServiceManager {
     }
 }
 
-// start_cli starts the CLI multi-thread for large files
-pub fn start_cli(file_name: String) -> Result<(), std::io::Error> {
-    let mut file = File::new(&file_name, "out_data.json");
-    let text = file.read().expect("cannot read file!");
-    let request = CompressRequest::new(text, true);
-    let result = benchmark_multi_thread(request);
-    let result = match result {
-        Ok(result) => result,
-        Err(err) => return Err(err),
+impl Default for ServiceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// start_cli runs a single CLI command
+pub fn start_cli(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Compress {
+            input,
+            output,
+            algorithm,
+            stream: false,
+        } => compress_file(&input, &output, algorithm),
+        Command::Compress {
+            input,
+            output,
+            algorithm,
+            stream: true,
+        } => compress_file_stream(&input, &output, algorithm),
+        Command::Decompress { input, output } => decompress_file(&input, &output),
+        Command::Bench {
+            input,
+            output,
+            multithread,
+        } => start_benchmark(&input, &output, multithread),
+    }
+}
+
+// start_benchmark benchmarks every algorithm on a file and writes the metrics as JSON
+fn start_benchmark(input: &str, output: &str, multithread: bool) -> Result<(), Box<dyn Error>> {
+    let mut file = File::new(input, output);
+    let data = file.read()?;
+    let result = match multithread {
+        true => benchmark_multi_thread(data)?,
+        false => benchmark_single_thread(data)?,
     };
 
-    let file = StdFile::create("out_data.json")?;
+    let file = StdFile::create(output)?;
     serde_json::to_writer(file, &result)?;
 
     Ok(())
 }
 
-// start_server starts and launches the http server
-pub async fn start_server(host: &str, port: u16) -> Result<(), std::io::Error> {
+// start_server starts and launches the http server, decompressing requests to at most
+// max_decoded_size bytes
+pub async fn start_server(
+    host: &str,
+    port: u16,
+    max_decoded_size: usize,
+) -> Result<(), std::io::Error> {
     HttpServer::new(move || {
         // get the handle for the service manager
         let service_manager = ServiceManager::new();
@@ -66,7 +108,10 @@ pub async fn start_server(host: &str, port: u16) -> Result<(), std::io::Error> {
         // launch the http server
         App::new()
             .wrap(middleware::Logger::default())
-            .app_data(web::Data::new(AppState::new(service_manager)))
+            .app_data(web::Data::new(AppState::new(
+                service_manager,
+                max_decoded_size,
+            )))
             .configure(api::init)
     })
     .bind((host, port))?
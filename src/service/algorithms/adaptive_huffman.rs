@@ -0,0 +1,123 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::{
+        adaptive_tree::{AdaptiveHuffmanTree, END_OF_STREAM},
+        bit_stream::{BitReader, BitWriter},
+    },
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// AdaptiveHuffman represents single pass Huffman encoding with the FGK algorithm.
+/// The encoder and the decoder start from the same empty tree and update it after
+/// every byte, so the data is coded as it arrives without a code length table or its length.
+/// The payload is the bit stream of the codes, a new byte being the NYT code followed by
+/// its value in 9 bits, ended by the NYT code followed by the reserved value 256.
+/// Its last byte is padded with zeros.
+#[derive(Default)]
+pub struct AdaptiveHuffman {}
+
+impl AdaptiveHuffman {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Codec for AdaptiveHuffman {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut tree = AdaptiveHuffmanTree::new();
+        let mut writer = BitWriter::new();
+        for byte in data {
+            tree.write_symbol(&mut writer, *byte);
+            tree.update(*byte);
+        }
+        tree.write_end(&mut writer);
+
+        Ok(writer.finish())
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+        let mut reader = BitReader::new(data);
+
+        // every code takes at least one bit
+        let mut tree = AdaptiveHuffmanTree::new();
+        let mut decoded = Vec::with_capacity(reader.remaining().min(max_size));
+        loop {
+            let symbol = tree
+                .read_symbol(&mut reader)
+                .ok_or_else(|| corrupted("adaptive huffman data ended before its end of stream"))?;
+            let byte = match symbol {
+                END_OF_STREAM => return Ok(decoded),
+                symbol => u8::try_from(symbol)
+                    .map_err(|_| corrupted("adaptive huffman symbol is out of range"))?,
+            };
+
+            check_decoded_size(decoded.len() + 1, max_size)?;
+            tree.update(byte);
+            decoded.push(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::huffman::Huffman;
+    use crate::utils::test_utils::binary_samples;
+
+    #[test]
+    fn adaptive_huffman_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"aaaaaaaaaaaaaaaa".to_vec(),
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+            (0..=255u8).rev().collect(),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = AdaptiveHuffman::new().encode(&test_case).unwrap();
+            let decoded = AdaptiveHuffman::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn encoding_is_close_to_static_huffman() {
+        let text = "aaaaaaaabbbbbbbccccdd adaptive huffman ".repeat(100);
+        let adaptive = AdaptiveHuffman::new().encode(text.as_bytes()).unwrap();
+        let canonical = Huffman::new().encode(text.as_bytes()).unwrap();
+
+        assert!(adaptive.len() < text.len() / 2);
+        assert!(adaptive.len() < canonical.len() * 11 / 10);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut huffman = AdaptiveHuffman::new();
+        assert!(huffman.decode(&[]).is_err());
+
+        // the first symbol is raw, and 9 bits hold values past END_OF_STREAM
+        assert!(huffman.decode(&[0xff, 0x01]).is_err());
+
+        // data cut before its end of stream
+        let encoded = huffman.encode(b"abracadabra").unwrap();
+        assert!(huffman.decode(&encoded[..encoded.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn data_ends_at_the_end_of_stream() {
+        // an empty tree codes the end of stream with no code and 9 raw bits
+        let encoded = AdaptiveHuffman::new().encode(b"").unwrap();
+        assert_eq!(encoded, [0x00, 0x01]);
+
+        // anything after the end of stream is not part of the data
+        let mut encoded = AdaptiveHuffman::new().encode(b"abracadabra").unwrap();
+        encoded.extend([0xff; 4]);
+        let decoded = AdaptiveHuffman::new().decode(&encoded).unwrap();
+        assert_eq!(decoded, b"abracadabra");
+    }
+}
@@ -0,0 +1,517 @@
+//! Asymmetric numeral systems code a symbol of probability f / M by growing a single
+//! integer state by about log2(M / f) bits. Both coders here use a static model:
+//! the byte counts are normalized so they sum to M = 2^SCALE_BITS, and the payload
+//! starts with the number of bytes and the normalized frequency table.
+//!
+//! The frequency table is a bitmap of the 256 byte values followed by the
+//! frequency of every byte set in it as a varint.
+//!
+//! The state is a stack: the encoder codes the data backwards, so the decoder
+//! reads the symbols forwards.
+
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::data_structures::byte_cursor::{write_varint, ByteCursor};
+use crate::service::pkg::traits::check_decoded_size;
+
+/// SCALE_BITS is the precision of the normalized frequencies
+const SCALE_BITS: u32 = 12;
+
+/// SCALE is the sum of the normalized frequencies
+const SCALE: u32 = 1 << SCALE_BITS;
+
+/// FrequencyTable holds the normalized frequency of every byte and where its slots start
+struct FrequencyTable {
+    frequencies: [u32; 256],
+    starts: [u32; 256],
+}
+
+impl FrequencyTable {
+    fn from_frequencies(frequencies: [u32; 256]) -> Self {
+        let mut starts = [0u32; 256];
+        for symbol in 1..256 {
+            starts[symbol] = starts[symbol - 1] + frequencies[symbol - 1];
+        }
+        Self {
+            frequencies,
+            starts,
+        }
+    }
+
+    /// normalize scales the byte counts of the data to sum to SCALE, keeping a frequency
+    /// of at least 1 for every byte of the data
+    fn normalize(data: &[u8]) -> Self {
+        let mut counts = [0u64; 256];
+        for byte in data {
+            counts[*byte as usize] += 1;
+        }
+
+        let mut frequencies = [0u32; 256];
+        for (frequency, count) in frequencies.iter_mut().zip(counts) {
+            if count > 0 {
+                *frequency = ((count * SCALE as u64 / data.len() as u64) as u32).max(1);
+            }
+        }
+
+        // the rounding is paid by the most frequent bytes, which lose the least by it
+        let mut sum = frequencies.iter().sum::<u32>();
+        while sum != SCALE {
+            let largest = (0..256).max_by_key(|s| frequencies[*s]).unwrap();
+            if sum < SCALE {
+                frequencies[largest] += SCALE - sum;
+                sum = SCALE;
+            } else {
+                // at most 256 bytes with a frequency of 1 always fit in SCALE
+                let excess = (sum - SCALE).min(frequencies[largest] - 1);
+                frequencies[largest] -= excess;
+                sum -= excess;
+            }
+        }
+
+        Self::from_frequencies(frequencies)
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut bitmap = [0u8; 32];
+        for (symbol, frequency) in self.frequencies.iter().enumerate() {
+            if *frequency > 0 {
+                bitmap[symbol / 8] |= 1 << (symbol % 8);
+            }
+        }
+        out.extend(bitmap);
+
+        for frequency in self.frequencies.iter().filter(|frequency| **frequency > 0) {
+            write_varint(out, *frequency as u64);
+        }
+    }
+
+    /// read reads a table written by write, rejecting tables that do not sum to SCALE
+    fn read(cursor: &mut ByteCursor) -> Result<Self, CodecError> {
+        let corrupted = || {
+            CodecError::new(
+                "ans frequency table is invalid",
+                CodecErrorKind::CorruptedData,
+            )
+        };
+
+        let bitmap = cursor.read_bytes(32)?;
+        let mut frequencies = [0u32; 256];
+        let mut sum = 0u64;
+        for (symbol, frequency) in frequencies.iter_mut().enumerate() {
+            if bitmap[symbol / 8] >> (symbol % 8) & 1 == 1 {
+                let value = cursor.read_varint()?;
+                if value == 0 || value > SCALE as u64 {
+                    return Err(corrupted());
+                }
+                *frequency = value as u32;
+                sum += value;
+            }
+        }
+
+        if sum != SCALE as u64 {
+            return Err(corrupted());
+        }
+        Ok(Self::from_frequencies(frequencies))
+    }
+
+    /// slot_symbols maps every slot of 0..SCALE to the symbol owning it
+    fn slot_symbols(&self) -> Vec<u8> {
+        let mut symbols = Vec::with_capacity(SCALE as usize);
+        for (symbol, frequency) in self.frequencies.iter().enumerate() {
+            symbols.extend(std::iter::repeat_n(symbol as u8, *frequency as usize));
+        }
+        symbols
+    }
+}
+
+/// write_header writes the number of bytes and, for non-empty data, the frequency table
+fn write_header(out: &mut Vec<u8>, data: &[u8]) -> Option<FrequencyTable> {
+    write_varint(out, data.len() as u64);
+    if data.is_empty() {
+        return None;
+    }
+
+    let table = FrequencyTable::normalize(data);
+    table.write(out);
+    Some(table)
+}
+
+/// read_header reads the header written by write_header, of at most max_size symbols. A symbol
+/// of frequency SCALE is decoded without reading any input, so only the limit bounds them
+fn read_header(
+    data: &[u8],
+    max_size: usize,
+) -> Result<(usize, Option<FrequencyTable>, &[u8]), CodecError> {
+    let mut cursor = ByteCursor::new(data);
+    let symbol_count = cursor.read_varint()?;
+    if symbol_count == 0 {
+        return Ok((0, None, cursor.remaining()));
+    }
+    let symbol_count = usize::try_from(symbol_count).unwrap_or(usize::MAX);
+    check_decoded_size(symbol_count, max_size)?;
+
+    let table = FrequencyTable::read(&mut cursor)?;
+    Ok((symbol_count, Some(table), cursor.remaining()))
+}
+
+/// final_state_error reports a stream that does not end where the encoder started
+fn final_state_error() -> CodecError {
+    CodecError::new(
+        "ans data does not end in the initial state",
+        CodecErrorKind::CorruptedData,
+    )
+}
+
+pub mod rans {
+    use super::{final_state_error, read_header, write_header, SCALE, SCALE_BITS};
+    use crate::errors::codec_error::{CodecError, CodecErrorKind};
+    use crate::service::pkg::traits::Codec;
+
+    /// LOWER_BOUND is the smallest state, states stay in [LOWER_BOUND, LOWER_BOUND << 8)
+    const LOWER_BOUND: u32 = 1 << 23;
+
+    /// Rans represents range asymmetric numeral systems with a 32 bit state, emitting bytes.
+    /// Coding a symbol takes a division, and decoding looks up the slot of the state.
+    /// After the header the payload holds the final state of the encoder in 4 bytes
+    /// (big endian), followed by the bytes it shifted out in the order they are read back.
+    #[derive(Clone, Default)]
+    pub struct Rans {}
+
+    impl Rans {
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+
+    impl Codec for Rans {
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+            let mut encoded = Vec::new();
+            let table = match write_header(&mut encoded, data) {
+                Some(table) => table,
+                None => return Ok(encoded),
+            };
+
+            // the bytes are pushed in the reverse of their reading order
+            let mut reversed = Vec::with_capacity(data.len());
+            let mut state = LOWER_BOUND;
+            for byte in data.iter().rev() {
+                let frequency = table.frequencies[*byte as usize];
+                let start = table.starts[*byte as usize];
+
+                // shift bytes out until coding the symbol keeps the state under its upper bound
+                let max_state = ((LOWER_BOUND >> SCALE_BITS) << 8) * frequency;
+                while state >= max_state {
+                    reversed.push(state as u8);
+                    state >>= 8;
+                }
+                state = ((state / frequency) << SCALE_BITS) + state % frequency + start;
+            }
+
+            reversed.extend(state.to_le_bytes());
+            encoded.extend(reversed.into_iter().rev());
+            Ok(encoded)
+        }
+
+        fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+            let (symbol_count, table, payload) = read_header(data, max_size)?;
+            let table = match table {
+                Some(table) => table,
+                None => return Ok(Vec::new()),
+            };
+            let slot_symbols = table.slot_symbols();
+
+            let truncated = || {
+                CodecError::new(
+                    "rans data ended before every symbol was decoded",
+                    CodecErrorKind::CorruptedData,
+                )
+            };
+            let mut bytes = payload.iter();
+            let mut state = 0u32;
+            for _ in 0..4 {
+                state = (state << 8) | *bytes.next().ok_or_else(truncated)? as u32;
+            }
+            if !(LOWER_BOUND..LOWER_BOUND << 8).contains(&state) {
+                return Err(final_state_error());
+            }
+
+            // a symbol can take no input at all, so the output grows with the decoded symbols
+            let mut decoded = Vec::new();
+            while decoded.len() < symbol_count {
+                let slot = state & (SCALE - 1);
+                let byte = slot_symbols[slot as usize];
+                let frequency = table.frequencies[byte as usize];
+                let start = table.starts[byte as usize];
+                state = frequency * (state >> SCALE_BITS) + slot - start;
+                while state < LOWER_BOUND {
+                    state = (state << 8) | *bytes.next().ok_or_else(truncated)? as u32;
+                }
+                decoded.push(byte);
+            }
+
+            if state != LOWER_BOUND || bytes.next().is_some() {
+                return Err(final_state_error());
+            }
+            Ok(decoded)
+        }
+    }
+}
+
+pub mod tans {
+    use super::{final_state_error, read_header, write_header, FrequencyTable, SCALE, SCALE_BITS};
+    use crate::errors::codec_error::{CodecError, CodecErrorKind};
+    use crate::service::data_structures::bit_stream::{BitReader, BitWriter};
+    use crate::service::pkg::traits::Codec;
+
+    /// Tans represents table asymmetric numeral systems, the finite state entropy coder of zstd.
+    /// The slots of the states are spread over the symbols, and coding or decoding a symbol
+    /// is a table lookup plus a few raw bits. States stay in [SCALE, 2 * SCALE).
+    /// After the header the payload is a bit stream holding the final state of the
+    /// encoder in SCALE_BITS bits, followed by the bits it emitted in the order they are read back.
+    #[derive(Clone, Default)]
+    pub struct Tans {}
+
+    /// DecodeEntry is what the decoder does in a state: emit the symbol, then read
+    /// n_bits bits and add them to base to get the next state
+    #[derive(Clone, Copy, Default)]
+    struct DecodeEntry {
+        symbol: u8,
+        n_bits: u32,
+        base: u32,
+    }
+
+    impl Tans {
+        pub fn new() -> Self {
+            Self {}
+        }
+
+        /// spread_symbols deals the slots of every symbol over the table with an odd step,
+        /// so the slots of a symbol are scattered across the states
+        fn spread_symbols(table: &FrequencyTable) -> Vec<u8> {
+            let step = (SCALE >> 1) + (SCALE >> 3) + 3;
+            let mut spread = vec![0u8; SCALE as usize];
+            let mut position = 0u32;
+            for (symbol, frequency) in table.frequencies.iter().enumerate() {
+                for _ in 0..*frequency {
+                    spread[position as usize] = symbol as u8;
+                    position = (position + step) & (SCALE - 1);
+                }
+            }
+            spread
+        }
+
+        /// encode_positions lists the states of every symbol in increasing order. The k-th state
+        /// of a symbol with frequency f is the one the decoder reaches from the sub-state f + k
+        fn encode_positions(spread: &[u8]) -> Vec<Vec<u32>> {
+            let mut positions = vec![Vec::new(); 256];
+            for (position, symbol) in spread.iter().enumerate() {
+                positions[*symbol as usize].push(SCALE + position as u32);
+            }
+            positions
+        }
+
+        /// decode_table builds the decoder entry of every state from the spread symbols
+        fn decode_table(table: &FrequencyTable, spread: &[u8]) -> Vec<DecodeEntry> {
+            let mut next = table.frequencies;
+            spread
+                .iter()
+                .map(|symbol| {
+                    let sub_state = next[*symbol as usize];
+                    next[*symbol as usize] += 1;
+
+                    // shift the sub-state back into [SCALE, 2 * SCALE)
+                    let n_bits = SCALE_BITS - sub_state.ilog2();
+                    DecodeEntry {
+                        symbol: *symbol,
+                        n_bits,
+                        base: sub_state << n_bits,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    impl Codec for Tans {
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+            let mut encoded = Vec::new();
+            let table = match write_header(&mut encoded, data) {
+                Some(table) => table,
+                None => return Ok(encoded),
+            };
+            let positions = Self::encode_positions(&Self::spread_symbols(&table));
+
+            // the bits are collected in the reverse of their reading order
+            let mut chunks = Vec::with_capacity(data.len());
+            let mut state = SCALE;
+            for byte in data.iter().rev() {
+                let frequency = table.frequencies[*byte as usize];
+                let mut n_bits = 0;
+                while (state >> n_bits) >= 2 * frequency {
+                    n_bits += 1;
+                }
+                chunks.push((state & ((1 << n_bits) - 1), n_bits));
+
+                let sub_state = state >> n_bits;
+                state = positions[*byte as usize][(sub_state - frequency) as usize];
+            }
+
+            let mut writer = BitWriter::new();
+            writer.write_bits((state - SCALE) as u64, SCALE_BITS);
+            for (bits, n_bits) in chunks.into_iter().rev() {
+                writer.write_bits(bits as u64, n_bits);
+            }
+            encoded.extend(writer.finish());
+            Ok(encoded)
+        }
+
+        fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+            let (symbol_count, table, payload) = read_header(data, max_size)?;
+            let table = match table {
+                Some(table) => table,
+                None => return Ok(Vec::new()),
+            };
+            let decode_table = Self::decode_table(&table, &Self::spread_symbols(&table));
+
+            let truncated = || {
+                CodecError::new(
+                    "tans data ended before every symbol was decoded",
+                    CodecErrorKind::CorruptedData,
+                )
+            };
+            let mut reader = BitReader::new(payload);
+            let mut state = SCALE + reader.read_bits(SCALE_BITS).ok_or_else(truncated)? as u32;
+
+            // a symbol can take no input at all, so the output grows with the decoded symbols
+            let mut decoded = Vec::new();
+            while decoded.len() < symbol_count {
+                let entry = decode_table[(state - SCALE) as usize];
+                let bits = reader.read_bits(entry.n_bits).ok_or_else(truncated)? as u32;
+                state = entry.base + bits;
+                decoded.push(entry.symbol);
+            }
+
+            // only the zero bits padding the last byte may be left
+            if state != SCALE || reader.remaining() >= 8 {
+                return Err(final_state_error());
+            }
+            Ok(decoded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::rans::Rans;
+    use super::tans::Tans;
+    use super::*;
+    use crate::service::algorithms::huffman::Huffman;
+    use crate::service::pkg::traits::Codec;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+    use crate::utils::utils::shannon_entropy;
+
+    fn codecs() -> Vec<Box<dyn Codec>> {
+        vec![Box::new(Rans::new()), Box::new(Tans::new())]
+    }
+
+    #[test]
+    fn normalized_frequencies_sum_to_scale() {
+        let mut rng = XorShift::new(3);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"a".to_vec(),
+            b"ab".repeat(1000),
+            (0..=255u8).collect(),
+            // a single frequent byte next to every rare one takes the whole rounding
+            [vec![b'x'; 100_000], (0..=255u8).collect()].concat(),
+            (0..10_000).map(|_| (rng.next_u64() % 7) as u8).collect(),
+        ]);
+
+        for test_case in test_cases.iter().filter(|test_case| !test_case.is_empty()) {
+            let table = FrequencyTable::normalize(test_case);
+            assert_eq!(table.frequencies.iter().sum::<u32>(), SCALE);
+            for byte in test_case {
+                assert!(table.frequencies[*byte as usize] > 0);
+            }
+
+            let mut written = Vec::new();
+            table.write(&mut written);
+            let read = FrequencyTable::read(&mut ByteCursor::new(&written)).unwrap();
+            assert_eq!(read.frequencies, table.frequencies);
+        }
+    }
+
+    #[test]
+    fn ans_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            vec![0xff; 10_000],
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+            [vec![b'x'; 100_000], (0..=255u8).collect()].concat(),
+        ]);
+
+        for mut codec in codecs() {
+            for test_case in test_cases.iter() {
+                let encoded = codec.encode(test_case).unwrap();
+                assert_eq!(codec.decode(&encoded).unwrap(), *test_case);
+            }
+        }
+    }
+
+    #[test]
+    fn skewed_data_beats_huffman() {
+        let mut rng = XorShift::new(11);
+        let data = (0..100_000)
+            .map(|_| match rng.next_u64() % 40 {
+                0 => b'c',
+                1..=3 => b'b',
+                _ => b'a',
+            })
+            .collect::<Vec<u8>>();
+        let huffman = Huffman::new().encode(&data).unwrap();
+        let bound = shannon_entropy(&data) * data.len() as f64 / 8.0;
+
+        for mut codec in codecs() {
+            let encoded = codec.encode(&data).unwrap();
+            assert!(encoded.len() * 2 < huffman.len());
+            assert!((encoded.len() as f64) < bound * 1.02 + 64.0);
+        }
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let data = "TOBEORNOTTOBEORTOBEORNOT".repeat(20).into_bytes();
+
+        for mut codec in codecs() {
+            assert!(codec.decode(&[5]).is_err());
+            assert!(codec.decode(&[5; 40]).is_err());
+
+            let encoded = codec.encode(&data).unwrap();
+            assert!(codec.decode(&encoded[..encoded.len() - 2]).is_err());
+
+            let mut flipped = encoded.clone();
+            let position = flipped.len() - 10;
+            flipped[position] ^= 0x10;
+            let decoded = codec.decode(&flipped);
+            assert!(decoded.is_err() || decoded.unwrap() != data);
+        }
+    }
+
+    #[test]
+    fn huge_symbol_count_is_rejected() {
+        // 2^35 symbols of a byte with frequency SCALE, which decode without reading any input
+        let mut data = vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        let mut bitmap = [0u8; 32];
+        bitmap[b'a' as usize / 8] |= 1 << (b'a' % 8);
+        data.extend(bitmap);
+        write_varint(&mut data, SCALE as u64);
+        data.extend([0x00, 0x80, 0x00, 0x00]);
+
+        for mut codec in codecs() {
+            let err = codec.decode(&data).unwrap_err();
+            assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    algorithms::{
+        burrows_wheeler_transform::sa_is::BurrowsWheelerTransform, huffman::Huffman,
+        move_to_front::MoveToFront,
+    },
+    data_structures::byte_cursor::{write_varint, ByteCursor, MAX_VARINT_SIZE},
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// RUNA and RUNB are the digits of a run of zeros written in bijective base 2,
+/// RUNA being worth 1 and RUNB 2 times the weight of its position
+const RUNA: u16 = 0;
+const RUNB: u16 = 1;
+
+/// SYMBOL_BITS is the width of the zero run symbols, a non-zero byte v is written as v + 1
+const SYMBOL_BITS: u32 = 9;
+
+/// MAX_RUN_DIGITS bounds the digits of a single run, so its length cannot overflow
+const MAX_RUN_DIGITS: usize = 32;
+
+/// BlockSort represents a bzip2-style block sorting compressor. The data goes through
+/// the Burrows-Wheeler transform, the Move-to-Front transform and a zero run encoding
+/// with RUNA/RUNB symbols, and the symbols are then compressed with Huffman encoding.
+/// The payload starts with the length of the transformed block as a varint, which bounds
+/// the zero runs when decoding.
+#[derive(Default)]
+pub struct BlockSort {
+    bwt: BurrowsWheelerTransform,
+    mtf: MoveToFront,
+    huffman: Huffman,
+}
+
+impl BlockSort {
+    pub fn new() -> Self {
+        Self {
+            bwt: BurrowsWheelerTransform::new(),
+            mtf: MoveToFront::new(),
+            huffman: Huffman::new(),
+        }
+    }
+
+    /// encode_zero_runs replaces every run of zeros by its length in RUNA/RUNB digits,
+    /// least significant first, and shifts every other byte past the two run symbols
+    fn encode_zero_runs(data: &[u8]) -> Vec<u16> {
+        let mut symbols = Vec::with_capacity(data.len());
+        let mut run = 0u64;
+
+        for byte in data.iter().chain(std::iter::once(&1u8)) {
+            if *byte == 0 {
+                run += 1;
+                continue;
+            }
+
+            while run > 0 {
+                if run & 1 == 1 {
+                    symbols.push(RUNA);
+                    run = (run - 1) / 2;
+                } else {
+                    symbols.push(RUNB);
+                    run = (run - 2) / 2;
+                }
+            }
+            symbols.push(*byte as u16 + 1);
+        }
+
+        // drop the byte chained above to flush the last run
+        symbols.pop();
+        symbols
+    }
+
+    /// decode_zero_runs expands the runs written by encode_zero_runs into block_length bytes
+    fn decode_zero_runs(symbols: &[u16], block_length: usize) -> Result<Vec<u8>, CodecError> {
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+        let mut data = Vec::with_capacity(symbols.len().min(block_length));
+        let (mut run, mut weight, mut digits) = (0u64, 1u64, 0usize);
+
+        for symbol in symbols.iter().chain(std::iter::once(&2u16)) {
+            match *symbol {
+                RUNA | RUNB => {
+                    digits += 1;
+                    if digits > MAX_RUN_DIGITS {
+                        return Err(corrupted("zero run is too long"));
+                    }
+                    run += weight * (*symbol as u64 + 1);
+                    weight <<= 1;
+                }
+                symbol => {
+                    // the byte chained at the end is the one allowed past the block
+                    if run > block_length.saturating_sub(data.len()) as u64 {
+                        return Err(corrupted("zero run goes past the end of the block"));
+                    }
+                    data.extend(std::iter::repeat_n(0u8, run as usize));
+                    (run, weight, digits) = (0, 1, 0);
+
+                    let byte = u8::try_from(symbol - 1)
+                        .map_err(|_| corrupted("zero run symbol is out of range"))?;
+                    data.push(byte);
+                    if data.len() > block_length + 1 {
+                        return Err(corrupted("zero run symbols go past the end of the block"));
+                    }
+                }
+            }
+        }
+
+        // drop the byte of the symbol chained above to flush the last run
+        data.pop();
+        if data.len() != block_length {
+            return Err(corrupted("zero run symbols do not fill the block"));
+        }
+        Ok(data)
+    }
+}
+
+impl Codec for BlockSort {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let transformed = self.bwt.encode(data)?;
+        let ranks = self.mtf.encode(&transformed)?;
+        let symbols = Self::encode_zero_runs(&ranks);
+
+        let mut encoded = Vec::new();
+        write_varint(&mut encoded, ranks.len() as u64);
+        encoded.extend(self.huffman.encode_symbols(&symbols, SYMBOL_BITS)?);
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let block_length = cursor.read_varint()? as usize;
+
+        // the block is the BWT payload, the data after the varint of its index, and every
+        // symbol stands for at least one of its bytes
+        check_decoded_size(block_length, max_size.saturating_add(MAX_VARINT_SIZE))?;
+        let symbols = self
+            .huffman
+            .decode_symbols(cursor.remaining(), SYMBOL_BITS, block_length)?;
+        let ranks = Self::decode_zero_runs(&symbols, block_length)?;
+        let transformed = self.mtf.decode_limited(&ranks, block_length)?;
+        self.bwt.decode_limited(&transformed, max_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::binary_samples;
+
+    #[test]
+    fn zero_runs_work() {
+        let test_cases: Vec<(Vec<u8>, Vec<u16>)> = vec![
+            (vec![0], vec![RUNA]),
+            (vec![0, 0], vec![RUNB]),
+            (vec![0, 0, 0], vec![RUNA, RUNA]),
+            (vec![0, 0, 0, 0], vec![RUNB, RUNA]),
+            (vec![5, 0, 0, 0, 0, 0, 255], vec![6, RUNA, RUNB, 256]),
+            (vec![], vec![]),
+        ];
+
+        for (data, symbols) in test_cases {
+            assert_eq!(BlockSort::encode_zero_runs(&data), symbols);
+            let decoded = BlockSort::decode_zero_runs(&symbols, data.len()).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        let long_run = vec![0u8; 100_000];
+        let symbols = BlockSort::encode_zero_runs(&long_run);
+        assert_eq!(symbols.len(), 16);
+        let decoded = BlockSort::decode_zero_runs(&symbols, long_run.len()).unwrap();
+        assert_eq!(decoded, long_run);
+    }
+
+    #[test]
+    fn block_sort_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"banana".to_vec(),
+            b"".to_vec(),
+            b"costs $5".to_vec(),
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(200)
+                .into_bytes(),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = BlockSort::new().encode(&test_case).unwrap();
+            let decoded = BlockSort::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn repetitive_text_is_compressed() {
+        let text = "TOBEORNOTTOBEORTOBEORNOT abracadabra! ".repeat(200);
+        let encoded = BlockSort::new().encode(text.as_bytes()).unwrap();
+        assert!(encoded.len() < text.len() / 20);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut block_sort = BlockSort::new();
+        let too_many_digits = [RUNA; MAX_RUN_DIGITS + 1];
+        assert!(BlockSort::decode_zero_runs(&too_many_digits, usize::MAX).is_err());
+        assert!(BlockSort::decode_zero_runs(&[300], 1).is_err());
+        assert!(block_sort.decode(&[0xff]).is_err());
+
+        // a run of 2^32 - 1 zeros in a block of 16 bytes, and symbols past the block
+        let long_run = [RUNA; MAX_RUN_DIGITS];
+        let err = BlockSort::decode_zero_runs(&long_run, 16).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+        assert!(BlockSort::decode_zero_runs(&[RUNB, 5, 6], 3).is_err());
+        assert!(BlockSort::decode_zero_runs(&[RUNB, 5], 4).is_err());
+
+        let symbols = Huffman::new()
+            .encode_symbols(&long_run, SYMBOL_BITS)
+            .unwrap();
+        let mut encoded = vec![16];
+        encoded.extend(symbols);
+        let err = block_sort.decode(&encoded).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+    }
+}
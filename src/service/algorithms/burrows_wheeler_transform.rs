@@ -1,150 +1,226 @@
-pub mod quadratic_log {
-    use std::collections::VecDeque;
+//! The transform sorts the rotations of the text followed by a virtual sentinel,
+//! a symbol smaller than every byte that is never written out. The payload holds
+//! the row of the original text, which is also where the sentinel would sit in
+//! the last column, followed by the last column without the sentinel. Any byte
+//! value can therefore be transformed.
 
-    use crate::service::pkg::traits::Codec;
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::data_structures::byte_cursor::{write_varint, ByteCursor};
+use crate::service::pkg::traits::check_decoded_size;
 
-    /// Rotation represents a rotation of a string at an index
-    #[derive(Debug)]
-    struct Rotation(usize, String);
+/// encode_with takes the last column of the rotations in the order given by sort_suffixes.
+/// The payload starts with the row of the original text, which is returned along with it.
+fn encode_with(
+    data: &[u8],
+    sort_suffixes: fn(&[u8]) -> Vec<usize>,
+) -> Result<(usize, Vec<u8>), CodecError> {
+    let mut encoded = Vec::with_capacity(data.len() + 8);
+    if data.is_empty() {
+        write_varint(&mut encoded, 0);
+        return Ok((0, encoded));
+    }
 
-    impl Rotation {
-        fn new(i: usize, s: String) -> Self {
-            Rotation(i, s)
-        }
+    // the sentinel sorts first, so the suffixes of the text follow the row of the sentinel
+    let suffix_indices = sort_suffixes(data);
+
+    // the rotation starting at index 0 is the original text, and its last symbol is the sentinel
+    let original_pos = 1 + suffix_indices.iter().position(|idx| *idx == 0).unwrap();
+    write_varint(&mut encoded, original_pos as u64);
+
+    // the row of the sentinel ends with the last byte of the text
+    encoded.push(data[data.len() - 1]);
+
+    // for each index in the stored suffixes, take the byte preceding it and skip the sentinel
+    encoded.extend(
+        suffix_indices
+            .into_iter()
+            .filter(|idx| *idx != 0)
+            .map(|idx| data[idx - 1]),
+    );
+    Ok((original_pos, encoded))
+}
+
+/// decode reads the row of the original text from the payload and inverts the last column,
+/// which holds as many bytes as the original text
+fn decode(data: &[u8], max_size: usize) -> Result<(usize, Vec<u8>), CodecError> {
+    let mut cursor = ByteCursor::new(data);
+    let original_pos = cursor.read_varint()? as usize;
+    check_decoded_size(cursor.remaining().len(), max_size)?;
+    Ok((
+        original_pos,
+        invert_transform(original_pos, cursor.remaining())?,
+    ))
+}
+
+fn invert_transform(original_pos: usize, encoded: &[u8]) -> Result<Vec<u8>, CodecError> {
+    let encoded_len = encoded.len();
+    let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+    if encoded_len == 0 {
+        return match original_pos {
+            0 => Ok(Vec::new()),
+            _ => Err(corrupted(
+                "bwt original position is outside the encoded data",
+            )),
+        };
+    }
+    // the row of the sentinel comes first, so the original text is in one of the rows after it
+    if original_pos == 0 || original_pos > encoded_len {
+        return Err(corrupted(
+            "bwt original position is outside the encoded data",
+        ));
     }
 
-    /// BurrowsWheelerTransform represents a struct for the BWT compression algorithm
-    #[derive(Clone)]
-    pub struct BurrowsWheelerTransform {
-        text: String,
-        suffix_indices: Vec<usize>,
-        encoded: String,
-        original_pos: usize,
-        decoded: String,
+    // the last column with the sentinel has one more row, where the sentinel sits at original_pos
+    let last_column = |row: usize| match row.cmp(&original_pos) {
+        std::cmp::Ordering::Less => Some(encoded[row]),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(encoded[row - 1]),
+    };
+
+    // count the occurrences of each byte to find where its rows start in the sorted column,
+    // after the first row which holds the sentinel
+    let mut starts = [0usize; 256];
+    for byte in encoded {
+        starts[*byte as usize] += 1;
+    }
+    let mut total = 1usize;
+    for start in starts.iter_mut() {
+        let count = *start;
+        *start = total;
+        total += count;
     }
 
-    impl BurrowsWheelerTransform {
-        pub fn new(mut text: String) -> Self {
-            for ch in text.chars() {
-                if ch == '$' {
-                    panic!("The text must not contain the '$' character");
-                }
-            }
-            text.push('$');
-
-            let n = text.len();
-            Self {
-                text,
-                suffix_indices: Vec::with_capacity(n),
-                encoded: String::new(),
-                original_pos: 0,
-                decoded: String::new(),
-            }
+    // map every row of the sorted column to the row holding the same symbol occurrence in the last column
+    let mut left_shifts: Vec<usize> = vec![0; encoded_len + 1];
+    left_shifts[0] = original_pos;
+    for row in 0..=encoded_len {
+        if let Some(byte) = last_column(row) {
+            left_shifts[starts[byte as usize]] = row;
+            starts[byte as usize] += 1;
         }
+    }
 
-        /// populate_suffix_vector gets a suffix array of rotations from the input string
-        fn populate_suffix_vector(&mut self) {
-            let text_length = self.text.len();
-            let mut rotations: Vec<Rotation> = Vec::with_capacity(text_length);
+    let mut curr_start_pos = original_pos;
+    let mut decoded = Vec::with_capacity(encoded_len);
+    for _ in 0..encoded_len {
+        curr_start_pos = left_shifts[curr_start_pos];
+        // reaching the sentinel before every byte is decoded means the rows form a shorter cycle
+        let byte = last_column(curr_start_pos)
+            .ok_or_else(|| corrupted("bwt data reached the sentinel too early"))?;
+        decoded.push(byte);
+    }
+    Ok(decoded)
+}
 
-            // for each character and index i in the input string, rotate it at i
-            self.text.chars().enumerate().for_each(|(i, _)| {
-                rotations.push(Rotation::new(i, (&self.text[i..]).to_string()));
-            });
+pub mod quadratic_log {
+    use crate::errors::codec_error::CodecError;
+    use crate::service::pkg::traits::Codec;
 
-            // sort the rotations by the suffix strings
-            rotations.sort_by(|a, b| a.1.cmp(&b.1));
+    /// BurrowsWheelerTransform represents a struct for the BWT compression algorithm.
+    /// The compressed payload starts with the row of the original text, followed by the last column.
+    #[derive(Clone, Default)]
+    pub struct BurrowsWheelerTransform {
+        original_pos: usize,
+    }
 
-            rotations.iter().enumerate().for_each(|(i, r)| {
-                if r.1 == self.text {
-                    self.original_pos = i;
-                }
-            });
+    impl BurrowsWheelerTransform {
+        pub fn new() -> Self {
+            Self { original_pos: 0 }
+        }
 
-            // get the indices of the sorted rotations in their order and set the suffixes field
-            rotations.iter().for_each(|r| self.suffix_indices.push(r.0));
+        /// suffix_vector gets the starting indices of the sorted suffixes of the text
+        /// by comparing the suffixes themselves, which takes O(n² log n) time in the worst case.
+        /// A suffix sorts before the longer ones it is a prefix of, as if followed by the sentinel
+        fn suffix_vector(text: &[u8]) -> Vec<usize> {
+            let mut suffix_indices = (0..text.len()).collect::<Vec<usize>>();
+            suffix_indices.sort_by(|a, b| text[*a..].cmp(&text[*b..]));
+            suffix_indices
         }
+    }
 
-        fn transform(&mut self) {
-            let text_characters = self.text.chars().collect::<Vec<char>>();
-            let text_length = self.text.len();
-
-            // for each index in the stored suffixes
-            self.suffix_indices.iter().for_each(|idx| {
-                let ch_idx = match *idx {
-                    0 => text_length - 1, // wrap the text at the last position to prevent underflow
-                    _ => *idx - 1,        // get the previous position
-                };
-                // add the character at that index to the encoded
-                self.encoded.push(text_characters[ch_idx]);
-            });
+    impl Codec for BurrowsWheelerTransform {
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+            let (original_pos, encoded) = super::encode_with(data, Self::suffix_vector)?;
+            self.original_pos = original_pos;
+            Ok(encoded)
         }
 
-        fn invert_transform(&mut self) {
-            let encoded_vector = self.encoded.chars().collect::<Vec<char>>();
-            let mut sorted_encoded_vector = encoded_vector.clone();
-            sorted_encoded_vector.sort();
-            let encoded_len = encoded_vector.len();
-            let mut left_shifts: Vec<usize> = vec![0; encoded_len];
-            let mut curr_start_pos = self.original_pos;
-
-            let mut int_arr: Vec<VecDeque<usize>> = vec![VecDeque::new(); 128];
-            for i in 0..int_arr.len() {
-                int_arr[i] = VecDeque::new();
-            }
-
-            // use the int_arr as a map of character to a queue of the character's
-            // occurrences in the encoded vector in order of magnitude
-            for i in 0..encoded_len {
-                int_arr[(encoded_vector[i] as u8) as usize].push_back(i);
-            }
-
-            for i in 0..encoded_len {
-                let queue_idx = (sorted_encoded_vector[i] as u8) as usize;
-                left_shifts[i] = int_arr[queue_idx].pop_front().unwrap();
-            }
-
-            let mut decoded: Vec<char> = vec!['\0'; encoded_len];
-            for i in 0..encoded_len {
-                curr_start_pos = left_shifts[curr_start_pos];
-                decoded[encoded_len - 1 - i] = encoded_vector[curr_start_pos];
-            }
-
-            self.decoded = decoded.iter().rev().collect::<String>();
-            // pop the '$' we added
-            self.decoded.pop();
+        fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+            let (original_pos, decoded) = super::decode(data, max_size)?;
+            self.original_pos = original_pos;
+            Ok(decoded)
         }
     }
+}
 
-    impl Codec for BurrowsWheelerTransform {
-        fn encode(&mut self) {
-            if self.text.is_empty() {
-                return;
-            }
-            self.populate_suffix_vector();
-            self.transform();
-        }
+pub mod sa_is {
+    use crate::errors::codec_error::CodecError;
+    use crate::service::data_structures::suffix_array::suffix_array;
+    use crate::service::pkg::traits::Codec;
+
+    /// BurrowsWheelerTransform produces the same payload as quadratic_log::BurrowsWheelerTransform,
+    /// but sorts the suffixes with an SA-IS suffix array in linear time and memory
+    #[derive(Clone, Default)]
+    pub struct BurrowsWheelerTransform {
+        original_pos: usize,
+    }
 
-        fn decode(&mut self) {
-            self.invert_transform();
+    impl BurrowsWheelerTransform {
+        pub fn new() -> Self {
+            Self { original_pos: 0 }
         }
+    }
 
-        fn compressed(&self) -> String {
-            self.encoded.clone()
+    impl Codec for BurrowsWheelerTransform {
+        fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+            let (original_pos, encoded) = super::encode_with(data, suffix_array)?;
+            self.original_pos = original_pos;
+            Ok(encoded)
         }
 
-        fn decompressed(&self) -> String {
-            self.decoded.clone()
+        fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+            let (original_pos, decoded) = super::decode(data, max_size)?;
+            self.original_pos = original_pos;
+            Ok(decoded)
         }
     }
 }
 
-
 #[cfg(test)]
 mod test {
     use crate::service::pkg::traits::Codec;
 
     use super::quadratic_log::BurrowsWheelerTransform;
+    use super::sa_is::BurrowsWheelerTransform as SaIsBurrowsWheelerTransform;
+    use crate::utils::test_utils::binary_samples;
+
+    #[test]
+    fn transform_works() {
+        let mut bwt = BurrowsWheelerTransform::new();
+        // the header holds the row of the original text before the last column
+        assert_eq!(bwt.encode(b"banana").unwrap(), b"\x04annbaa".to_vec());
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut bwt = BurrowsWheelerTransform::new();
+        assert!(bwt.decode(b"\x09annbaa").is_err());
+        assert!(bwt.decode(b"\x00annbaa").is_err());
+        assert!(bwt.decode(b"").is_err());
+        // a last column whose rows cycle back to the sentinel before the end
+        assert!(bwt.decode(b"\x01aa").is_err());
+    }
+
+    #[test]
+    fn any_byte_is_accepted() {
+        let test_cases: Vec<&[u8]> = vec![b"costs $5", b"$$$", b"$", b"a$b$c", b"\x00\xff\x00"];
+
+        for test_case in test_cases {
+            let encoded = BurrowsWheelerTransform::new().encode(test_case).unwrap();
+            let decoded = BurrowsWheelerTransform::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
 
     #[test]
     fn compression_works() {
@@ -173,11 +249,48 @@ mod test {
        : 
        5");
 
-       let mut bwt = BurrowsWheelerTransform::new(text.clone());
+        let encoded = BurrowsWheelerTransform::new()
+            .encode(text.as_bytes())
+            .unwrap();
+        // a fresh instance reads the original row from the payload
+        let decoded = BurrowsWheelerTransform::new().decode(&encoded).unwrap();
 
-       bwt.encode();
-       bwt.decode();
+        assert_eq!(decoded, text.as_bytes());
+    }
 
-       assert_eq!(bwt.decompressed(), text);
+    #[test]
+    fn binary_data_works() {
+        for sample in binary_samples() {
+            let encoded = BurrowsWheelerTransform::new().encode(&sample).unwrap();
+            let decoded = BurrowsWheelerTransform::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, sample);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn backends_produce_identical_output() {
+        let mut test_cases = vec![
+            b"banana".to_vec(),
+            b"abracadabra!".to_vec(),
+            b"mississippi".to_vec(),
+            b"aaaaaaaaaa".to_vec(),
+            b"".to_vec(),
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+        ];
+        test_cases.extend(binary_samples());
+
+        for test_case in test_cases {
+            let encoded = BurrowsWheelerTransform::new().encode(&test_case).unwrap();
+            let sa_is_encoded = SaIsBurrowsWheelerTransform::new()
+                .encode(&test_case)
+                .unwrap();
+            assert_eq!(sa_is_encoded, encoded);
+
+            // either backend decodes the payload of the other
+            let decoded = SaIsBurrowsWheelerTransform::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+}
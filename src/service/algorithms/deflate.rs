@@ -0,0 +1,825 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::{
+        bit_stream::{BitReader, BitWriter},
+        canonical_code::{CanonicalDecoder, CanonicalEncoder},
+        hash_chain::HashChain,
+        package_merge::limited_code_lengths,
+    },
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// WINDOW_SIZE is the farthest distance a match can reach back
+const WINDOW_SIZE: usize = 1 << 15;
+
+/// MIN_MATCH and MAX_MATCH bound the length of a match
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// MAX_CHAIN is the number of candidates the match finder tries at every position
+const MAX_CHAIN: usize = 128;
+
+/// BLOCK_TOKENS is the number of tokens sharing the codes of a block
+const BLOCK_TOKENS: usize = 1 << 14;
+
+/// MAX_STORED is the largest number of bytes held by a stored block
+const MAX_STORED: usize = u16::MAX as usize;
+
+/// END_OF_BLOCK is the literal/length symbol ending every compressed block
+const END_OF_BLOCK: u16 = 256;
+
+/// LITERAL_CODES and DISTANCE_CODES are the number of symbols a dynamic block can use
+const LITERAL_CODES: usize = 286;
+const DISTANCE_CODES: usize = 30;
+
+/// MAX_CODE_LENGTH bounds the codes of the literals, lengths and distances, and
+/// MAX_CODE_LENGTH_CODE the codes of the code lengths of a dynamic header
+const MAX_CODE_LENGTH: u8 = 15;
+const MAX_CODE_LENGTH_CODE: u8 = 7;
+
+/// the smallest length of every length symbol from 257 and its number of extra bits
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// the smallest distance of every distance symbol and its number of extra bits
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// CODE_LENGTH_ORDER is the order in which a dynamic header stores the code length code,
+/// the lengths most likely to be 0 coming last so they can be left out
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Token is a literal byte or a back-reference to an earlier occurrence of length bytes
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+impl Token {
+    /// len returns the number of bytes covered by the token
+    fn len(&self) -> usize {
+        match self {
+            Token::Literal(_) => 1,
+            Token::Match { length, .. } => *length as usize,
+        }
+    }
+}
+
+/// BlockType is the kind of a block, stored as its 2 bit BTYPE
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BlockType {
+    Stored = 0,
+    Fixed = 1,
+    Dynamic = 2,
+}
+
+/// DynamicHeader holds the code lengths of a dynamic block as they are written: the lengths
+/// of both codes run-length encoded with symbols 16 to 18, and coded with a third code
+struct DynamicHeader {
+    literal_count: usize,
+    distance_count: usize,
+    code_length_count: usize,
+    code_length_lengths: Vec<u8>,
+    // every code length symbol with the value of its extra bits
+    symbols: Vec<(u8, u8)>,
+}
+
+impl DynamicHeader {
+    /// new run-length encodes the lengths and builds the code length code
+    fn new(literal_lengths: &[u8], distance_lengths: &[u8]) -> Self {
+        // the trailing unused symbols are left out, down to the smallest counts allowed
+        let used = |lengths: &[u8], min: usize| {
+            lengths
+                .iter()
+                .rposition(|length| *length > 0)
+                .map_or(min, |last| (last + 1).max(min))
+        };
+        let literal_count = used(literal_lengths, 257);
+        let distance_count = used(distance_lengths, 1);
+
+        // the runs may cross from the literal lengths into the distance lengths
+        let lengths = [
+            &literal_lengths[..literal_count],
+            &distance_lengths[..distance_count],
+        ]
+        .concat();
+        let mut symbols = Vec::new();
+        let mut i = 0;
+        while i < lengths.len() {
+            let length = lengths[i];
+            let run = lengths[i..].iter().take_while(|l| **l == length).count();
+            match (length, run) {
+                (0, 11..) => {
+                    let run = run.min(138);
+                    symbols.push((18, (run - 11) as u8));
+                    i += run;
+                }
+                (0, 3..) => {
+                    symbols.push((17, (run - 3) as u8));
+                    i += run;
+                }
+                (_, 4..) => {
+                    // the first length is written as is, and repeated 3 to 6 times
+                    let repeat = (run - 1).min(6);
+                    symbols.push((length, 0));
+                    symbols.push((16, (repeat - 3) as u8));
+                    i += 1 + repeat;
+                }
+                _ => {
+                    symbols.push((length, 0));
+                    i += 1;
+                }
+            }
+        }
+
+        // inflaters reject an incomplete code length code, so it always gets two symbols
+        let mut frequencies = vec![0u64; CODE_LENGTH_ORDER.len()];
+        for (symbol, _) in symbols.iter() {
+            frequencies[*symbol as usize] += 1;
+        }
+        if frequencies
+            .iter()
+            .filter(|frequency| **frequency > 0)
+            .count()
+            < 2
+        {
+            let unused = frequencies.iter().position(|frequency| *frequency == 0);
+            frequencies[unused.unwrap()] = 1;
+        }
+        let code_length_lengths = limited_code_lengths(&frequencies, MAX_CODE_LENGTH_CODE).unwrap();
+        let code_length_count = CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|symbol| code_length_lengths[*symbol] > 0)
+            .map_or(4, |last| (last + 1).max(4));
+
+        Self {
+            literal_count,
+            distance_count,
+            code_length_count,
+            code_length_lengths,
+            symbols,
+        }
+    }
+
+    /// extra_bits returns the number of extra bits following a code length symbol
+    fn extra_bits(symbol: u8) -> u32 {
+        match symbol {
+            16 => 2,
+            17 => 3,
+            18 => 7,
+            _ => 0,
+        }
+    }
+
+    /// cost returns the size of the header in bits
+    fn cost(&self) -> u64 {
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|(symbol, _)| {
+                self.code_length_lengths[*symbol as usize] as u64 + Self::extra_bits(*symbol) as u64
+            })
+            .sum::<u64>();
+        5 + 5 + 4 + 3 * self.code_length_count as u64 + symbols
+    }
+
+    /// write writes the header after the block type
+    fn write(&self, writer: &mut BitWriter) {
+        writer.write_bits(self.literal_count as u64 - 257, 5);
+        writer.write_bits(self.distance_count as u64 - 1, 5);
+        writer.write_bits(self.code_length_count as u64 - 4, 4);
+        for symbol in CODE_LENGTH_ORDER.iter().take(self.code_length_count) {
+            writer.write_bits(self.code_length_lengths[*symbol] as u64, 3);
+        }
+
+        let encoder = CanonicalEncoder::new(&self.code_length_lengths);
+        for (symbol, extra) in self.symbols.iter() {
+            encoder.write_symbol(writer, *symbol as u16);
+            writer.write_bits(*extra as u64, Self::extra_bits(*symbol));
+        }
+    }
+
+    /// read reads the header of a dynamic block and returns the code lengths of the
+    /// literals and lengths, and of the distances
+    fn read(reader: &mut BitReader) -> Result<(Vec<u8>, Vec<u8>), CodecError> {
+        let literal_count = reader.read_bits(5).ok_or_else(truncated)? as usize + 257;
+        let distance_count = reader.read_bits(5).ok_or_else(truncated)? as usize + 1;
+        let code_length_count = reader.read_bits(4).ok_or_else(truncated)? as usize + 4;
+        if literal_count > LITERAL_CODES || distance_count > DISTANCE_CODES {
+            return Err(corrupted("deflate header has too many codes"));
+        }
+
+        let mut code_length_lengths = vec![0u8; CODE_LENGTH_ORDER.len()];
+        for symbol in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+            code_length_lengths[*symbol] = reader.read_bits(3).ok_or_else(truncated)? as u8;
+        }
+        let decoder = CanonicalDecoder::new(&code_length_lengths)?;
+
+        let total = literal_count + distance_count;
+        let mut lengths = Vec::with_capacity(total);
+        while lengths.len() < total {
+            let symbol = decoder.read_symbol(reader).ok_or_else(invalid_code)?;
+            let (length, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => match lengths.last() {
+                    Some(previous) => (*previous, 3 + reader.read_bits(2).ok_or_else(truncated)?),
+                    None => return Err(corrupted("deflate header repeats a missing length")),
+                },
+                17 => (0, 3 + reader.read_bits(3).ok_or_else(truncated)?),
+                _ => (0, 11 + reader.read_bits(7).ok_or_else(truncated)?),
+            };
+            if lengths.len() + repeat as usize > total {
+                return Err(corrupted("deflate header has too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(length, repeat as usize));
+        }
+
+        if lengths[END_OF_BLOCK as usize] == 0 {
+            return Err(corrupted("deflate block has no end of block code"));
+        }
+        let distance_lengths = lengths.split_off(literal_count);
+        Ok((lengths, distance_lengths))
+    }
+}
+
+/// corrupted returns the error of a stream that is not valid DEFLATE
+fn corrupted(message: &str) -> CodecError {
+    CodecError::new(message, CodecErrorKind::CorruptedData)
+}
+
+/// truncated returns the error of a stream ending before its last block does
+fn truncated() -> CodecError {
+    corrupted("deflate data ended before the last block")
+}
+
+/// invalid_code returns the error of bits that are not a code of the block
+fn invalid_code() -> CodecError {
+    corrupted("deflate code is not valid")
+}
+
+/// Deflate represents the DEFLATE format of RFC 1951, used by gzip, zlib and zip.
+/// The data is turned into literals and matches of 3 to 258 bytes reaching up to 32 KiB back
+/// with a hash chain, and the tokens are Huffman coded in blocks. Every block is written
+/// stored, with the fixed codes of the RFC, or with its own codes limited to 15 bits,
+/// whichever is smallest.
+///
+/// The payload is a raw DEFLATE stream, with neither a header nor a checksum, so it can be
+/// read by any inflater.
+#[derive(Default)]
+pub struct Deflate {}
+
+impl Deflate {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// tokens finds the matches of the data. A match is deferred by a byte when a longer one
+    /// starts at the next byte, which is the lazy matching of zlib
+    fn tokens(data: &[u8]) -> Vec<Token> {
+        let mut chain = HashChain::new(WINDOW_SIZE, MAX_CHAIN);
+        let find = |chain: &HashChain, position: usize| {
+            chain
+                .longest_match(data, position, MAX_MATCH)
+                .filter(|(_, length)| *length >= MIN_MATCH)
+        };
+
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        while position < data.len() {
+            let found = find(&chain, position);
+            chain.insert(data, position);
+
+            let token = match found {
+                Some((distance, length))
+                    if find(&chain, position + 1).is_none_or(|(_, next)| next <= length) =>
+                {
+                    Token::Match {
+                        length: length as u16,
+                        distance: distance as u16,
+                    }
+                }
+                _ => Token::Literal(data[position]),
+            };
+
+            // every covered position can start a later match
+            for covered in position + 1..position + token.len() {
+                chain.insert(data, covered);
+            }
+            position += token.len();
+            tokens.push(token);
+        }
+
+        tokens
+    }
+
+    /// length_symbol returns the index of the length symbol of a match length
+    fn length_symbol(length: u16) -> usize {
+        LENGTH_BASES.partition_point(|base| *base <= length) - 1
+    }
+
+    /// distance_symbol returns the distance symbol of a match distance
+    fn distance_symbol(distance: u16) -> usize {
+        DISTANCE_BASES.partition_point(|base| *base <= distance) - 1
+    }
+
+    /// fixed_lengths returns the code lengths of the fixed codes, for the literals and
+    /// lengths and for the distances
+    fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+        let literal_lengths = (0..288)
+            .map(|symbol| match symbol {
+                0..=143 => 8,
+                144..=255 => 9,
+                256..=279 => 7,
+                _ => 8,
+            })
+            .collect();
+        (literal_lengths, vec![5; DISTANCE_CODES])
+    }
+
+    /// frequencies counts the literal and length symbols and the distance symbols of the tokens,
+    /// the end of block code included
+    fn frequencies(tokens: &[Token]) -> (Vec<u64>, Vec<u64>) {
+        let mut literals = vec![0u64; LITERAL_CODES];
+        let mut distances = vec![0u64; DISTANCE_CODES];
+        for token in tokens {
+            match token {
+                Token::Literal(byte) => literals[*byte as usize] += 1,
+                Token::Match { length, distance } => {
+                    literals[257 + Self::length_symbol(*length)] += 1;
+                    distances[Self::distance_symbol(*distance)] += 1;
+                }
+            }
+        }
+        literals[END_OF_BLOCK as usize] += 1;
+        (literals, distances)
+    }
+
+    /// tokens_cost returns the size in bits of the tokens and the end of block code
+    fn tokens_cost(
+        frequencies: &(Vec<u64>, Vec<u64>),
+        literal_lengths: &[u8],
+        distance_lengths: &[u8],
+    ) -> u64 {
+        let (literals, distances) = frequencies;
+        let literal_bits = literals
+            .iter()
+            .enumerate()
+            .map(|(symbol, frequency)| {
+                let extra = symbol
+                    .checked_sub(257)
+                    .map_or(0, |index| LENGTH_EXTRA_BITS[index]);
+                frequency * (literal_lengths[symbol] + extra) as u64
+            })
+            .sum::<u64>();
+        let distance_bits = distances
+            .iter()
+            .enumerate()
+            .map(|(symbol, frequency)| {
+                frequency * (distance_lengths[symbol] + DISTANCE_EXTRA_BITS[symbol]) as u64
+            })
+            .sum::<u64>();
+        literal_bits + distance_bits
+    }
+
+    /// write_block writes the tokens covering bytes as the smallest kind of block
+    fn write_block(writer: &mut BitWriter, tokens: &[Token], bytes: &[u8], last: bool) {
+        let frequencies = Self::frequencies(tokens);
+
+        // a block without matches still needs a distance code
+        let literal_lengths = limited_code_lengths(&frequencies.0, MAX_CODE_LENGTH).unwrap();
+        let mut distance_lengths = limited_code_lengths(&frequencies.1, MAX_CODE_LENGTH).unwrap();
+        if distance_lengths.iter().all(|length| *length == 0) {
+            distance_lengths[0] = 1;
+        }
+        let header = DynamicHeader::new(&literal_lengths, &distance_lengths);
+        let (fixed_literal_lengths, fixed_distance_lengths) = Self::fixed_lengths();
+
+        // every stored block takes 3 bits, up to 7 bits of padding and 4 bytes of lengths
+        let n_stored = bytes.len().div_ceil(MAX_STORED).max(1);
+        let costs = [
+            (
+                BlockType::Stored,
+                8 * bytes.len() as u64 + n_stored as u64 * (3 + 7 + 32),
+            ),
+            (
+                BlockType::Fixed,
+                3 + Self::tokens_cost(
+                    &frequencies,
+                    &fixed_literal_lengths,
+                    &fixed_distance_lengths,
+                ),
+            ),
+            (
+                BlockType::Dynamic,
+                3 + header.cost()
+                    + Self::tokens_cost(&frequencies, &literal_lengths, &distance_lengths),
+            ),
+        ];
+        let block_type = costs.iter().min_by_key(|(_, cost)| *cost).unwrap().0;
+
+        match block_type {
+            BlockType::Stored => {
+                let chunks = bytes.chunks(MAX_STORED).collect::<Vec<&[u8]>>();
+                let chunks = if chunks.is_empty() {
+                    vec![bytes]
+                } else {
+                    chunks
+                };
+                for (i, chunk) in chunks.iter().enumerate() {
+                    writer.write_bit(last && i == chunks.len() - 1);
+                    writer.write_bits(BlockType::Stored as u64, 2);
+                    writer.align_to_byte();
+                    writer.write_bits(chunk.len() as u64, 16);
+                    writer.write_bits(!chunk.len() as u64 & 0xffff, 16);
+                    writer.write_bytes(chunk);
+                }
+            }
+            BlockType::Fixed => {
+                writer.write_bit(last);
+                writer.write_bits(BlockType::Fixed as u64, 2);
+                Self::write_tokens(
+                    writer,
+                    tokens,
+                    &CanonicalEncoder::new(&fixed_literal_lengths),
+                    &CanonicalEncoder::new(&fixed_distance_lengths),
+                );
+            }
+            BlockType::Dynamic => {
+                writer.write_bit(last);
+                writer.write_bits(BlockType::Dynamic as u64, 2);
+                header.write(writer);
+                Self::write_tokens(
+                    writer,
+                    tokens,
+                    &CanonicalEncoder::new(&literal_lengths),
+                    &CanonicalEncoder::new(&distance_lengths),
+                );
+            }
+        }
+    }
+
+    /// write_tokens writes the codes and extra bits of the tokens, followed by the end of block code
+    fn write_tokens(
+        writer: &mut BitWriter,
+        tokens: &[Token],
+        literals: &CanonicalEncoder,
+        distances: &CanonicalEncoder,
+    ) {
+        for token in tokens {
+            match *token {
+                Token::Literal(byte) => literals.write_symbol(writer, byte as u16),
+                Token::Match { length, distance } => {
+                    let index = Self::length_symbol(length);
+                    literals.write_symbol(writer, 257 + index as u16);
+                    writer.write_bits(
+                        (length - LENGTH_BASES[index]) as u64,
+                        LENGTH_EXTRA_BITS[index] as u32,
+                    );
+
+                    let symbol = Self::distance_symbol(distance);
+                    distances.write_symbol(writer, symbol as u16);
+                    writer.write_bits(
+                        (distance - DISTANCE_BASES[symbol]) as u64,
+                        DISTANCE_EXTRA_BITS[symbol] as u32,
+                    );
+                }
+            }
+        }
+        literals.write_symbol(writer, END_OF_BLOCK);
+    }
+
+    /// inflate decodes the blocks of a DEFLATE stream of at most max_size bytes up to its last
+    /// block, leaving the reader after it, so formats wrapping the stream can read what follows
+    pub fn inflate(reader: &mut BitReader, max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut decoded = Vec::new();
+        loop {
+            let last = reader.read_bit().ok_or_else(truncated)?;
+            match reader.read_bits(2).ok_or_else(truncated)? {
+                0 => {
+                    reader.align_to_byte();
+                    let length = reader.read_bits(16).ok_or_else(truncated)?;
+                    let complement = reader.read_bits(16).ok_or_else(truncated)?;
+                    if length != !complement & 0xffff {
+                        return Err(corrupted("deflate stored block length is corrupted"));
+                    }
+                    check_decoded_size(decoded.len() + length as usize, max_size)?;
+                    decoded.extend_from_slice(
+                        reader.read_bytes(length as usize).ok_or_else(truncated)?,
+                    );
+                }
+                block_type @ (1 | 2) => {
+                    let (literal_lengths, distance_lengths) = match block_type {
+                        1 => Self::fixed_lengths(),
+                        _ => DynamicHeader::read(reader)?,
+                    };
+                    Self::inflate_block(
+                        reader,
+                        &CanonicalDecoder::new(&literal_lengths)?,
+                        &CanonicalDecoder::new(&distance_lengths)?,
+                        &mut decoded,
+                        max_size,
+                    )?;
+                }
+                _ => return Err(corrupted("deflate block type is reserved")),
+            }
+
+            if last {
+                return Ok(decoded);
+            }
+        }
+    }
+
+    /// inflate_block decodes the tokens of a compressed block up to its end of block code,
+    /// failing when the data grows past max_size bytes
+    fn inflate_block(
+        reader: &mut BitReader,
+        literals: &CanonicalDecoder,
+        distances: &CanonicalDecoder,
+        decoded: &mut Vec<u8>,
+        max_size: usize,
+    ) -> Result<(), CodecError> {
+        loop {
+            let symbol = literals.read_symbol(reader).ok_or_else(invalid_code)?;
+            match symbol {
+                0..=255 => {
+                    check_decoded_size(decoded.len() + 1, max_size)?;
+                    decoded.push(symbol as u8);
+                }
+                END_OF_BLOCK => return Ok(()),
+                257..=285 => {
+                    let index = symbol as usize - 257;
+                    let extra = reader
+                        .read_bits(LENGTH_EXTRA_BITS[index] as u32)
+                        .ok_or_else(truncated)?;
+                    let length = LENGTH_BASES[index] as usize + extra as usize;
+
+                    let symbol = distances.read_symbol(reader).ok_or_else(invalid_code)? as usize;
+                    if symbol >= DISTANCE_CODES {
+                        return Err(invalid_code());
+                    }
+                    let extra = reader
+                        .read_bits(DISTANCE_EXTRA_BITS[symbol] as u32)
+                        .ok_or_else(truncated)?;
+                    let distance = DISTANCE_BASES[symbol] as usize + extra as usize;
+                    if distance > decoded.len() {
+                        return Err(corrupted("deflate match points before the data"));
+                    }
+                    check_decoded_size(decoded.len() + length, max_size)?;
+
+                    // the match may overlap the bytes it produces, so copy one byte at a time
+                    let start = decoded.len() - distance;
+                    for i in 0..length {
+                        decoded.push(decoded[start + i]);
+                    }
+                }
+                _ => return Err(invalid_code()),
+            }
+        }
+    }
+}
+
+impl Codec for Deflate {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let tokens = Self::tokens(data);
+        let mut writer = BitWriter::new();
+
+        // empty data is a single block holding only the end of block code
+        let blocks = match tokens.is_empty() {
+            true => vec![&tokens[..]],
+            false => tokens.chunks(BLOCK_TOKENS).collect(),
+        };
+        let mut start = 0;
+        for (i, block) in blocks.iter().enumerate() {
+            let end = start + block.iter().map(Token::len).sum::<usize>();
+            Self::write_block(&mut writer, block, &data[start..end], i == blocks.len() - 1);
+            start = end;
+        }
+
+        Ok(writer.finish())
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        Self::inflate(&mut BitReader::new(data), max_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::pkg::traits::MAX_DECODED_SIZE;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    /// FIXTURES are raw DEFLATE streams written by zlib, next to the data they hold
+    const FIXTURES: [(&str, &[u8], &[u8]); 6] = [
+        (
+            "empty",
+            include_bytes!("../../../test_data/deflate/empty.txt"),
+            include_bytes!("../../../test_data/deflate/empty.deflate"),
+        ),
+        (
+            "stored",
+            include_bytes!("../../../test_data/deflate/stored.txt"),
+            include_bytes!("../../../test_data/deflate/stored.deflate"),
+        ),
+        (
+            "fixed",
+            include_bytes!("../../../test_data/deflate/fixed.txt"),
+            include_bytes!("../../../test_data/deflate/fixed.deflate"),
+        ),
+        (
+            "dynamic",
+            include_bytes!("../../../test_data/deflate/dynamic.txt"),
+            include_bytes!("../../../test_data/deflate/dynamic.deflate"),
+        ),
+        (
+            "flushed",
+            include_bytes!("../../../test_data/deflate/flushed.txt"),
+            include_bytes!("../../../test_data/deflate/flushed.deflate"),
+        ),
+        (
+            "binary",
+            include_bytes!("../../../test_data/deflate/binary.bin"),
+            include_bytes!("../../../test_data/deflate/binary.deflate"),
+        ),
+    ];
+
+    /// block_types returns the type of every block of a stream
+    fn block_types(data: &[u8]) -> Vec<u64> {
+        let mut reader = BitReader::new(data);
+        let mut decoded = Vec::new();
+        let mut types = Vec::new();
+        loop {
+            let last = reader.read_bit().unwrap();
+            let block_type = reader.read_bits(2).unwrap();
+            types.push(block_type);
+            match block_type {
+                0 => {
+                    reader.align_to_byte();
+                    let length = reader.read_bits(16).unwrap();
+                    reader.read_bits(16);
+                    decoded.extend(reader.read_bytes(length as usize).unwrap());
+                }
+                _ => {
+                    let (literals, distances) = match block_type {
+                        1 => Deflate::fixed_lengths(),
+                        _ => DynamicHeader::read(&mut reader).unwrap(),
+                    };
+                    Deflate::inflate_block(
+                        &mut reader,
+                        &CanonicalDecoder::new(&literals).unwrap(),
+                        &CanonicalDecoder::new(&distances).unwrap(),
+                        &mut decoded,
+                        MAX_DECODED_SIZE,
+                    )
+                    .unwrap();
+                }
+            }
+
+            if last {
+                return types;
+            }
+        }
+    }
+
+    #[test]
+    fn zlib_streams_are_inflated() {
+        for (name, original, compressed) in FIXTURES {
+            let decoded = Deflate::new().decode(compressed).unwrap();
+            assert_eq!(decoded, original, "{}", name);
+        }
+
+        // the fixtures cover every kind of block
+        assert_eq!(block_types(FIXTURES[1].2), vec![0]);
+        assert_eq!(block_types(FIXTURES[2].2), vec![1]);
+        assert_eq!(block_types(FIXTURES[3].2), vec![2]);
+        assert!(block_types(FIXTURES[4].2).len() > 2);
+    }
+
+    #[test]
+    fn hand_written_streams_are_inflated() {
+        // a fixed block with "a" and a match of 4 bytes 1 byte back, so it overlaps itself
+        let mut writer = BitWriter::new();
+        writer.write_bits(1, 1);
+        writer.write_bits(1, 2);
+        let (literals, distances) = Deflate::fixed_lengths();
+        let (literals, distances) = (
+            CanonicalEncoder::new(&literals),
+            CanonicalEncoder::new(&distances),
+        );
+        literals.write_symbol(&mut writer, b'a' as u16);
+        literals.write_symbol(&mut writer, 258);
+        distances.write_symbol(&mut writer, 0);
+        literals.write_symbol(&mut writer, END_OF_BLOCK);
+        assert_eq!(Deflate::new().decode(&writer.finish()).unwrap(), b"aaaaa");
+
+        // a stored block that is not the last, followed by the last fixed block
+        let stream = [0, 3, 0, 0xfc, 0xff, b'a', b'b', b'c', 3, 0];
+        assert_eq!(Deflate::new().decode(&stream).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn deflate_works() {
+        let mut rng = XorShift::new(3);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"abcabcabcabcabc".to_vec(),
+            vec![0u8; 100_000],
+            rng.bytes(200_000),
+            (0..300_000)
+                .map(|_| b'a' + (rng.next_u64() % 3) as u8)
+                .collect(),
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(500)
+                .into_bytes(),
+        ]);
+        for (_, original, _) in FIXTURES {
+            test_cases.push(original.to_vec());
+        }
+
+        for test_case in test_cases {
+            let encoded = Deflate::new().encode(&test_case).unwrap();
+            let decoded = Deflate::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn smallest_block_is_chosen() {
+        // random bytes are stored, short text uses the fixed codes and long text its own codes
+        let random = XorShift::new(1).bytes(10_000);
+        let encoded = Deflate::new().encode(&random).unwrap();
+        assert_eq!(block_types(&encoded), vec![0]);
+        assert_eq!(encoded.len(), random.len() + 5);
+
+        let encoded = Deflate::new().encode(b"abracadabra").unwrap();
+        assert_eq!(block_types(&encoded), vec![1]);
+
+        let text = FIXTURES[3].1;
+        let encoded = Deflate::new().encode(text).unwrap();
+        assert_eq!(block_types(&encoded), vec![2]);
+
+        // within a few percent of zlib at its highest level
+        assert!(encoded.len() * 100 < FIXTURES[3].2.len() * 105);
+
+        assert_eq!(Deflate::new().encode(b"").unwrap(), vec![3, 0]);
+    }
+
+    #[test]
+    fn symbols_cover_every_length_and_distance() {
+        assert_eq!(Deflate::length_symbol(3), 0);
+        assert_eq!(Deflate::length_symbol(10), 7);
+        assert_eq!(Deflate::length_symbol(11), 8);
+        assert_eq!(Deflate::length_symbol(12), 8);
+        assert_eq!(Deflate::length_symbol(257), 27);
+        assert_eq!(Deflate::length_symbol(258), 28);
+        assert_eq!(Deflate::distance_symbol(1), 0);
+        assert_eq!(Deflate::distance_symbol(6), 4);
+        assert_eq!(Deflate::distance_symbol(32768), 29);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut deflate = Deflate::new();
+        assert!(deflate.decode(&[]).is_err());
+
+        // the reserved block type
+        assert!(deflate.decode(&[0b111]).is_err());
+
+        // a stored block with a wrong complement, and one longer than the data
+        assert!(deflate
+            .decode(&[1, 3, 0, 0xfc, 0xfe, b'a', b'b', b'c'])
+            .is_err());
+        assert!(deflate
+            .decode(&[1, 4, 0, 0xfb, 0xff, b'a', b'b', b'c'])
+            .is_err());
+
+        // a match before any byte was decoded
+        let mut writer = BitWriter::new();
+        writer.write_bits(1, 1);
+        writer.write_bits(1, 2);
+        let (literals, distances) = Deflate::fixed_lengths();
+        CanonicalEncoder::new(&literals).write_symbol(&mut writer, 257);
+        CanonicalEncoder::new(&distances).write_symbol(&mut writer, 0);
+        assert!(deflate.decode(&writer.finish()).is_err());
+
+        // a stream without its last block
+        let encoded = deflate.encode(b"abracadabra").unwrap();
+        assert!(deflate.decode(&encoded[..encoded.len() - 2]).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    algorithms::deflate::Deflate,
+    data_structures::{bit_stream::BitReader, byte_cursor::ByteCursor},
+    pkg::traits::Codec,
+};
+use crate::utils::checksum::crc32;
+
+/// MAGIC identifies a gzip member
+pub const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// DEFLATED is the compression method of every gzip member
+const DEFLATED: u8 = 8;
+
+/// the flags of the optional header fields, the others are reserved
+const FLAG_HEADER_CRC: u8 = 1 << 1;
+const FLAG_EXTRA: u8 = 1 << 2;
+const FLAG_NAME: u8 = 1 << 3;
+const FLAG_COMMENT: u8 = 1 << 4;
+const FLAG_RESERVED: u8 = 0xe0;
+
+/// OS_UNKNOWN is the operating system written in the header
+const OS_UNKNOWN: u8 = 255;
+
+/// is_gzip returns true if the bytes start like a gzip member
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Gzip represents the gzip format of RFC 1952, a DEFLATE stream between a header and a
+/// trailer, as read and written by `gzip` and `gunzip`. Every integer is little endian:
+///
+/// | field                   | size                      |
+/// |-------------------------|---------------------------|
+/// | magic `1f 8b`           | 2 bytes                   |
+/// | method (8, DEFLATE)     | 1 byte                    |
+/// | flags                   | 1 byte                    |
+/// | modification time       | 4 bytes                   |
+/// | extra flags, OS         | 2 bytes                   |
+/// | optional fields         | as set in the flags       |
+/// | DEFLATE stream          | up to its last block      |
+/// | CRC-32 of the data      | 4 bytes                   |
+/// | length of the data      | 4 bytes, modulo 2^32      |
+///
+/// The file name and modification time are written when set. Decoding reads every member
+/// of the data, as gunzip does for concatenated files, and keeps the header of the first.
+#[derive(Default)]
+pub struct Gzip {
+    pub filename: Option<String>,
+    pub modified: u32,
+}
+
+impl Gzip {
+    pub fn new() -> Self {
+        Self {
+            filename: None,
+            modified: 0,
+        }
+    }
+
+    /// with_header writes the file name and the modification time in seconds since the epoch
+    pub fn with_header(filename: &str, modified: u32) -> Self {
+        Self {
+            filename: Some(filename.to_string()),
+            modified,
+        }
+    }
+
+    /// read_header reads the header of a member and returns its file name and modification time
+    fn read_header(cursor: &mut ByteCursor) -> Result<(Option<String>, u32), CodecError> {
+        let start = cursor.remaining();
+        if cursor.read_bytes(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(CodecError::new(
+                "data is not a gzip member",
+                CodecErrorKind::InvalidMagic,
+            ));
+        }
+        if cursor.read_u8()? != DEFLATED {
+            return Err(CodecError::new(
+                "gzip compression method is not deflate",
+                CodecErrorKind::UnknownAlgorithm,
+            ));
+        }
+        let flags = cursor.read_u8()?;
+        if flags & FLAG_RESERVED != 0 {
+            return Err(corrupted("gzip header has reserved flags set"));
+        }
+        let modified = cursor.read_u32_le()?;
+
+        // the extra flags and the operating system only describe how the member was written
+        cursor.read_bytes(2)?;
+
+        if flags & FLAG_EXTRA != 0 {
+            let length = cursor.read_u16_le()?;
+            cursor.read_bytes(length as usize)?;
+        }
+        let filename = match flags & FLAG_NAME {
+            0 => None,
+            _ => Some(String::from_utf8_lossy(Self::read_string(cursor)?).into_owned()),
+        };
+        if flags & FLAG_COMMENT != 0 {
+            Self::read_string(cursor)?;
+        }
+        if flags & FLAG_HEADER_CRC != 0 {
+            let header = &start[..start.len() - cursor.remaining().len()];
+            if cursor.read_u16_le()? != crc32(header) as u16 {
+                return Err(CodecError::new(
+                    "gzip header does not match its checksum",
+                    CodecErrorKind::ChecksumMismatch,
+                ));
+            }
+        }
+
+        Ok((filename, modified))
+    }
+
+    /// read_string reads a zero terminated string, without its terminator
+    fn read_string<'a>(cursor: &mut ByteCursor<'a>) -> Result<&'a [u8], CodecError> {
+        let remaining = cursor.remaining();
+        let length = remaining
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| corrupted("gzip header string is not terminated"))?;
+        cursor.read_bytes(length + 1)?;
+        Ok(&remaining[..length])
+    }
+}
+
+/// corrupted returns the error of a member that is not valid gzip
+fn corrupted(message: &str) -> CodecError {
+    CodecError::new(message, CodecErrorKind::CorruptedData)
+}
+
+impl Codec for Gzip {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let flags = match self.filename {
+            Some(_) => FLAG_NAME,
+            None => 0,
+        };
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&MAGIC);
+        encoded.extend_from_slice(&[DEFLATED, flags]);
+        encoded.extend_from_slice(&self.modified.to_le_bytes());
+        encoded.extend_from_slice(&[0, OS_UNKNOWN]);
+
+        // the name is written as its bytes, as gzip does, and ends with a zero byte
+        if let Some(filename) = &self.filename {
+            if filename.contains('\0') {
+                return Err(CodecError::new(
+                    "gzip file name cannot hold a zero byte",
+                    CodecErrorKind::InvalidInput,
+                ));
+            }
+            encoded.extend_from_slice(filename.as_bytes());
+            encoded.push(0);
+        }
+
+        encoded.extend(Deflate::new().encode(data)?);
+        encoded.extend_from_slice(&crc32(data).to_le_bytes());
+        encoded.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let mut decoded = Vec::new();
+        let mut first = true;
+
+        // the members follow each other up to the end of the data
+        while first || !cursor.remaining().is_empty() {
+            let (filename, modified) = Self::read_header(&mut cursor)?;
+            if first {
+                (self.filename, self.modified) = (filename, modified);
+                first = false;
+            }
+
+            let mut reader = BitReader::new(cursor.remaining());
+            let member = Deflate::inflate(&mut reader, max_size - decoded.len())?;
+            cursor.read_bytes(reader.consumed_bytes())?;
+
+            if cursor.read_u32_le()? != crc32(&member) {
+                return Err(CodecError::new(
+                    "gzip data does not match its checksum",
+                    CodecErrorKind::ChecksumMismatch,
+                ));
+            }
+            if cursor.read_u32_le()? != member.len() as u32 {
+                return Err(corrupted("gzip data does not match its length"));
+            }
+            decoded.extend(member);
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::binary_samples;
+
+    /// LINES was compressed by `gzip -9`, keeping the name and modification time of the file
+    const LINES: &[u8] = include_bytes!("../../../test_data/deflate/dynamic.txt");
+    const LINES_GZ: &[u8] = include_bytes!("../../../test_data/gzip/lines.txt.gz");
+
+    #[test]
+    fn gzip_files_are_read() {
+        let mut gzip = Gzip::new();
+        assert_eq!(gzip.decode(LINES_GZ).unwrap(), LINES);
+        assert_eq!(gzip.filename.as_deref(), Some("lines.txt"));
+        assert_eq!(gzip.modified, 1_714_564_800);
+
+        // concatenated members, written by `gzip -n` without a name
+        let mut gzip = Gzip::new();
+        let members = include_bytes!("../../../test_data/gzip/members.gz");
+        let decoded = gzip.decode(members).unwrap();
+        assert_eq!(
+            decoded,
+            include_bytes!("../../../test_data/gzip/members.txt")
+        );
+        assert_eq!(gzip.filename, None);
+
+        // a member with an extra field, a comment and a header checksum
+        let mut gzip = Gzip::new();
+        let fields = include_bytes!("../../../test_data/gzip/fields.gz");
+        let decoded = gzip.decode(fields).unwrap();
+        assert_eq!(
+            decoded,
+            include_bytes!("../../../test_data/gzip/fields.txt")
+        );
+        assert_eq!(gzip.filename.as_deref(), Some("fields.txt"));
+    }
+
+    #[test]
+    fn gzip_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([b"".to_vec(), b"a".to_vec(), LINES.to_vec()]);
+
+        for test_case in test_cases {
+            let encoded = Gzip::new().encode(&test_case).unwrap();
+            assert!(is_gzip(&encoded));
+            assert_eq!(Gzip::new().decode(&encoded).unwrap(), test_case);
+        }
+    }
+
+    #[test]
+    fn header_is_written() {
+        let encoded = Gzip::with_header("lines.txt", 1_714_564_800)
+            .encode(LINES)
+            .unwrap();
+        // gzip -9 also marks the level and its operating system in the two bytes after the time
+        assert_eq!(&encoded[..8], &LINES_GZ[..8]);
+        assert_eq!(&encoded[10..20], b"lines.txt\0");
+
+        // the trailer holds the checksum and the length of the data
+        let trailer = &encoded[encoded.len() - 8..];
+        assert_eq!(trailer, &LINES_GZ[LINES_GZ.len() - 8..]);
+
+        let mut gzip = Gzip::new();
+        gzip.decode(&encoded).unwrap();
+        assert_eq!(gzip.filename.as_deref(), Some("lines.txt"));
+        assert_eq!(gzip.modified, 1_714_564_800);
+
+        assert!(Gzip::with_header("a\0b", 0).encode(b"a").is_err());
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut gzip = Gzip::new();
+        assert_eq!(
+            gzip.decode(b"TCZ\x1a").unwrap_err().kind,
+            CodecErrorKind::InvalidMagic
+        );
+        assert!(gzip.decode(&[]).is_err());
+        assert!(gzip.decode(&LINES_GZ[..LINES_GZ.len() - 1]).is_err());
+
+        // a flipped byte of the data or of its checksum
+        let mut corrupted = LINES_GZ.to_vec();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 1;
+        assert_eq!(
+            gzip.decode(&corrupted).unwrap_err().kind,
+            CodecErrorKind::ChecksumMismatch
+        );
+
+        // another compression method, and reserved flags
+        let mut other = LINES_GZ.to_vec();
+        other[2] = 0;
+        assert_eq!(
+            gzip.decode(&other).unwrap_err().kind,
+            CodecErrorKind::UnknownAlgorithm
+        );
+        let mut reserved = LINES_GZ.to_vec();
+        reserved[3] |= 0x80;
+        assert!(gzip.decode(&reserved).is_err());
+
+        // trailing bytes that are not another member
+        let trailing = [LINES_GZ, b"junk"].concat();
+        assert!(gzip.decode(&trailing).is_err());
+    }
+}
@@ -1,144 +1,547 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
 use crate::service::{
     data_structures::{
+        bit_stream::{BitReader, BitWriter},
+        byte_cursor::{write_varint, ByteCursor},
+        canonical_code::{CanonicalDecoder, CanonicalEncoder, MAX_CODE_LENGTH},
         huffman_node::{HuffmanLeaf, HuffmanNode, Link, Node},
+        package_merge::limited_code_lengths,
         priority_queue::Heap,
     },
-    pkg::traits::Codec,
+    pkg::traits::{check_decoded_size, Codec},
 };
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cmp::Reverse;
 use std::rc::Rc;
 
-/// Huffman represents the implementation of the Huffman Encoding algorithm
+/// BYTE_BITS is the width of the symbols in the code length table when coding bytes
+const BYTE_BITS: u32 = 8;
+
+/// LENGTH_BITS is the width of the first code length in the table
+const LENGTH_BITS: u32 = 6;
+
+/// Huffman represents the implementation of the Huffman Encoding algorithm with canonical codes.
+/// The compressed payload starts with the number of symbols and the code length
+/// of every symbol, so it can be decoded without the encoder.
+/// Symbols are bytes for the Codec, and up to 16 bit wide for the symbol methods.
+///
+/// The code length table holds the largest symbol, a bit for every symbol up to it
+/// telling whether it has a code, and the lengths of those symbols. A table without
+/// any code means the largest symbol is the only one, with an empty code. The first length
+/// is written in LENGTH_BITS bits, and every following one as its difference to the
+/// previous one: `10` adds one, `11` removes one and `0` moves to the next symbol.
 pub struct Huffman {
-    root: Link,
-    text: String,
-    char_frequencies: HashMap<char, i32>,
-    char_encodings: HashMap<char, String>,
-    encoded: String,
-    decoded: String,
+    symbol_frequencies: Vec<i32>,
+    code_lengths: Vec<u8>,
+    max_code_length: Option<u8>,
 }
 
 impl Huffman {
-    pub fn new(text: String) -> Self {
-        let mut huffman = Self {
-            root: None,
-            text,
-            char_frequencies: Default::default(),
-            char_encodings: Default::default(),
-            encoded: String::new(),
-            decoded: String::new(),
-        };
-
-        huffman.populate_char_frequencies();
+    pub fn new() -> Self {
+        Self {
+            symbol_frequencies: Default::default(),
+            code_lengths: Default::default(),
+            max_code_length: None,
+        }
+    }
 
-        huffman
+    /// with_max_code_length creates a Huffman encoder whose codes are never longer than
+    /// max_code_length bits. The lengths are computed with package-merge, which gives the
+    /// smallest output under that limit. Decoding does not need the limit.
+    /// A limit outside of 1 to MAX_CODE_LENGTH is rejected as invalid input
+    pub fn with_max_code_length(max_code_length: u8) -> Result<Self, CodecError> {
+        if !(1..=MAX_CODE_LENGTH as u8).contains(&max_code_length) {
+            return Err(CodecError::new(
+                &format!(
+                    "the maximum code length must be between 1 and {}",
+                    MAX_CODE_LENGTH
+                ),
+                CodecErrorKind::InvalidInput,
+            ));
+        }
+        Ok(Self {
+            max_code_length: Some(max_code_length),
+            ..Self::new()
+        })
     }
 
-    fn populate_char_frequencies(&mut self) {
-        self.char_frequencies = self.text.chars().fold(HashMap::new(), |mut hashmap, ch| {
-            *hashmap.entry(ch).or_insert(0) += 1;
-            hashmap
-        });
+    fn populate_symbol_frequencies(&mut self, symbols: &[u16], symbol_bits: u32) {
+        self.symbol_frequencies = vec![0; 1 << symbol_bits];
+        for symbol in symbols {
+            self.symbol_frequencies[*symbol as usize] += 1;
+        }
     }
 
-    fn generate(&mut self, curr_node: Link, curr_code: &str) {
+    /// assign_code_lengths sets the code length of every leaf to its depth in the tree
+    fn assign_code_lengths(&mut self, curr_node: Link, depth: u8) {
         if let Some(curr_node) = curr_node {
             let curr_node = curr_node.borrow();
-            if curr_node.is_leaf() {
-                self.char_encodings
-                    .insert(curr_node.get_character().unwrap(), curr_code.to_string());
+            if let Some(symbol) = curr_node.get_symbol() {
+                self.code_lengths[symbol as usize] = depth;
                 return;
             }
 
-            self.generate(curr_node.get_left(), format!("{}0", curr_code).as_str());
-            self.generate(curr_node.get_right(), format!("{}1", curr_code).as_str());
+            self.assign_code_lengths(curr_node.get_left(), depth + 1);
+            self.assign_code_lengths(curr_node.get_right(), depth + 1);
         }
     }
 
-    fn get_encoded_text(&mut self) {
-        let mut priority_queue: Heap<Box<dyn Node>> = Heap::with_capacity(self.text.len());
-        for (k, v) in &self.char_frequencies {
-            priority_queue.push(Box::new(HuffmanLeaf::new(*k, *v)));
+    /// build_limited_code_lengths computes the code lengths under the maximum code length
+    fn build_limited_code_lengths(&mut self, max_code_length: u8) -> Result<(), CodecError> {
+        let frequencies = self
+            .symbol_frequencies
+            .iter()
+            .map(|frequency| *frequency as u64)
+            .collect::<Vec<u64>>();
+
+        let mut lengths = limited_code_lengths(&frequencies, max_code_length).ok_or_else(|| {
+            CodecError::new(
+                &format!(
+                    "{} bit codes cannot hold every symbol of the data",
+                    max_code_length
+                ),
+                CodecErrorKind::InvalidInput,
+            )
+        })?;
+
+        // the table ends at the largest symbol, and a single symbol keeps its empty code
+        let max_symbol = lengths.iter().rposition(|length| *length > 0).unwrap();
+        lengths.truncate(max_symbol + 1);
+        if lengths.iter().filter(|length| **length > 0).count() == 1 {
+            lengths[max_symbol] = 0;
+        }
+        self.code_lengths = lengths;
+        Ok(())
+    }
+
+    /// build_code_lengths builds the huffman tree of the frequencies and keeps the depth of every symbol.
+    /// The codes themselves are canonical, so the shape of the tree does not matter
+    fn build_code_lengths(&mut self) {
+        // wrap the nodes in Reverse so the heap pops the least frequent node first
+        let mut priority_queue: Heap<Reverse<Box<dyn Node>>> = Heap::new();
+        for (symbol, frequency) in self.symbol_frequencies.iter().enumerate() {
+            if *frequency > 0 {
+                priority_queue.push(Reverse(Box::new(HuffmanLeaf::new(
+                    symbol as u16,
+                    *frequency,
+                ))));
+            }
         }
 
         while priority_queue.size() > 1 {
-            let first = priority_queue.pop().unwrap();
-            let second = priority_queue.pop().unwrap();
-            priority_queue.push(Box::new(HuffmanNode::new(
+            let Reverse(first) = priority_queue.pop().unwrap();
+            let Reverse(second) = priority_queue.pop().unwrap();
+            priority_queue.push(Reverse(Box::new(HuffmanNode::new(
                 Some(Rc::new(RefCell::new(first))),
                 Some(Rc::new(RefCell::new(second))),
-            )));
+            ))));
         }
 
-        let root = priority_queue.pop().unwrap();
-        self.root = Some(Rc::new(RefCell::new(root)));
-        self.generate(self.root.clone(), "");
+        // the table ends at the largest symbol, so a single symbol is the last entry even with a length of 0
+        let max_symbol = self
+            .symbol_frequencies
+            .iter()
+            .rposition(|frequency| *frequency > 0)
+            .unwrap();
+        let Reverse(root) = priority_queue.pop().unwrap();
+        self.code_lengths = vec![0; max_symbol + 1];
+        self.assign_code_lengths(Some(Rc::new(RefCell::new(root))), 0);
+
+        // frequencies fit in an i32, which keeps the tree far shallower than the longest code
+        debug_assert!(self
+            .code_lengths
+            .iter()
+            .all(|l| *l as usize <= MAX_CODE_LENGTH));
     }
-}
 
-impl Codec for Huffman {
-    fn encode(&mut self) {
-        self.get_encoded_text();
-        for ch in self.text.chars() {
-            let s = self.char_encodings.get(&ch).unwrap();
-            self.encoded.push_str(s);
+    /// write_code_lengths writes the code length table of the symbols, which must not be empty
+    fn write_code_lengths(writer: &mut BitWriter, lengths: &[u8], symbol_bits: u32) {
+        writer.write_bits(lengths.len() as u64 - 1, symbol_bits);
+        for length in lengths {
+            writer.write_bit(*length > 0);
         }
-    }
 
-    fn decode(&mut self) {
-        let curr_node = self.root.clone();
-        if let Some(mut curr_node) = curr_node {
-            for ch in self.encoded.chars() {
-                curr_node = if ch == '0' {
-                    curr_node.borrow().get_left()
+        let mut used_lengths = lengths.iter().filter(|length| **length > 0);
+        let mut previous = match used_lengths.next() {
+            Some(length) => *length,
+            None => return,
+        };
+        writer.write_bits(previous as u64, LENGTH_BITS);
+
+        for length in used_lengths {
+            while previous != *length {
+                writer.write_bit(true);
+                writer.write_bit(previous > *length);
+                previous = if previous > *length {
+                    previous - 1
                 } else {
-                    curr_node.borrow().get_right()
-                }
-                .unwrap();
+                    previous + 1
+                };
+            }
+            writer.write_bit(false);
+        }
+    }
 
-                if curr_node.borrow().is_leaf() {
-                    self.decoded
-                        .push(curr_node.borrow().get_character().unwrap());
-                    curr_node = self.root.clone().unwrap();
+    /// read_code_lengths reads a table written by write_code_lengths
+    fn read_code_lengths(reader: &mut BitReader, symbol_bits: u32) -> Result<Vec<u8>, CodecError> {
+        let corrupted = || {
+            CodecError::new(
+                "huffman code length table is corrupted",
+                CodecErrorKind::CorruptedData,
+            )
+        };
+
+        let max_symbol = reader.read_bits(symbol_bits).ok_or_else(corrupted)? as usize;
+        let mut used = Vec::with_capacity(max_symbol + 1);
+        for _ in 0..=max_symbol {
+            used.push(reader.read_bit().ok_or_else(corrupted)?);
+        }
+
+        let mut lengths = vec![0u8; max_symbol + 1];
+        let mut previous: Option<usize> = None;
+        for (symbol, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            let length = match previous {
+                None => reader.read_bits(LENGTH_BITS).ok_or_else(corrupted)? as usize,
+                Some(mut length) => {
+                    while reader.read_bit().ok_or_else(corrupted)? {
+                        length = match reader.read_bit().ok_or_else(corrupted)? {
+                            true => length.checked_sub(1),
+                            false => length.checked_add(1),
+                        }
+                        .ok_or_else(corrupted)?;
+                        // the length has to stay a valid code length while it moves
+                        if !(1..=MAX_CODE_LENGTH).contains(&length) {
+                            return Err(corrupted());
+                        }
+                    }
+                    length
                 }
+            };
+            if !(1..=MAX_CODE_LENGTH).contains(&length) {
+                return Err(corrupted());
             }
+            lengths[symbol] = length as u8;
+            previous = Some(length);
         }
+
+        Ok(lengths)
     }
 
-    fn compressed(&self) -> String {
-        self.encoded.clone()
+    /// encode_symbols compresses symbols of at most symbol_bits bits each. It fails when the
+    /// maximum code length is too short to give a code to every symbol of the data
+    pub fn encode_symbols(
+        &mut self,
+        symbols: &[u16],
+        symbol_bits: u32,
+    ) -> Result<Vec<u8>, CodecError> {
+        debug_assert!(symbol_bits <= 16);
+        let mut encoded = Vec::new();
+        write_varint(&mut encoded, symbols.len() as u64);
+        if symbols.is_empty() {
+            self.code_lengths.clear();
+            return Ok(encoded);
+        }
+
+        self.populate_symbol_frequencies(symbols, symbol_bits);
+        match self.max_code_length {
+            Some(max_code_length) => self.build_limited_code_lengths(max_code_length)?,
+            None => self.build_code_lengths(),
+        }
+
+        // write the code length table ahead of the code of every symbol in the output bit stream
+        let mut writer = BitWriter::new();
+        Self::write_code_lengths(&mut writer, &self.code_lengths, symbol_bits);
+
+        // a single symbol sits at the root with an empty code, so only the table is written
+        let encoder = CanonicalEncoder::new(&self.code_lengths);
+        for symbol in symbols {
+            encoder.write_symbol(&mut writer, *symbol);
+        }
+        encoded.extend(writer.finish());
+        Ok(encoded)
     }
 
-    fn decompressed(&self) -> String {
-        self.decoded.clone()
+    /// decode_symbols restores the symbols compressed by encode_symbols with the same
+    /// symbol_bits, failing when there are more than max_count of them
+    pub fn decode_symbols(
+        &mut self,
+        data: &[u8],
+        symbol_bits: u32,
+        max_count: usize,
+    ) -> Result<Vec<u16>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let symbol_count = cursor.read_varint()? as usize;
+        if symbol_count == 0 {
+            return Ok(Vec::new());
+        }
+        check_decoded_size(symbol_count, max_count)?;
+
+        let mut reader = BitReader::new(cursor.remaining());
+        self.code_lengths = Self::read_code_lengths(&mut reader, symbol_bits)?;
+
+        // a single symbol of length 0 has an empty code, so the symbol is repeated
+        let used_symbols = self
+            .code_lengths
+            .iter()
+            .enumerate()
+            .filter(|(_, length)| **length > 0)
+            .collect::<Vec<_>>();
+        if used_symbols.is_empty() {
+            let symbol = self.code_lengths.len() as u16 - 1;
+            return Ok(vec![symbol; symbol_count]);
+        }
+
+        // every symbol needs at least one bit, so a larger count cannot be valid
+        if symbol_count > reader.remaining() {
+            return Err(CodecError::new(
+                "huffman symbol count is larger than the encoded data",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+
+        let decoder = CanonicalDecoder::new(&self.code_lengths)?;
+        let mut decoded = Vec::with_capacity(symbol_count);
+        while decoded.len() < symbol_count {
+            let symbol = decoder.read_symbol(&mut reader).ok_or_else(|| {
+                CodecError::new(
+                    "huffman data ended before every symbol was decoded",
+                    CodecErrorKind::CorruptedData,
+                )
+            })?;
+            decoded.push(symbol);
+        }
+
+        Ok(decoded)
+    }
+}
+
+impl Default for Huffman {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Codec for Huffman {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let symbols = data.iter().map(|byte| *byte as u16).collect::<Vec<u16>>();
+        self.encode_symbols(&symbols, BYTE_BITS)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        // symbols read with 8 bits always fit in a byte
+        let symbols = self.decode_symbols(data, BYTE_BITS, max_size)?;
+        Ok(symbols.into_iter().map(|symbol| symbol as u8).collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_utils::binary_samples;
 
     #[test]
     fn huffman_works() {
-        let test_cases = vec!["abracadabra!", "aabbc", "aaaaaaaabbbbbbbccccdd", "12ab", "a"];
+        let test_cases = vec![
+            "abracadabra!",
+            "aabbc",
+            "aaaaaaaabbbbbbbccccdd",
+            "12ab",
+            "a",
+        ];
 
         for test_case in test_cases {
-            let mut huffman = Huffman::new(test_case.to_string());
-            huffman.encode();
+            let mut huffman = Huffman::new();
+            huffman.encode(test_case.as_bytes()).unwrap();
         }
     }
 
+    #[test]
+    fn skewed_text_is_compressed() {
+        let text = "aaaaaaaabbbbbbbccccdd".repeat(32);
+        let mut huffman = Huffman::new();
+        let encoded = huffman.encode(text.as_bytes()).unwrap();
+        assert!(encoded.len() < text.len() / 3);
+    }
+
     #[test]
     fn decoder_works() {
-        let test_cases = vec!["abracadabra!", "aabbc", "aaaaaaaabbbbbbbccccdd", "12ab", "a"];
-        let test_cases = vec!["aaaaaaaaaaaaaaaaab"];
+        let test_cases = vec![
+            "abracadabra!",
+            "aabbc",
+            "aaaaaaaabbbbbbbccccdd",
+            "12ab",
+            "a",
+            "aaaaaaaaaaaaaaaaab",
+            "",
+        ];
 
         for test_case in test_cases {
-            let mut huffman = Huffman::new(test_case.to_string());
-            huffman.encode();
-            huffman.decode();
-            assert_eq!(huffman.decoded, test_case.to_string());
+            let encoded = Huffman::new().encode(test_case.as_bytes()).unwrap();
+            // a fresh instance only has the code lengths in the payload to rebuild the codes from
+            let decoded = Huffman::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let encoded = Huffman::new().encode(b"abracadabra!").unwrap();
+        assert!(Huffman::new().decode(&encoded[..2]).is_err());
+        assert!(Huffman::new().decode(&[0xff]).is_err());
+
+        // a single symbol has an empty code, so only the count bounds its output
+        let huge_count = [0x80, 0x80, 0x80, 0x80, 0x80, 0x20, 0x00, 0x00];
+        let err = Huffman::new().decode(&huge_count).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+    }
+
+    #[test]
+    fn corrupted_code_lengths_are_rejected() {
+        // two symbols with a first length of 0, then a decrease of the length
+        let mut writer = BitWriter::new();
+        writer.write_bits(1, BYTE_BITS);
+        writer.write_bits(0b11, 2);
+        writer.write_bits(0, LENGTH_BITS);
+        for bit in [true, true, false] {
+            writer.write_bit(bit);
+        }
+        let table = writer.finish();
+
+        let mut reader = BitReader::new(&table);
+        let err = Huffman::read_code_lengths(&mut reader, BYTE_BITS).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+
+        let mut encoded = vec![2];
+        encoded.extend(table);
+        let err = Huffman::new().decode(&encoded).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+    }
+
+    #[test]
+    fn frequent_symbols_get_shorter_codes() {
+        let mut huffman = Huffman::new();
+        huffman.encode(b"aaaaaaaabbbbbbbccccdd").unwrap();
+        assert!(huffman.code_lengths[b'a' as usize] <= huffman.code_lengths[b'd' as usize]);
+    }
+
+    #[test]
+    fn binary_data_works() {
+        for sample in binary_samples() {
+            let encoded = Huffman::new().encode(&sample).unwrap();
+            let decoded = Huffman::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, sample);
+        }
+    }
+
+    #[test]
+    fn code_lengths_are_canonical() {
+        let mut huffman = Huffman::new();
+        huffman.encode(b"aaaaaaaabbbbbbbccccdd").unwrap();
+        assert_eq!(
+            &huffman.code_lengths[b'a' as usize..=b'd' as usize],
+            [1, 2, 3, 3]
+        );
+
+        // the table holds the lengths only, so the header is smaller than a serialized tree
+        let encoded = huffman.encode(b"abcdefghijklmnopqrstuvwxyz").unwrap();
+        assert!(encoded.len() < 26 + 15);
+    }
+
+    #[test]
+    fn code_length_table_round_trip_works() {
+        let test_cases: Vec<Vec<u8>> = vec![
+            vec![0, 3, 3, 0, 2, 2, 0],
+            vec![1, 1],
+            vec![0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 12],
+        ];
+
+        for lengths in test_cases {
+            let mut writer = BitWriter::new();
+            Huffman::write_code_lengths(&mut writer, &lengths, BYTE_BITS);
+            let bytes = writer.finish();
+
+            let mut reader = BitReader::new(&bytes);
+            let read = Huffman::read_code_lengths(&mut reader, BYTE_BITS).unwrap();
+            assert_eq!(read, lengths);
+        }
+    }
+
+    #[test]
+    fn symbols_wider_than_a_byte_work() {
+        let symbols = (0..2000u16).map(|i| (i * 7) % 300).collect::<Vec<u16>>();
+        let encoded = Huffman::new().encode_symbols(&symbols, 9).unwrap();
+        assert_eq!(
+            Huffman::new().decode_symbols(&encoded, 9, 2000).unwrap(),
+            symbols
+        );
+    }
+
+    /// fibonacci_text repeats the i-th symbol as many times as the i-th fibonacci number,
+    /// which makes an unlimited huffman tree one level deeper for every symbol
+    fn fibonacci_text(n_symbols: usize) -> Vec<u8> {
+        let (mut a, mut b) = (1usize, 1usize);
+        let mut text = Vec::new();
+        for symbol in 0..n_symbols {
+            text.extend(std::iter::repeat_n(b'A' + symbol as u8, a));
+            (a, b) = (b, a + b);
+        }
+        text
+    }
+
+    #[test]
+    fn fibonacci_codes_are_limited() {
+        let text = fibonacci_text(25);
+
+        // the number of bits taken by the codes of the text
+        let cost = |huffman: &Huffman| -> usize {
+            text.iter()
+                .map(|byte| huffman.code_lengths[*byte as usize] as usize)
+                .sum()
+        };
+
+        let mut unlimited = Huffman::new();
+        unlimited.encode(&text).unwrap();
+        assert_eq!(*unlimited.code_lengths.iter().max().unwrap(), 24);
+
+        for max_code_length in [5, 10, 15] {
+            let mut huffman = Huffman::with_max_code_length(max_code_length).unwrap();
+            let encoded = huffman.encode(&text).unwrap();
+            assert_eq!(*huffman.code_lengths.iter().max().unwrap(), max_code_length);
+            assert!(cost(&huffman) > cost(&unlimited));
+
+            // the decoder reads the lengths from the table, so it needs no limit
+            assert_eq!(Huffman::new().decode(&encoded).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn loose_limit_keeps_the_optimal_size() {
+        let text = b"aaaaaaaabbbbbbbccccdd".repeat(8);
+        let unlimited = Huffman::new().encode(&text).unwrap();
+        let limited = Huffman::with_max_code_length(15)
+            .unwrap()
+            .encode(&text)
+            .unwrap();
+        assert_eq!(limited.len(), unlimited.len());
+
+        let single = Huffman::with_max_code_length(15)
+            .unwrap()
+            .encode(b"aaaa")
+            .unwrap();
+        assert_eq!(Huffman::new().decode(&single).unwrap(), b"aaaa");
+    }
+
+    #[test]
+    fn too_short_limit_is_rejected() {
+        let mut huffman = Huffman::with_max_code_length(2).unwrap();
+        let err = huffman.encode(b"abcde").unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::InvalidInput);
+
+        for max_code_length in [0, MAX_CODE_LENGTH as u8 + 1] {
+            let err = Huffman::with_max_code_length(max_code_length)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind, CodecErrorKind::InvalidInput);
         }
     }
 }
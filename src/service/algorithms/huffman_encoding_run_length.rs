@@ -1,7 +1,231 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
 use crate::service::{
-    algorithms::{
-        
-        run_length_encoding::RunLengthEncoding,
+    algorithms::huffman::Huffman,
+    data_structures::{
+        bit_stream::{BitReader, BitWriter},
+        byte_cursor::{write_varint, ByteCursor},
     },
-    pkg::traits::Codec,
-};
\ No newline at end of file
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// MAX_RUN is the longest run a single run symbol can hold, longer runs are split
+const MAX_RUN: u64 = u32::MAX as u64;
+
+/// BYTE_BITS is the width of the run symbols
+const BYTE_BITS: u32 = 8;
+
+/// CATEGORY_BITS is the width of the length categories, which go up to 32
+const CATEGORY_BITS: u32 = 6;
+
+/// HuffmanRunLength represents run length encoding followed by Huffman encoding, in the
+/// way fax and JPEG code runs: the bytes of the runs and their lengths are coded with two
+/// separate Huffman codes, as they follow different distributions. A length is coded as
+/// its category, the number of bits of the length, followed by the bits below its leading
+/// one written as they are, so lengths of any size share a small alphabet:
+///
+/// | length   | category | extra bits |
+/// |----------|----------|------------|
+/// | 1        | 1        | none       |
+/// | 2 - 3    | 2        | 1          |
+/// | 4 - 7    | 3        | 2          |
+/// | 2^(c-1)… | c        | c - 1      |
+///
+/// The payload holds the Huffman coded bytes and categories, each after its length as a
+/// varint, followed by the extra bits of every length.
+#[derive(Default)]
+pub struct HuffmanRunLength {
+    bytes: Huffman,
+    categories: Huffman,
+}
+
+impl HuffmanRunLength {
+    pub fn new() -> Self {
+        Self {
+            bytes: Huffman::new(),
+            categories: Huffman::new(),
+        }
+    }
+
+    /// runs splits the data into runs of equal bytes of at most MAX_RUN bytes
+    fn runs(data: &[u8]) -> Vec<(u8, u64)> {
+        let mut runs: Vec<(u8, u64)> = Vec::new();
+        for byte in data {
+            match runs.last_mut() {
+                Some((last, length)) if last == byte && *length < MAX_RUN => *length += 1,
+                _ => runs.push((*byte, 1)),
+            }
+        }
+        runs
+    }
+
+    /// category returns the number of bits of a run length
+    fn category(length: u64) -> u32 {
+        u64::BITS - length.leading_zeros()
+    }
+
+    /// read_part reads a part of the payload written after its length
+    fn read_part<'a>(cursor: &mut ByteCursor<'a>) -> Result<&'a [u8], CodecError> {
+        let length = cursor.read_varint()?;
+        if length > cursor.remaining().len() as u64 {
+            return Err(CodecError::new(
+                "huffman run length part is longer than the data",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+        cursor.read_bytes(length as usize)
+    }
+}
+
+impl Codec for HuffmanRunLength {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let runs = Self::runs(data);
+        let bytes = runs
+            .iter()
+            .map(|(byte, _)| *byte as u16)
+            .collect::<Vec<u16>>();
+        let categories = runs
+            .iter()
+            .map(|(_, length)| Self::category(*length) as u16)
+            .collect::<Vec<u16>>();
+
+        // the bits below the leading one of every length
+        let mut extra_bits = BitWriter::new();
+        for (_, length) in &runs {
+            let category = Self::category(*length);
+            extra_bits.write_bits(*length - (1 << (category - 1)), category - 1);
+        }
+
+        let mut encoded = Vec::new();
+        for part in [
+            self.bytes.encode_symbols(&bytes, BYTE_BITS)?,
+            self.categories.encode_symbols(&categories, CATEGORY_BITS)?,
+        ] {
+            write_varint(&mut encoded, part.len() as u64);
+            encoded.extend(part);
+        }
+        encoded.extend(extra_bits.finish());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+        let mut cursor = ByteCursor::new(data);
+
+        // every run holds at least one byte
+        let bytes =
+            self.bytes
+                .decode_symbols(Self::read_part(&mut cursor)?, BYTE_BITS, max_size)?;
+        let categories = self.categories.decode_symbols(
+            Self::read_part(&mut cursor)?,
+            CATEGORY_BITS,
+            max_size,
+        )?;
+        if bytes.len() != categories.len() {
+            return Err(corrupted("huffman run length has more bytes than lengths"));
+        }
+
+        let mut extra_bits = BitReader::new(cursor.remaining());
+        let mut decoded = Vec::with_capacity(bytes.len());
+        for (byte, category) in bytes.into_iter().zip(categories) {
+            let category = category as u32;
+            if !(1..=u32::BITS).contains(&category) {
+                return Err(corrupted("huffman run length category is out of range"));
+            }
+            let extra = extra_bits
+                .read_bits(category - 1)
+                .ok_or_else(|| corrupted("huffman run length data ended in a length"))?;
+            let length = (1u64 << (category - 1)) + extra;
+            check_decoded_size(decoded.len().saturating_add(length as usize), max_size)?;
+
+            // symbols read with 8 bits always fit in a byte
+            decoded.extend(std::iter::repeat_n(byte as u8, length as usize));
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::run_length_encoding::RunLengthEncoding;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    /// scan_lines returns a bitmap like the ones fax machines send, with long runs of white
+    /// broken by short runs of black
+    fn scan_lines(rng: &mut XorShift) -> Vec<u8> {
+        let mut data = Vec::new();
+        for _ in 0..400 {
+            let white = 20 + rng.next_u64() % 300;
+            let black = 1 + rng.next_u64() % 12;
+            data.extend(std::iter::repeat_n(0x00, white as usize));
+            data.extend(std::iter::repeat_n(0xff, black as usize));
+        }
+        data
+    }
+
+    #[test]
+    fn huffman_run_length_works() {
+        let mut rng = XorShift::new(10);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"aaaaabbbbbaaaaabbbbb".to_vec(),
+            b"abracadabra!".to_vec(),
+            vec![7u8; 100_000],
+            rng.bytes(10_000),
+            scan_lines(&mut rng),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = HuffmanRunLength::new().encode(&test_case).unwrap();
+            let decoded = HuffmanRunLength::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn lengths_are_coded_by_category() {
+        assert_eq!(HuffmanRunLength::category(1), 1);
+        assert_eq!(HuffmanRunLength::category(3), 2);
+        assert_eq!(HuffmanRunLength::category(4), 3);
+        assert_eq!(HuffmanRunLength::category(MAX_RUN), 32);
+
+        // a single run is one byte, one category and the bits below the leading one of 100
+        let encoded = HuffmanRunLength::new().encode(&[b'x'; 100]).unwrap();
+        let extra = encoded.last().unwrap();
+        assert_eq!(*extra, 100 - 64);
+
+        // consecutive equal bytes make a run
+        assert_eq!(HuffmanRunLength::runs(b"aab"), [(b'a', 2), (b'b', 1)]);
+    }
+
+    #[test]
+    fn runs_compress_better_than_with_rle_or_huffman() {
+        let data = scan_lines(&mut XorShift::new(11));
+        let encoded = HuffmanRunLength::new().encode(&data).unwrap();
+        let rle = RunLengthEncoding::new().encode(&data).unwrap();
+        let huffman = Huffman::new().encode(&data).unwrap();
+        assert!(encoded.len() < rle.len() / 2);
+        assert!(encoded.len() < huffman.len() / 10);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut codec = HuffmanRunLength::new();
+        let encoded = codec.encode(b"aaaaabbbbbaaaaabbbbb").unwrap();
+        assert!(codec.decode(&[]).is_err());
+        assert!(codec.decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(codec.decode(&[200, 1]).is_err());
+
+        // more bytes than lengths
+        let bytes = Huffman::new().encode_symbols(&[1, 2], BYTE_BITS).unwrap();
+        let categories = Huffman::new().encode_symbols(&[1], CATEGORY_BITS).unwrap();
+        let mut data = vec![bytes.len() as u8];
+        data.extend(bytes);
+        data.push(categories.len() as u8);
+        data.extend(categories);
+        assert!(codec.decode(&data).is_err());
+    }
+}
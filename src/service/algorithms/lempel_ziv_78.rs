@@ -0,0 +1,201 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::{
+        bit_stream::{BitReader, BitWriter},
+        byte_cursor::{write_varint, ByteCursor},
+        dictionary_trie::{DictionaryTrie, ROOT},
+    },
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// MAX_PHRASES is the size at which the dictionary is dropped and started over
+const MAX_PHRASES: usize = 1 << 16;
+
+/// LempelZiv78 represents a struct for the LZ78 compression algorithm. The data is cut into
+/// phrases made of the longest phrase already in the dictionary followed by one more byte,
+/// and every phrase is written as the (index, byte) pair of that prefix and byte.
+/// Unlike LZW, the dictionary starts empty and every token carries a literal byte.
+///
+/// The payload is the number of bytes followed by a bit stream of tokens. The index takes as
+/// many bits as the largest index of the dictionary at that point, so the first token has
+/// none, and the byte takes 8 bits.
+#[derive(Default)]
+pub struct LempelZiv78 {}
+
+impl LempelZiv78 {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// index_width returns the number of bits holding every index of the dictionary
+    fn index_width(dictionary: &DictionaryTrie) -> u32 {
+        usize::BITS - (dictionary.len() - 1).leading_zeros()
+    }
+
+    /// write_token writes a phrase and adds it to the dictionary, which is started over when full
+    fn write_token(
+        writer: &mut BitWriter,
+        dictionary: &mut DictionaryTrie,
+        prefix: usize,
+        byte: u8,
+    ) {
+        writer.write_bits(prefix as u64, Self::index_width(dictionary));
+        writer.write_bits(byte as u64, u8::BITS);
+
+        dictionary.insert(prefix, byte);
+        if dictionary.len() == MAX_PHRASES {
+            *dictionary = DictionaryTrie::new();
+        }
+    }
+}
+
+impl Codec for LempelZiv78 {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = Vec::new();
+        write_varint(&mut encoded, data.len() as u64);
+
+        let mut dictionary = DictionaryTrie::new();
+        let mut writer = BitWriter::new();
+        let mut node = ROOT;
+        for byte in data {
+            match dictionary.child(node, *byte) {
+                Some(child) => node = child,
+                None => {
+                    Self::write_token(&mut writer, &mut dictionary, node, *byte);
+                    node = ROOT;
+                }
+            }
+        }
+
+        // the data ended inside a known phrase, so write it as its prefix and its last byte
+        if node != ROOT {
+            let (prefix, byte) = (dictionary.parent(node), dictionary.last_byte(node));
+            Self::write_token(&mut writer, &mut dictionary, prefix, byte);
+        }
+
+        encoded.extend(writer.finish());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let original_length = usize::try_from(cursor.read_varint()?).unwrap_or(usize::MAX);
+        check_decoded_size(original_length, max_size)?;
+        let mut reader = BitReader::new(cursor.remaining());
+
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+        let truncated = || corrupted("lz78 data ended before every byte was decoded");
+
+        // every token of at most 16 + 8 bits, an index below MAX_PHRASES and a byte, adds at
+        // least one byte
+        let mut dictionary = DictionaryTrie::new();
+        let mut decoded = Vec::with_capacity(original_length.min(data.len() * 8));
+        while decoded.len() < original_length {
+            let prefix = reader
+                .read_bits(Self::index_width(&dictionary))
+                .ok_or_else(truncated)? as usize;
+            let byte = reader.read_bits(u8::BITS).ok_or_else(truncated)? as u8;
+            if prefix >= dictionary.len() {
+                return Err(corrupted("lz78 index is not in the dictionary"));
+            }
+            if decoded.len() + dictionary.phrase_len(prefix) + 1 > original_length {
+                return Err(corrupted("lz78 phrase runs past the end of the data"));
+            }
+
+            decoded.extend(dictionary.phrase(prefix));
+            decoded.push(byte);
+            dictionary.insert(prefix, byte);
+            if dictionary.len() == MAX_PHRASES {
+                dictionary = DictionaryTrie::new();
+            }
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::lempel_ziv_welch::LempelZivWelch;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    #[test]
+    fn phrases_are_cut_as_in_lz78() {
+        // a|b|ab|c|ba|bab|aa|aaa and the last "aa" is written as ("a", a)
+        let data = b"ababcbababaaaaaaa";
+        let encoded = LempelZiv78::new().encode(data).unwrap();
+
+        let mut reader = BitReader::new(&encoded[1..]);
+        let mut dictionary = DictionaryTrie::new();
+        let mut tokens = Vec::new();
+        while tokens.len() < 9 {
+            let prefix = reader
+                .read_bits(LempelZiv78::index_width(&dictionary))
+                .unwrap();
+            let byte = reader.read_bits(8).unwrap() as u8;
+            dictionary.insert(prefix as usize, byte);
+            tokens.push((prefix, byte as char));
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                (0, 'a'),
+                (0, 'b'),
+                (1, 'b'),
+                (0, 'c'),
+                (2, 'a'),
+                (5, 'b'),
+                (1, 'a'),
+                (7, 'a'),
+                (1, 'a'),
+            ]
+        );
+    }
+
+    #[test]
+    fn lz78_works() {
+        let mut rng = XorShift::new(5);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"aaaa".to_vec(),
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+            // enough phrases to start the dictionary over
+            (0..400_000).map(|_| (rng.next_u64() % 4) as u8).collect(),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = LempelZiv78::new().encode(&test_case).unwrap();
+            let decoded = LempelZiv78::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn lzw_improves_on_lz78() {
+        let text = "TOBEORNOTTOBEORTOBEORNOT abracadabra! ".repeat(100);
+        let lz78 = LempelZiv78::new().encode(text.as_bytes()).unwrap();
+        let lzw = LempelZivWelch::new().encode(text.as_bytes()).unwrap();
+        assert!(lz78.len() < text.len() / 2);
+        assert!(lzw.len() < lz78.len());
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut lz78 = LempelZiv78::new();
+        assert!(lz78.decode(&[]).is_err());
+        assert!(lz78.decode(&[3, b'a']).is_err());
+
+        // the second token "ab" runs past the 2 bytes of the data
+        let mut writer = BitWriter::new();
+        writer.write_bits(b'a' as u64, 8);
+        writer.write_bits(1, 1);
+        writer.write_bits(b'b' as u64, 8);
+        let encoded = [vec![2], writer.finish()].concat();
+        assert!(lz78.decode(&encoded).is_err());
+    }
+}
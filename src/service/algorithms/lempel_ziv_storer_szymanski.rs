@@ -0,0 +1,281 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::{
+        bit_stream::{BitReader, BitWriter},
+        byte_cursor::{write_varint, ByteCursor},
+        hash_chain::{HashChain, HASH_LENGTH},
+    },
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// MAX_WINDOW_BITS bounds the window to 16 MiB
+const MAX_WINDOW_BITS: u32 = 24;
+
+/// MAX_LENGTH_BITS bounds the width of the match lengths
+const MAX_LENGTH_BITS: u32 = 16;
+
+/// MAX_LOOKAHEAD bounds the longest match, so the lengths read from a payload stay small
+const MAX_LOOKAHEAD: usize = 1 << 20;
+
+/// MAX_CHAIN is the number of candidates the match finder tries at every position
+const MAX_CHAIN: usize = 128;
+
+/// LempelZivStorerSzymanski represents the LZSS variant of LZ77. Repeated bytes are replaced by a
+/// back-reference to an earlier occurrence within a sliding window, found with a hash chain,
+/// and matches shorter than min_match bytes are written as literals.
+///
+/// The payload starts with the number of bytes, the window bits, the minimum match length
+/// and the lookahead (the longest match) as varints. It is followed by a bit stream of
+/// tokens: a 1 bit and 8 bits for a literal, or a 0 bit, the distance minus one in window
+/// bits and the length minus min_match in as many bits as the longest length needs.
+pub struct LempelZivStorerSzymanski {
+    window_bits: u32,
+    min_match: usize,
+    lookahead: usize,
+}
+
+impl LempelZivStorerSzymanski {
+    /// new uses a 32 KiB window and matches of 3 to 258 bytes, as DEFLATE does
+    pub fn new() -> Self {
+        Self {
+            window_bits: 15,
+            min_match: 3,
+            lookahead: 258,
+        }
+    }
+
+    /// with_window configures the window of 2^window_bits bytes and the lengths of the matches.
+    /// min_match cannot be shorter than the bytes hashed by the match finder, and parameters
+    /// out of range are rejected as invalid input
+    pub fn with_window(
+        window_bits: u32,
+        min_match: usize,
+        lookahead: usize,
+    ) -> Result<Self, CodecError> {
+        if !Self::valid_parameters(window_bits as u64, min_match as u64, lookahead as u64) {
+            return Err(CodecError::new(
+                &format!(
+                    "lzss needs 1 to {} window bits and matches of {} to {} bytes, whose lengths fit in {} bits",
+                    MAX_WINDOW_BITS, HASH_LENGTH, MAX_LOOKAHEAD, MAX_LENGTH_BITS
+                ),
+                CodecErrorKind::InvalidInput,
+            ));
+        }
+
+        Ok(Self {
+            window_bits,
+            min_match,
+            lookahead,
+        })
+    }
+
+    /// valid_parameters tells whether the window has 1 to MAX_WINDOW_BITS bits and the matches
+    /// are HASH_LENGTH to MAX_LOOKAHEAD bytes long, with every length in MAX_LENGTH_BITS bits
+    fn valid_parameters(window_bits: u64, min_match: u64, lookahead: u64) -> bool {
+        (1..=MAX_WINDOW_BITS as u64).contains(&window_bits)
+            && (HASH_LENGTH as u64..=lookahead).contains(&min_match)
+            && lookahead <= MAX_LOOKAHEAD as u64
+            && lookahead - min_match < 1 << MAX_LENGTH_BITS
+    }
+
+    /// length_bits returns the number of bits holding every match length
+    fn length_bits(min_match: usize, lookahead: usize) -> u32 {
+        usize::BITS - (lookahead - min_match).leading_zeros()
+    }
+}
+
+impl Default for LempelZivStorerSzymanski {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Codec for LempelZivStorerSzymanski {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = Vec::new();
+        write_varint(&mut encoded, data.len() as u64);
+        write_varint(&mut encoded, self.window_bits as u64);
+        write_varint(&mut encoded, self.min_match as u64);
+        write_varint(&mut encoded, self.lookahead as u64);
+
+        let length_bits = Self::length_bits(self.min_match, self.lookahead);
+        let mut chain = HashChain::new(1 << self.window_bits, MAX_CHAIN);
+        let mut writer = BitWriter::new();
+
+        let mut position = 0;
+        while position < data.len() {
+            let token_length = match chain.longest_match(data, position, self.lookahead) {
+                Some((distance, length)) if length >= self.min_match => {
+                    writer.write_bit(false);
+                    writer.write_bits(distance as u64 - 1, self.window_bits);
+                    writer.write_bits((length - self.min_match) as u64, length_bits);
+                    length
+                }
+                _ => {
+                    writer.write_bit(true);
+                    writer.write_bits(data[position] as u64, u8::BITS);
+                    1
+                }
+            };
+
+            // every covered position can start a later match
+            for covered in position..position + token_length {
+                chain.insert(data, covered);
+            }
+            position += token_length;
+        }
+
+        encoded.extend(writer.finish());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+
+        let mut cursor = ByteCursor::new(data);
+        let original_length = usize::try_from(cursor.read_varint()?).unwrap_or(usize::MAX);
+        check_decoded_size(original_length, max_size)?;
+        let window_bits = cursor.read_varint()?;
+        let min_match = cursor.read_varint()?;
+        let lookahead = cursor.read_varint()?;
+        if !Self::valid_parameters(window_bits, min_match, lookahead) {
+            return Err(corrupted("lzss parameters are out of range"));
+        }
+        let (window_bits, min_match, lookahead) =
+            (window_bits as u32, min_match as usize, lookahead as usize);
+        let length_bits = Self::length_bits(min_match, lookahead);
+
+        let mut reader = BitReader::new(cursor.remaining());
+        let truncated = || corrupted("lzss data ended before every byte was decoded");
+
+        // a token of 9 bits holds at least one byte
+        let mut decoded =
+            Vec::with_capacity(original_length.min(data.len().saturating_mul(lookahead)));
+        while decoded.len() < original_length {
+            if reader.read_bit().ok_or_else(truncated)? {
+                decoded.push(reader.read_bits(u8::BITS).ok_or_else(truncated)? as u8);
+                continue;
+            }
+
+            let distance = reader.read_bits(window_bits).ok_or_else(truncated)? as usize + 1;
+            let length = reader.read_bits(length_bits).ok_or_else(truncated)? as usize + min_match;
+            if distance > decoded.len() || decoded.len() + length > original_length {
+                return Err(corrupted("lzss match points outside of the data"));
+            }
+
+            // the match may overlap the bytes it produces, so copy one byte at a time
+            let start = decoded.len() - distance;
+            for i in 0..length {
+                decoded.push(decoded[start + i]);
+            }
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::lempel_ziv_welch::LempelZivWelch;
+    use crate::utils::test_utils::binary_samples;
+
+    fn log_lines() -> Vec<u8> {
+        (0..2000)
+            .map(|i| {
+                format!(
+                    "2024-05-{:02} 12:{:02}:{:02} INFO request handled path=/api/v1/items/{} status=200\n",
+                    i % 28 + 1,
+                    i % 60,
+                    (i * 7) % 60,
+                    i % 37
+                )
+            })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    fn lzss_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"abc".to_vec(),
+            vec![0u8; 100_000],
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+            log_lines(),
+        ]);
+
+        let mut codecs = [
+            LempelZivStorerSzymanski::new(),
+            LempelZivStorerSzymanski::with_window(8, 3, 18).unwrap(),
+            LempelZivStorerSzymanski::with_window(12, 5, 5).unwrap(),
+            LempelZivStorerSzymanski::with_window(1, 3, 65538).unwrap(),
+        ];
+        for codec in codecs.iter_mut() {
+            for test_case in test_cases.iter() {
+                let encoded = codec.encode(test_case).unwrap();
+
+                // the decoder reads the parameters from the payload
+                let decoded = LempelZivStorerSzymanski::new().decode(&encoded).unwrap();
+                assert_eq!(decoded, *test_case);
+            }
+        }
+    }
+
+    #[test]
+    fn logs_compress_better_than_lzw() {
+        let logs = log_lines();
+        let lzss = LempelZivStorerSzymanski::new().encode(&logs).unwrap();
+        let lzw = LempelZivWelch::new().encode(&logs).unwrap();
+        assert!(lzss.len() < lzw.len());
+
+        // a larger window reaches more of the earlier lines
+        let small = LempelZivStorerSzymanski::with_window(6, 3, 258)
+            .unwrap()
+            .encode(&logs)
+            .unwrap();
+        assert!(lzss.len() < small.len());
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut lzss = LempelZivStorerSzymanski::new();
+        assert!(lzss.decode(&[]).is_err());
+        assert!(lzss.decode(&[1, 0, 3, 3]).is_err());
+        assert!(lzss.decode(&[1, 15, 2, 3]).is_err());
+        assert!(lzss.decode(&[1, 15, 3, 18]).is_err());
+
+        // a match before any byte was decoded
+        assert!(lzss.decode(&[4, 15, 3, 18, 0, 0, 0, 0]).is_err());
+
+        // matches of 2^60 bytes need no length bits at all
+        let mut data = vec![1, 15];
+        write_varint(&mut data, 1 << 60);
+        write_varint(&mut data, 1 << 60);
+        data.extend([0, 0, 0, 0]);
+        let err = lzss.decode(&data).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+    }
+
+    #[test]
+    fn invalid_windows_are_rejected() {
+        let parameters = [
+            (0, 3, 18),
+            (MAX_WINDOW_BITS + 1, 3, 18),
+            (15, HASH_LENGTH - 1, 18),
+            (15, 5, 4),
+            (15, 3, 3 + (1 << MAX_LENGTH_BITS)),
+            (15, MAX_LOOKAHEAD + 1, MAX_LOOKAHEAD + 1),
+        ];
+        for (window_bits, min_match, lookahead) in parameters {
+            let err = LempelZivStorerSzymanski::with_window(window_bits, min_match, lookahead)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind, CodecErrorKind::InvalidInput);
+        }
+    }
+}
@@ -1,141 +1,302 @@
-use crate::service::pkg::traits::Codec;
-use std::collections::HashMap;
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::data_structures::{
+    bit_stream::{BitReader, BitWriter},
+    dictionary_trie::{DictionaryTrie, ROOT},
+};
+use crate::service::pkg::traits::{check_decoded_size, Codec};
 
-/// LempelZivWelch represents a struct for the LZW compression algorithm
+/// MAX_BITS_WIDTH is the number of bits used to store the largest code width
+const MAX_BITS_WIDTH: u32 = 8;
+
+/// MIN_BITS is the width of the first codes, enough for every byte and CLEAR
+const MIN_BITS: u32 = 9;
+
+/// MAX_BITS bounds the largest code width, and so the dictionary to 2^24 phrases
+const MAX_BITS: u32 = 24;
+
+/// CLEAR is the code telling the decoder to start the dictionary over
+const CLEAR: u64 = 256;
+
+/// FIRST_CODE is the code of the first phrase added to the dictionary
+const FIRST_CODE: u64 = 257;
+
+/// LempelZivWelch represents a struct for the LZW compression algorithm, with the code layout
+/// of Unix `compress`. Codes 0 to 255 are the single bytes and 256 is CLEAR. Every code is
+/// written with the width of the largest code the decoder may receive at that point, starting
+/// at 9 bits and growing up to max_bits. Once the dictionary holds 2^max_bits codes the encoder
+/// writes CLEAR, and both sides start over with the single bytes, so memory stays bounded.
+///
+/// The payload starts with max_bits in 8 bits, followed by the codes.
 pub struct LempelZivWelch {
-    text: String,
-    encoded: Vec<u64>,
-    decoded: String,
+    max_bits: u32,
 }
 
 impl LempelZivWelch {
-    pub fn new(text: String) -> Self {
-        Self {
-            text,
-            encoded: Vec::new(),
-            decoded: String::new(),
+    /// new allows codes of up to 16 bits, the default of `compress`
+    pub fn new() -> Self {
+        Self { max_bits: 16 }
+    }
+
+    /// with_max_bits bounds the codes to max_bits bits and the dictionary to 2^max_bits codes,
+    /// and rejects a width outside of MIN_BITS to MAX_BITS as invalid input
+    pub fn with_max_bits(max_bits: u32) -> Result<Self, CodecError> {
+        if !(MIN_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(CodecError::new(
+                &format!(
+                    "the maximum code width must be between {} and {}",
+                    MIN_BITS, MAX_BITS
+                ),
+                CodecErrorKind::InvalidInput,
+            ));
         }
+        Ok(Self { max_bits })
+    }
+
+    /// code_width returns the width of the next code, the number of bits of the largest code
+    /// in the dictionary, which is the code the decoder is about to add when the data repeats
+    fn code_width(next_code: u64) -> u32 {
+        u64::BITS - (next_code - 1).leading_zeros()
+    }
+}
+
+impl Default for LempelZivWelch {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Codec for LempelZivWelch {
-    fn encode(&mut self) {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
         // if there is no text to process, abandon the call
-        if self.text.is_empty() {
-            return;
+        if data.is_empty() {
+            return Ok(Vec::new());
         }
 
-        // use a hashmap to hold all ASCII characters for reference
-        let size = 255_u8;
-        let mut hashmap = (0..=size).fold(HashMap::new(), |mut hashmap, idx| {
-            hashmap.insert((idx as char).to_string(), idx as u64);
-            hashmap
-        });
-
-        // transform the data to a vector of characters
-        let data = self.text.chars().collect::<Vec<char>>();
-
-        // create a pattern to hold the common substrings in the text
-        let mut pattern = data.first().unwrap().to_string();
-
-        // continuing from the last recorded ASCII character, build more repeated patterns
-        let mut size = (size as u64) + 1;
-        for i in 0..data.len() - 1 {
-            // start a potential pattern
-            let curr_char = data[i + 1];
-
-            let mut new_pattern = pattern.clone();
-            new_pattern.push(curr_char);
-
-            // if the new pattern is in the hashmap,
-            if hashmap.contains_key(&new_pattern) {
-                // update the old pattern with the new one
-                pattern = new_pattern;
-            } else {
-                // if the new pattern is not in the hashmap
-                // add the old pattern to the encoded result
-                self.encoded.push(hashmap.get(&pattern).unwrap().clone());
-                // put the new pattern in the hashmap with the next index available as value
-                hashmap.insert(new_pattern, size);
-                size += 1;
-                // start the pattern from the current character
-                pattern = curr_char.to_string();
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.max_bits as u64, MAX_BITS_WIDTH);
+
+        // the dictionary starts with every single byte, the code of a byte is its node minus one.
+        // The root takes the place of CLEAR, so the code of a later phrase is its node
+        let mut dictionary = DictionaryTrie::with_single_bytes();
+        let mut next_code = FIRST_CODE;
+        let code_of = |node: usize| match node as u64 {
+            node if node < FIRST_CODE => node - 1,
+            node => node,
+        };
+
+        // the node of the pattern holding the common substrings in the text
+        let mut pattern = dictionary.child(ROOT, data[0]).unwrap();
+
+        // continuing from the last recorded byte, build more repeated patterns
+        for curr_byte in &data[1..] {
+            match dictionary.child(pattern, *curr_byte) {
+                // if the pattern followed by the byte is in the dictionary, extend the pattern
+                Some(node) => pattern = node,
+                None => {
+                    // add the pattern to the encoded result and the extended pattern to the dictionary
+                    writer.write_bits(code_of(pattern), Self::code_width(next_code));
+                    if next_code < 1 << self.max_bits {
+                        dictionary.insert(pattern, *curr_byte);
+                        next_code += 1;
+                    } else {
+                        // the dictionary is full, so start over
+                        writer.write_bits(CLEAR, Self::code_width(next_code));
+                        dictionary = DictionaryTrie::with_single_bytes();
+                        next_code = FIRST_CODE;
+                    }
+                    // start the pattern from the current byte
+                    pattern = dictionary.child(ROOT, *curr_byte).unwrap();
+                }
             }
         }
-        self.encoded.push(hashmap.get(&pattern).unwrap().clone());
+        writer.write_bits(code_of(pattern), Self::code_width(next_code));
+
+        Ok(writer.finish())
     }
 
-    fn decode(&mut self) {
-        // create a hashmap of byte number to the corresponding ASCII value
-        let mut size = 255_u8;
-        let mut hashmap = (0..=size).fold(HashMap::new(), |mut hashmap, idx| {
-            hashmap.insert(idx as u64, (idx as char).to_string());
-            hashmap
-        });
-
-        // initialize the current encoded index with the first value from the encoded vector
-        let mut current_encoded_index = self.encoded[0];
-        // get the corresponding string for the current encoded index
-        let mut current_decoded_string = hashmap.get(&current_encoded_index).unwrap().clone();
-        // get the first character of the current decoded string
-        let mut first_char_decoded_string =
-            current_decoded_string.chars().next().unwrap().to_string();
-
-        // add the current decoded string to the output
-        self.decoded.push_str(current_decoded_string.as_str());
-
-        // initialize the count with the next ASCII value
-        let mut count = (size as u64) + 1;
-
-        // iterate over the encoded vector
-        for i in 0..self.encoded.len() - 1 {
-            // get the next encoded value
-            let next_encoded_value = self.encoded[i + 1];
-            // if the next encoded value is in the hashmap, update the current decoded string
-            // else append the first character of the decoded string to the current decoded string
-            match hashmap.get(&next_encoded_value) {
-                Some(value) => {
-                    current_decoded_string = value.clone();
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = BitReader::new(data);
+        let max_bits = reader.read_bits(MAX_BITS_WIDTH).unwrap() as u32;
+        if !(MIN_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(CodecError::new(
+                "lzw code width is out of range",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+        let corrupted = || {
+            CodecError::new(
+                "lzw code is not in the dictionary",
+                CodecErrorKind::CorruptedData,
+            )
+        };
+
+        // the decoder builds the same trie as the encoder, one phrase behind it, so a code maps
+        // to the same node and a phrase is read back by walking up from its node
+        let mut dictionary = DictionaryTrie::with_single_bytes();
+        let node_of = |code: u64| match code {
+            code if code < CLEAR => code as usize + 1,
+            code => code as usize,
+        };
+
+        // next_code follows the dictionary of the encoder, which is one phrase ahead of this one
+        let mut next_code = FIRST_CODE;
+        let mut previous: Option<usize> = None;
+        let mut decoded = Vec::new();
+
+        // the padding of the last byte is always shorter than a code
+        while reader.remaining() >= Self::code_width(next_code) as usize {
+            let code = reader.read_bits(Self::code_width(next_code)).unwrap();
+            if code == CLEAR {
+                dictionary = DictionaryTrie::with_single_bytes();
+                next_code = FIRST_CODE;
+                previous = None;
+                continue;
+            }
+
+            // if the code is in the trie, its phrase starts with the first byte of its node
+            // else it is the code being built, the previous phrase followed by its own first byte
+            let node = node_of(code);
+            let first_byte = match previous {
+                _ if node < dictionary.len() => dictionary.first_byte(node),
+                Some(previous) if node == dictionary.len() && code == next_code - 1 => {
+                    dictionary.first_byte(previous)
                 }
-                None => {
-                    current_decoded_string.push_str(&first_char_decoded_string);
+                _ => return Err(corrupted()),
+            };
+
+            // the new entry is the previous phrase followed by the first byte of the current one
+            if let Some(previous) = previous {
+                if dictionary.len() < 1 << max_bits {
+                    dictionary.insert(previous, first_byte);
                 }
             }
-            // update the first character of the decoded string
-            first_char_decoded_string = current_decoded_string.chars().next().unwrap().to_string();
-            // create a new string to be inserted into the hashmap
-            let v = format!(
-                "{}{}",
-                hashmap.get(&current_encoded_index).unwrap(),
-                first_char_decoded_string
-            );
-            // add the current decoded string to the output
-            self.decoded.push_str(&current_decoded_string);
-            // insert the new string into the hashmap
-            hashmap.insert(count, v);
-            count += 1;
-            // update the current encoded index
-            current_encoded_index = next_encoded_value;
+
+            // add the current phrase to the output
+            check_decoded_size(decoded.len() + dictionary.phrase_len(node), max_size)?;
+            dictionary.append_phrase(node, &mut decoded);
+            if next_code < 1 << max_bits {
+                next_code += 1;
+            }
+            previous = Some(node);
         }
 
-        // add the last decoded string to the output
-        format!(
-            "{}{}",
-            hashmap.get(&current_encoded_index).unwrap(),
-            first_char_decoded_string
-        );
+        Ok(decoded)
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::{binary_samples, XorShift};
 
-    fn compressed(&self) -> String {
-        self.encoded
-            .iter()
-            .map(|&num| num.to_string())
-            .collect::<Vec<String>>()
-            .join(" ")
+    #[test]
+    fn lzw_works() {
+        let test_cases = vec![
+            "abracadabra!",
+            "aabbc",
+            "TOBEORNOTTOBEORTOBEORNOT",
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            "a",
+            "",
+        ];
+
+        for test_case in test_cases {
+            let encoded = LempelZivWelch::new().encode(test_case.as_bytes()).unwrap();
+            let decoded = LempelZivWelch::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case.as_bytes());
+        }
     }
 
-    fn decompressed(&self) -> String {
-        self.decoded.clone()
+    #[test]
+    fn repetitive_text_is_compressed() {
+        let text = "TOBEORNOTTOBEORTOBEORNOT".repeat(64);
+        let mut lzw = LempelZivWelch::new();
+        let encoded = lzw.encode(text.as_bytes()).unwrap();
+        assert!(encoded.len() < text.len() / 4);
+    }
+
+    #[test]
+    fn binary_data_works() {
+        for sample in binary_samples() {
+            let encoded = LempelZivWelch::new().encode(&sample).unwrap();
+            let decoded = LempelZivWelch::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, sample);
+        }
+    }
+
+    #[test]
+    fn codes_grow_from_nine_bits() {
+        // the header and two 9 bit codes
+        let encoded = LempelZivWelch::new().encode(b"ab").unwrap();
+        assert_eq!(encoded.len(), 1 + 3);
+
+        let mut reader = BitReader::new(&encoded);
+        assert_eq!(reader.read_bits(MAX_BITS_WIDTH), Some(16));
+        assert_eq!(reader.read_bits(9), Some(b'a' as u64));
+        assert_eq!(reader.read_bits(9), Some(b'b' as u64));
+
+        // codes take 10 bits once the decoder may receive code 512
+        assert_eq!(LempelZivWelch::code_width(FIRST_CODE), 9);
+        assert_eq!(LempelZivWelch::code_width(512), 9);
+        assert_eq!(LempelZivWelch::code_width(513), 10);
     }
-}
 
+    #[test]
+    fn full_dictionary_is_cleared() {
+        let mut rng = XorShift::new(9);
+        let mut test_cases = vec![
+            (0..200_000)
+                .map(|_| b'a' + (rng.next_u64() % 4) as u8)
+                .collect::<Vec<u8>>(),
+            rng.bytes(50_000),
+            "TOBEORNOTTOBEORTOBEORNOT".repeat(2000).into_bytes(),
+        ];
+        test_cases.extend(binary_samples());
+
+        for max_bits in [9, 10, 12, 16] {
+            for test_case in test_cases.iter() {
+                let encoded = LempelZivWelch::with_max_bits(max_bits)
+                    .unwrap()
+                    .encode(test_case)
+                    .unwrap();
+                let decoded = LempelZivWelch::new().decode(&encoded).unwrap();
+                assert_eq!(decoded, *test_case);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_code_widths_are_rejected() {
+        for max_bits in [0, MIN_BITS - 1, MAX_BITS + 1, u32::MAX] {
+            let err = LempelZivWelch::with_max_bits(max_bits).err().unwrap();
+            assert_eq!(err.kind, CodecErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn long_phrases_work() {
+        // a run grows the phrases by one byte at every code, up to thousands of bytes
+        let data = vec![0u8; 4_000_000];
+        let encoded = LempelZivWelch::new().encode(&data).unwrap();
+        assert!(encoded.len() < 8_000);
+        let decoded = LempelZivWelch::new().decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut lzw = LempelZivWelch::new();
+        assert!(lzw.decode(&[8]).is_err());
+        assert!(lzw.decode(&[25]).is_err());
+
+        // the first code cannot refer to a phrase
+        let mut writer = BitWriter::new();
+        writer.write_bits(16, MAX_BITS_WIDTH);
+        writer.write_bits(FIRST_CODE, 9);
+        assert!(lzw.decode(&writer.finish()).is_err());
+    }
+}
@@ -0,0 +1,510 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::byte_cursor::ByteCursor,
+    pkg::traits::{check_decoded_size, Codec},
+};
+use crate::utils::checksum::xxh32;
+
+/// MAGIC identifies an LZ4 frame
+pub const MAGIC: [u8; 4] = 0x184d_2204u32.to_le_bytes();
+
+/// SKIPPABLE_MAGIC is the magic of a skippable frame, with any value in its lowest 4 bits
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a50;
+
+/// MIN_MATCH is the length of the shortest match
+const MIN_MATCH: usize = 4;
+
+/// LAST_LITERALS is the number of bytes at the end of a block that are always literals
+const LAST_LITERALS: usize = 5;
+
+/// MF_LIMIT is the distance from the end of a block within which no match can start
+const MF_LIMIT: usize = 12;
+
+/// MAX_OFFSET is the farthest distance a match can reach back
+const MAX_OFFSET: usize = u16::MAX as usize;
+
+/// HASH_BITS is the width of the hash table of the match finder
+const HASH_BITS: u32 = 12;
+
+/// SKIP_TRIGGER makes the match finder step faster over data without matches,
+/// moving one more byte at a time every 2^SKIP_TRIGGER misses
+const SKIP_TRIGGER: u32 = 6;
+
+/// BLOCK_SIZE is the size of the blocks written, the smallest maximum of the frame format
+const BLOCK_SIZE: usize = 1 << 16;
+
+/// the bits of the flag byte of a frame descriptor
+const VERSION: u8 = 0b01 << 6;
+const VERSION_MASK: u8 = 0b11 << 6;
+const FLAG_INDEPENDENT_BLOCKS: u8 = 1 << 5;
+const FLAG_BLOCK_CHECKSUM: u8 = 1 << 4;
+const FLAG_CONTENT_SIZE: u8 = 1 << 3;
+const FLAG_CONTENT_CHECKSUM: u8 = 1 << 2;
+const FLAG_RESERVED: u8 = 1 << 1;
+const FLAG_DICTIONARY_ID: u8 = 1;
+
+/// UNCOMPRESSED marks a block stored as it is in its size
+const UNCOMPRESSED: u32 = 1 << 31;
+
+/// is_lz4 returns true if the bytes start like an LZ4 frame
+pub fn is_lz4(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// corrupted returns the error of data that is not a valid LZ4 frame or block
+fn corrupted(message: &str) -> CodecError {
+    CodecError::new(message, CodecErrorKind::CorruptedData)
+}
+
+/// Lz4 represents the LZ4 frame format, read and written by the `lz4` tool. It trades ratio
+/// for speed: the match finder probes a single earlier position per hash, skips ahead faster
+/// the longer it goes without a match, and matches are written as bytes rather than bits.
+///
+/// The frame starts with its magic and a descriptor: the flags, the largest block size, the
+/// length of the data and a checksum of the descriptor. It is followed by independent blocks
+/// of up to 64 KiB, each stored as is when it does not compress, an empty end mark and the
+/// xxHash32 of the data. Decoding also reads linked blocks, block checksums, skippable frames
+/// and frames following each other.
+#[derive(Default)]
+pub struct Lz4 {}
+
+impl Lz4 {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// hash returns the slot of the 4 bytes at position in the hash table
+    fn hash(data: &[u8], position: usize) -> usize {
+        let bytes = u32::from_le_bytes(data[position..position + 4].try_into().unwrap());
+        (bytes.wrapping_mul(0x9e37_79b1) >> (u32::BITS - HASH_BITS)) as usize
+    }
+
+    /// compress_block compresses data into an LZ4 block, a list of sequences of literals
+    /// followed by a match, the last sequence holding only literals
+    pub fn compress_block(data: &[u8]) -> Vec<u8> {
+        let mut block = Vec::with_capacity(data.len() + data.len() / 255 + 16);
+        let mut anchor = 0;
+
+        if data.len() > MF_LIMIT {
+            let mut table = vec![usize::MAX; 1 << HASH_BITS];
+            let match_limit = data.len() - MF_LIMIT;
+            let extend_limit = data.len() - LAST_LITERALS;
+
+            let mut position = 0;
+            let mut misses = 0;
+            while position < match_limit {
+                let slot = Self::hash(data, position);
+                let candidate = table[slot];
+                table[slot] = position;
+
+                let found = candidate != usize::MAX
+                    && position - candidate <= MAX_OFFSET
+                    && data[candidate..candidate + MIN_MATCH]
+                        == data[position..position + MIN_MATCH];
+                if !found {
+                    misses += 1;
+                    position += 1 + (misses >> SKIP_TRIGGER);
+                    continue;
+                }
+
+                // grow the match backwards over the pending literals, then forwards
+                let (mut start, mut candidate) = (position, candidate);
+                while start > anchor && candidate > 0 && data[start - 1] == data[candidate - 1] {
+                    start -= 1;
+                    candidate -= 1;
+                }
+                let length = data[candidate..extend_limit]
+                    .iter()
+                    .zip(&data[start..extend_limit])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                Self::write_sequence(
+                    &mut block,
+                    &data[anchor..start],
+                    Some((start - candidate, length)),
+                );
+                position = start + length;
+                anchor = position;
+                misses = 0;
+            }
+        }
+
+        Self::write_sequence(&mut block, &data[anchor..], None);
+        block
+    }
+
+    /// write_sequence writes the token, the literals and the match of a sequence. The token
+    /// holds up to 15 in each half, and longer lengths go on in bytes of up to 255
+    fn write_sequence(block: &mut Vec<u8>, literals: &[u8], found: Option<(usize, usize)>) {
+        let match_length = found.map_or(0, |(_, length)| length - MIN_MATCH);
+        block.push((literals.len().min(15) as u8) << 4 | match_length.min(15) as u8);
+        if literals.len() >= 15 {
+            Self::write_length(block, literals.len() - 15);
+        }
+        block.extend_from_slice(literals);
+
+        if let Some((offset, _)) = found {
+            block.extend_from_slice(&(offset as u16).to_le_bytes());
+            if match_length >= 15 {
+                Self::write_length(block, match_length - 15);
+            }
+        }
+    }
+
+    /// write_length writes the rest of a length that did not fit in the token
+    fn write_length(block: &mut Vec<u8>, length: usize) {
+        block.extend(std::iter::repeat_n(u8::MAX, length / 255));
+        block.push((length % 255) as u8);
+    }
+
+    /// decompress_block decodes an LZ4 block of at most max_size bytes
+    pub fn decompress_block(block: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut decoded = Vec::new();
+        Self::decompress_block_into(block, &mut decoded, 0, max_size)?;
+        Ok(decoded)
+    }
+
+    /// decompress_block_into appends a decoded block of at most max_size bytes to output.
+    /// Matches can reach back into the last `history` bytes before the block
+    fn decompress_block_into(
+        block: &[u8],
+        output: &mut Vec<u8>,
+        history: usize,
+        max_size: usize,
+    ) -> Result<(), CodecError> {
+        let truncated = || corrupted("lz4 block ended in the middle of a sequence");
+        let window_start = output.len() - history;
+        let limit = output.len() + max_size;
+
+        let mut cursor = ByteCursor::new(block);
+        loop {
+            let token = cursor.read_u8().map_err(|_| truncated())?;
+            let mut literal_length = (token >> 4) as usize;
+            if literal_length == 15 {
+                literal_length += Self::read_length(&mut cursor)?;
+            }
+            if output.len() + literal_length > limit {
+                return Err(corrupted("lz4 block is larger than its maximum size"));
+            }
+            output.extend_from_slice(cursor.read_bytes(literal_length).map_err(|_| truncated())?);
+
+            // the last sequence has no match
+            if cursor.remaining().is_empty() {
+                return Ok(());
+            }
+
+            let offset = cursor.read_u16_le().map_err(|_| truncated())? as usize;
+            let mut match_length = (token & 0x0f) as usize;
+            if match_length == 15 {
+                match_length += Self::read_length(&mut cursor)?;
+            }
+            match_length += MIN_MATCH;
+            if offset == 0 || offset > output.len() - window_start {
+                return Err(corrupted("lz4 match points outside of the data"));
+            }
+            if output.len() + match_length > limit {
+                return Err(corrupted("lz4 block is larger than its maximum size"));
+            }
+
+            // the match may overlap the bytes it produces, so copy one byte at a time
+            let start = output.len() - offset;
+            for i in 0..match_length {
+                output.push(output[start + i]);
+            }
+        }
+    }
+
+    /// read_length reads the bytes of a length that did not fit in the token
+    fn read_length(cursor: &mut ByteCursor) -> Result<usize, CodecError> {
+        let mut length = 0;
+        loop {
+            let byte = cursor
+                .read_u8()
+                .map_err(|_| corrupted("lz4 block ended in the middle of a length"))?;
+            length += byte as usize;
+            if byte != u8::MAX {
+                return Ok(length);
+            }
+        }
+    }
+
+    /// block_size_code returns the code of the descriptor for a largest block size
+    fn block_size_code(block_size: usize) -> u8 {
+        match block_size {
+            0..=0x1_0000 => 4,
+            0x1_0001..=0x4_0000 => 5,
+            0x4_0001..=0x10_0000 => 6,
+            _ => 7,
+        }
+    }
+
+    /// decode_frame decodes the frame at the cursor, and returns nothing for a skippable frame.
+    /// It fails when the decoded data grows past max_size bytes
+    fn decode_frame(
+        cursor: &mut ByteCursor,
+        decoded: &mut Vec<u8>,
+        max_size: usize,
+    ) -> Result<(), CodecError> {
+        let magic = cursor.read_u32_le()?;
+        if magic & !0x0f == SKIPPABLE_MAGIC {
+            let length = cursor.read_u32_le()?;
+            cursor.read_bytes(length as usize)?;
+            return Ok(());
+        }
+        if magic.to_le_bytes() != MAGIC {
+            return Err(CodecError::new(
+                "data is not an lz4 frame",
+                CodecErrorKind::InvalidMagic,
+            ));
+        }
+
+        let descriptor_start = cursor.remaining();
+        let flags = cursor.read_u8()?;
+        let block_descriptor = cursor.read_u8()?;
+        if flags & VERSION_MASK != VERSION {
+            return Err(CodecError::new(
+                "lz4 frame version is not supported",
+                CodecErrorKind::UnsupportedVersion,
+            ));
+        }
+        if flags & FLAG_RESERVED != 0 || block_descriptor & 0x8f != 0 {
+            return Err(corrupted("lz4 frame has reserved bits set"));
+        }
+        let block_size_code = block_descriptor >> 4;
+        if block_size_code < 4 {
+            return Err(corrupted("lz4 frame block size is not valid"));
+        }
+        let max_block_size = 1usize << (2 * block_size_code as usize + 8);
+
+        let content_size = match flags & FLAG_CONTENT_SIZE {
+            0 => None,
+            _ => Some(cursor.read_u64_le()?),
+        };
+        if flags & FLAG_DICTIONARY_ID != 0 {
+            return Err(CodecError::new(
+                "lz4 frames with a dictionary are not supported",
+                CodecErrorKind::InvalidInput,
+            ));
+        }
+        let descriptor = &descriptor_start[..descriptor_start.len() - cursor.remaining().len()];
+        if cursor.read_u8()? != (xxh32(descriptor, 0) >> 8) as u8 {
+            return Err(CodecError::new(
+                "lz4 frame descriptor does not match its checksum",
+                CodecErrorKind::ChecksumMismatch,
+            ));
+        }
+
+        let frame_start = decoded.len();
+        loop {
+            let size = cursor.read_u32_le()?;
+            if size == 0 {
+                break;
+            }
+
+            let length = (size & !UNCOMPRESSED) as usize;
+            if length > max_block_size {
+                return Err(corrupted("lz4 block is larger than its maximum size"));
+            }
+            let block = cursor.read_bytes(length)?;
+            if flags & FLAG_BLOCK_CHECKSUM != 0 && cursor.read_u32_le()? != xxh32(block, 0) {
+                return Err(CodecError::new(
+                    "lz4 block does not match its checksum",
+                    CodecErrorKind::ChecksumMismatch,
+                ));
+            }
+
+            // linked blocks can refer to the last 64 KiB of the frame
+            let history = match flags & FLAG_INDEPENDENT_BLOCKS {
+                0 => (decoded.len() - frame_start).min(MAX_OFFSET),
+                _ => 0,
+            };
+            match size & UNCOMPRESSED {
+                0 => {
+                    let block_limit = max_block_size.min(max_size - decoded.len());
+                    Self::decompress_block_into(block, decoded, history, block_limit)?;
+                }
+                _ => {
+                    check_decoded_size(decoded.len() + block.len(), max_size)?;
+                    decoded.extend_from_slice(block);
+                }
+            }
+        }
+
+        let frame = &decoded[frame_start..];
+        if content_size.is_some_and(|size| size != frame.len() as u64) {
+            return Err(corrupted("lz4 frame does not match its content size"));
+        }
+        if flags & FLAG_CONTENT_CHECKSUM != 0 && cursor.read_u32_le()? != xxh32(frame, 0) {
+            return Err(CodecError::new(
+                "lz4 frame does not match its checksum",
+                CodecErrorKind::ChecksumMismatch,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Codec for Lz4 {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = MAGIC.to_vec();
+        let flags = VERSION | FLAG_INDEPENDENT_BLOCKS | FLAG_CONTENT_SIZE | FLAG_CONTENT_CHECKSUM;
+        let descriptor_start = encoded.len();
+        encoded.push(flags);
+        encoded.push(Self::block_size_code(BLOCK_SIZE) << 4);
+        encoded.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        encoded.push((xxh32(&encoded[descriptor_start..], 0) >> 8) as u8);
+
+        for chunk in data.chunks(BLOCK_SIZE) {
+            let block = Self::compress_block(chunk);
+            match block.len() < chunk.len() {
+                true => {
+                    encoded.extend_from_slice(&(block.len() as u32).to_le_bytes());
+                    encoded.extend(block);
+                }
+                false => {
+                    encoded.extend_from_slice(&(chunk.len() as u32 | UNCOMPRESSED).to_le_bytes());
+                    encoded.extend_from_slice(chunk);
+                }
+            }
+        }
+
+        encoded.extend_from_slice(&0u32.to_le_bytes());
+        encoded.extend_from_slice(&xxh32(data, 0).to_le_bytes());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let mut decoded = Vec::new();
+
+        // the frames follow each other up to the end of the data
+        Self::decode_frame(&mut cursor, &mut decoded, max_size)?;
+        while !cursor.remaining().is_empty() {
+            Self::decode_frame(&mut cursor, &mut decoded, max_size)?;
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::lempel_ziv_storer_szymanski::LempelZivStorerSzymanski;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    const LINES: &[u8] = include_bytes!("../../../test_data/deflate/dynamic.txt");
+
+    #[test]
+    fn lz4_files_are_read() {
+        // written by the lz4 tool with its defaults: independent blocks and a content checksum
+        let mut lz4 = Lz4::new();
+        let lines = include_bytes!("../../../test_data/lz4/lines.lz4");
+        assert_eq!(lz4.decode(lines).unwrap(), LINES);
+
+        // lz4 -9 -B4 -BD -BX --content-size: linked blocks of 64 KiB with checksums
+        let linked = include_bytes!("../../../test_data/lz4/linked.lz4");
+        assert_eq!(lz4.decode(linked).unwrap(), LINES.repeat(4));
+
+        // data that does not compress is stored in an uncompressed block
+        let random = include_bytes!("../../../test_data/lz4/random.lz4");
+        assert_eq!(
+            lz4.decode(random).unwrap(),
+            include_bytes!("../../../test_data/lz4/random.bin")
+        );
+
+        let empty = include_bytes!("../../../test_data/lz4/empty.lz4");
+        assert_eq!(lz4.decode(empty).unwrap(), b"");
+    }
+
+    #[test]
+    fn lz4_works() {
+        let mut rng = XorShift::new(8);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"abcabcabcabcabc".to_vec(),
+            vec![0u8; 300_000],
+            rng.bytes(100_000),
+            (0..200_000)
+                .map(|_| b'a' + (rng.next_u64() % 3) as u8)
+                .collect(),
+            LINES.repeat(6),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = Lz4::new().encode(&test_case).unwrap();
+            assert!(is_lz4(&encoded));
+            assert_eq!(Lz4::new().decode(&encoded).unwrap(), test_case);
+        }
+    }
+
+    #[test]
+    fn blocks_follow_the_format() {
+        // the last 5 bytes are literals and no match starts in the last 12 bytes
+        let data = b"abcdabcdabcdabcdabcdabcd";
+        let block = Lz4::compress_block(data);
+        assert_eq!(
+            block,
+            [&[0x4b, b'a', b'b', b'c', b'd', 4, 0, 0x50][..], &data[19..]].concat()
+        );
+        assert_eq!(Lz4::decompress_block(&block, data.len()).unwrap(), data);
+
+        // short data is a single sequence of literals
+        assert_eq!(Lz4::compress_block(b"abc"), vec![0x30, b'a', b'b', b'c']);
+        assert_eq!(Lz4::compress_block(b""), vec![0]);
+
+        // lengths longer than the token are written in bytes of up to 255
+        let zeros = vec![0u8; 1000];
+        let block = Lz4::compress_block(&zeros);
+        assert_eq!(&block[..4], &[0x1f, 0, 1, 0]);
+        assert_eq!(Lz4::decompress_block(&block, zeros.len()).unwrap(), zeros);
+    }
+
+    #[test]
+    fn lzss_compresses_more_than_lz4() {
+        // the match finder of lz4 probes one position where lzss walks a hash chain
+        let data = LINES.repeat(6);
+        let lz4 = Lz4::new().encode(&data).unwrap();
+        let lzss = LempelZivStorerSzymanski::new().encode(&data).unwrap();
+        assert!(lz4.len() < data.len() / 2);
+        assert!(lzss.len() < lz4.len());
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut lz4 = Lz4::new();
+        let encoded = lz4.encode(&LINES[..2000]).unwrap();
+        assert_eq!(
+            lz4.decode(b"TCZ\x1a").unwrap_err().kind,
+            CodecErrorKind::InvalidMagic
+        );
+        assert!(lz4.decode(&[]).is_err());
+        assert!(lz4.decode(&encoded[..encoded.len() - 1]).is_err());
+
+        // a flipped descriptor, block or checksum byte
+        for position in [6, 30, encoded.len() - 1] {
+            let mut corrupted = encoded.clone();
+            corrupted[position] ^= 0x10;
+            assert!(lz4.decode(&corrupted).is_err());
+        }
+        let mut corrupted = encoded.clone();
+        corrupted[6] ^= 0x10;
+        assert_eq!(
+            lz4.decode(&corrupted).unwrap_err().kind,
+            CodecErrorKind::ChecksumMismatch
+        );
+
+        // a match before any byte was decoded, and a match past the maximum size
+        assert!(Lz4::decompress_block(&[0x00, 1, 0, 0x10], 100).is_err());
+        assert!(Lz4::decompress_block(&[0x1f, b'a', 1, 0, 200, 0x10], 100).is_err());
+        assert!(Lz4::decompress_block(&[], 100).is_err());
+
+        // a skippable frame before a frame is skipped
+        let skippable = [&0x184d_2a5au32.to_le_bytes()[..], &[2, 0, 0, 0, 7, 7]].concat();
+        let data = [&skippable[..], &encoded].concat();
+        assert_eq!(lz4.decode(&data).unwrap(), &LINES[..2000]);
+    }
+}
@@ -1,18 +1,54 @@
-pub mod burrows_wheeler_run_length;
+pub mod adaptive_huffman;
+pub mod asymmetric_numeral_systems;
+pub mod block_sort;
 pub mod burrows_wheeler_transform;
+pub mod deflate;
+pub mod gzip;
 pub mod huffman;
+pub mod huffman_encoding_run_length;
+pub mod lempel_ziv_78;
+pub mod lempel_ziv_storer_szymanski;
 pub mod lempel_ziv_welch;
+pub mod lz4;
+pub mod move_to_front;
+pub mod pipeline;
+pub mod range_coding;
 pub mod run_length_encoding;
+pub mod snappy;
+pub mod zlib;
 
+use crate::service::data_structures::canonical_code::MAX_CODE_LENGTH;
 use std::fmt::{Debug, Display, Formatter};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum Algorithm {
     Rle,
+    #[default]
     Huffman,
     Bwt,
     Lzw,
     BwtRle,
+    BwtSais,
+    BlockSort,
+    AdaptiveHuffman,
+    Range,
+    Rans,
+    Tans,
+    Lzss,
+    Lz78,
+    Deflate,
+    Gzip,
+    Zlib,
+    Lz4,
+    Snappy,
+    SnappyRaw,
+    Mtf,
+    Pipeline(Vec<Algorithm>),
+    HuffmanRle,
+    PackBits,
+    RleEscape,
+    /// LimitedHuffman is Huffman coding with codes of at most the given number of bits
+    LimitedHuffman(u8),
     All,
     Invalid,
 }
@@ -25,34 +61,166 @@ impl Algorithm {
             Algorithm::Bwt => "Burrows Wheeler Transform".to_string(),
             Algorithm::Lzw => "Lempel Ziv Welch".to_string(),
             Algorithm::BwtRle => "Burrows Wheeler Run Length".to_string(),
+            Algorithm::BwtSais => "Burrows Wheeler Transform (SA-IS)".to_string(),
+            Algorithm::BlockSort => "Block Sorting (BWT, MTF, RLE, Huffman)".to_string(),
+            Algorithm::AdaptiveHuffman => "Adaptive Huffman Encoding (FGK)".to_string(),
+            Algorithm::Range => "Range Coding (adaptive order-0)".to_string(),
+            Algorithm::Rans => "Range Asymmetric Numeral Systems (rANS)".to_string(),
+            Algorithm::Tans => "Table Asymmetric Numeral Systems (tANS)".to_string(),
+            Algorithm::Lzss => "Lempel Ziv Storer Szymanski (LZ77)".to_string(),
+            Algorithm::Lz78 => "Lempel Ziv 78".to_string(),
+            Algorithm::Deflate => "DEFLATE (LZ77 and Huffman, RFC 1951)".to_string(),
+            Algorithm::Gzip => "gzip (DEFLATE, RFC 1952)".to_string(),
+            Algorithm::Zlib => "zlib (DEFLATE, RFC 1950)".to_string(),
+            Algorithm::Lz4 => "LZ4 (frame format)".to_string(),
+            Algorithm::Snappy => "Snappy (framing format)".to_string(),
+            Algorithm::SnappyRaw => "Snappy (raw format)".to_string(),
+            Algorithm::Mtf => "Move to Front".to_string(),
+            Algorithm::Pipeline(_) => format!("Pipeline ({})", self),
+            Algorithm::HuffmanRle => "Run Length and Huffman Encoding".to_string(),
+            Algorithm::PackBits => "PackBits Run Length Encoding".to_string(),
+            Algorithm::RleEscape => "Escape Byte Run Length Encoding".to_string(),
+            Algorithm::LimitedHuffman(max_code_length) => format!(
+                "Length Limited Huffman Encoding ({} bit codes)",
+                max_code_length
+            ),
             Algorithm::All => "ALL".to_string(),
             Algorithm::Invalid => "invalid".to_string(),
         }
     }
-}
 
-impl Debug for Algorithm {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+    /// id returns the identifier stored for the algorithm in compressed files
+    pub fn id(&self) -> Option<u8> {
+        match self {
+            Algorithm::Rle => Some(1),
+            Algorithm::Huffman => Some(2),
+            Algorithm::Bwt => Some(3),
+            Algorithm::Lzw => Some(4),
+            Algorithm::BwtRle => Some(5),
+            Algorithm::BwtSais => Some(6),
+            Algorithm::BlockSort => Some(7),
+            Algorithm::AdaptiveHuffman => Some(8),
+            Algorithm::Range => Some(9),
+            Algorithm::Rans => Some(10),
+            Algorithm::Tans => Some(11),
+            Algorithm::Lzss => Some(12),
+            Algorithm::Lz78 => Some(13),
+            Algorithm::Deflate => Some(14),
+            Algorithm::Gzip => Some(15),
+            Algorithm::Zlib => Some(16),
+            Algorithm::Lz4 => Some(17),
+            Algorithm::Snappy => Some(18),
+            Algorithm::SnappyRaw => Some(19),
+            Algorithm::Mtf => Some(20),
+            Algorithm::Pipeline(_) => Some(21),
+            Algorithm::HuffmanRle => Some(22),
+            Algorithm::PackBits => Some(23),
+            Algorithm::RleEscape => Some(24),
+            // the limit only changes the code lengths written in the payload, which the
+            // Huffman decoder reads as they are
+            Algorithm::LimitedHuffman(_) => Some(2),
+            Algorithm::All | Algorithm::Invalid => None,
+        }
+    }
+
+    /// from_id returns the algorithm stored under an identifier in compressed files
+    pub fn from_id(id: u8) -> Option<Algorithm> {
+        match id {
+            1 => Some(Algorithm::Rle),
+            2 => Some(Algorithm::Huffman),
+            3 => Some(Algorithm::Bwt),
+            4 => Some(Algorithm::Lzw),
+            5 => Some(Algorithm::BwtRle),
+            6 => Some(Algorithm::BwtSais),
+            7 => Some(Algorithm::BlockSort),
+            8 => Some(Algorithm::AdaptiveHuffman),
+            9 => Some(Algorithm::Range),
+            10 => Some(Algorithm::Rans),
+            11 => Some(Algorithm::Tans),
+            12 => Some(Algorithm::Lzss),
+            13 => Some(Algorithm::Lz78),
+            14 => Some(Algorithm::Deflate),
+            15 => Some(Algorithm::Gzip),
+            16 => Some(Algorithm::Zlib),
+            17 => Some(Algorithm::Lz4),
+            18 => Some(Algorithm::Snappy),
+            19 => Some(Algorithm::SnappyRaw),
+            20 => Some(Algorithm::Mtf),
+            // the stages of a pipeline are written in its payload
+            21 => Some(Algorithm::Pipeline(vec![])),
+            22 => Some(Algorithm::HuffmanRle),
+            23 => Some(Algorithm::PackBits),
+            24 => Some(Algorithm::RleEscape),
+            _ => None,
+        }
+    }
+
+    /// is_standalone returns true for formats read by other tools, which are written
+    /// as they are rather than inside a container
+    pub fn is_standalone(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::Gzip | Algorithm::Zlib | Algorithm::Lz4 | Algorithm::Snappy
+        )
     }
 }
 
-impl Default for Algorithm {
-    fn default() -> Self {
-        Algorithm::Huffman
+impl Debug for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
     }
 }
 
 impl From<String> for Algorithm {
     fn from(value: String) -> Self {
+        if value.contains(pipeline::SEPARATOR) {
+            return pipeline::parse_stages(&value).map_or(Algorithm::Invalid, Algorithm::Pipeline);
+        }
+
         match value.to_ascii_lowercase().as_str() {
             "rle" => Algorithm::Rle,
             "huffman" => Algorithm::Huffman,
             "bwt" => Algorithm::Bwt,
             "lzw" => Algorithm::Lzw,
             "bwtrle" => Algorithm::BwtRle,
+            "bwtsais" => Algorithm::BwtSais,
+            "blocksort" => Algorithm::BlockSort,
+            "adaptivehuffman" => Algorithm::AdaptiveHuffman,
+            "range" => Algorithm::Range,
+            "rans" => Algorithm::Rans,
+            "tans" => Algorithm::Tans,
+            "lzss" => Algorithm::Lzss,
+            "lz78" => Algorithm::Lz78,
+            "deflate" => Algorithm::Deflate,
+            "gzip" => Algorithm::Gzip,
+            "zlib" => Algorithm::Zlib,
+            "lz4" => Algorithm::Lz4,
+            "snappy" => Algorithm::Snappy,
+            "snappyraw" => Algorithm::SnappyRaw,
+            "mtf" => Algorithm::Mtf,
+            "huffmanrle" => Algorithm::HuffmanRle,
+            "packbits" => Algorithm::PackBits,
+            "rleescape" => Algorithm::RleEscape,
             "all" => Algorithm::All,
-            _ => Algorithm::Invalid,
+            name => Self::parse_limited_huffman(name).unwrap_or(Algorithm::Invalid),
+        }
+    }
+}
+
+impl Algorithm {
+    /// parse_limited_huffman reads a name such as huffman15, Huffman with codes of at most
+    /// 1 to MAX_CODE_LENGTH bits
+    fn parse_limited_huffman(name: &str) -> Option<Algorithm> {
+        let limit = name.strip_prefix("huffman")?;
+        if limit.is_empty() || !limit.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        match limit.parse::<u8>().ok()? {
+            limit if (1..=MAX_CODE_LENGTH as u8).contains(&limit) => {
+                Some(Algorithm::LimitedHuffman(limit))
+            }
+            _ => None,
         }
     }
 }
@@ -65,6 +233,32 @@ impl Display for Algorithm {
             Algorithm::Bwt => "BWT".to_string(),
             Algorithm::Lzw => "LZW".to_string(),
             Algorithm::BwtRle => "BWTRLE".to_string(),
+            Algorithm::BwtSais => "BWTSAIS".to_string(),
+            Algorithm::BlockSort => "BLOCKSORT".to_string(),
+            Algorithm::AdaptiveHuffman => "AdaptiveHuffman".to_string(),
+            Algorithm::Range => "RANGE".to_string(),
+            Algorithm::Rans => "RANS".to_string(),
+            Algorithm::Tans => "TANS".to_string(),
+            Algorithm::Lzss => "LZSS".to_string(),
+            Algorithm::Lz78 => "LZ78".to_string(),
+            Algorithm::Deflate => "DEFLATE".to_string(),
+            Algorithm::Gzip => "GZIP".to_string(),
+            Algorithm::Zlib => "ZLIB".to_string(),
+            Algorithm::Lz4 => "LZ4".to_string(),
+            Algorithm::Snappy => "SNAPPY".to_string(),
+            Algorithm::SnappyRaw => "SNAPPYRAW".to_string(),
+            Algorithm::Mtf => "MTF".to_string(),
+            Algorithm::HuffmanRle => "HUFFMANRLE".to_string(),
+            Algorithm::PackBits => "PACKBITS".to_string(),
+            Algorithm::RleEscape => "RLEESCAPE".to_string(),
+            Algorithm::LimitedHuffman(max_code_length) => format!("Huffman{}", max_code_length),
+            // a pipeline read from a compressed file has its stages in its payload
+            Algorithm::Pipeline(stages) if stages.is_empty() => "PIPELINE".to_string(),
+            Algorithm::Pipeline(stages) => stages
+                .iter()
+                .map(|stage| stage.to_string())
+                .collect::<Vec<String>>()
+                .join(&pipeline::SEPARATOR.to_string()),
             Algorithm::All => "ALL".to_string(),
             Algorithm::Invalid => "invalid".to_string(),
         };
@@ -74,7 +268,7 @@ impl Display for Algorithm {
 
 impl PartialEq for Algorithm {
     fn eq(&self, other: &Self) -> bool {
-        return self.to_string() == other.to_string();
+        self.to_string() == other.to_string()
     }
 }
 
@@ -96,6 +290,30 @@ mod test {
             (Algorithm::Bwt, "BWT"),
             (Algorithm::All, "ALL"),
             (Algorithm::Lzw, "LZW"),
+            (Algorithm::BwtSais, "BWTSAIS"),
+            (Algorithm::BlockSort, "BLOCKSORT"),
+            (Algorithm::AdaptiveHuffman, "AdaptiveHuffman"),
+            (Algorithm::Range, "RANGE"),
+            (Algorithm::Rans, "RANS"),
+            (Algorithm::Tans, "TANS"),
+            (Algorithm::Lzss, "LZSS"),
+            (Algorithm::Lz78, "LZ78"),
+            (Algorithm::Deflate, "DEFLATE"),
+            (Algorithm::Gzip, "GZIP"),
+            (Algorithm::Zlib, "ZLIB"),
+            (Algorithm::Lz4, "LZ4"),
+            (Algorithm::Snappy, "SNAPPY"),
+            (Algorithm::SnappyRaw, "SNAPPYRAW"),
+            (Algorithm::Mtf, "MTF"),
+            (Algorithm::HuffmanRle, "HUFFMANRLE"),
+            (Algorithm::PackBits, "PACKBITS"),
+            (Algorithm::RleEscape, "RLEESCAPE"),
+            (Algorithm::LimitedHuffman(15), "Huffman15"),
+            (
+                Algorithm::Pipeline(vec![Algorithm::Bwt, Algorithm::Mtf, Algorithm::Huffman]),
+                "BWT|MTF|Huffman",
+            ),
+            (Algorithm::Pipeline(vec![]), "PIPELINE"),
             (Algorithm::Invalid, "invalid"),
         ];
 
@@ -104,6 +322,57 @@ mod test {
         }
     }
 
+    #[test]
+    fn algorithm_id_works() {
+        let algorithms = vec![
+            Algorithm::Rle,
+            Algorithm::Huffman,
+            Algorithm::Bwt,
+            Algorithm::Lzw,
+            Algorithm::BwtRle,
+            Algorithm::BwtSais,
+            Algorithm::BlockSort,
+            Algorithm::AdaptiveHuffman,
+            Algorithm::Range,
+            Algorithm::Rans,
+            Algorithm::Tans,
+            Algorithm::Lzss,
+            Algorithm::Lz78,
+            Algorithm::Deflate,
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+            Algorithm::SnappyRaw,
+            Algorithm::Mtf,
+            Algorithm::HuffmanRle,
+            Algorithm::PackBits,
+            Algorithm::RleEscape,
+        ];
+
+        for algorithm in algorithms {
+            let id = algorithm.id().unwrap();
+            assert_eq!(Algorithm::from_id(id), Some(algorithm));
+        }
+        let pipeline = Algorithm::Pipeline(vec![Algorithm::Rle, Algorithm::Huffman]);
+        assert_eq!(pipeline.id(), Some(21));
+        assert_eq!(Algorithm::from_id(21), Some(Algorithm::Pipeline(vec![])));
+        assert_eq!(Algorithm::LimitedHuffman(15).id(), Some(2));
+        assert_eq!(Algorithm::All.id(), None);
+        assert_eq!(Algorithm::from_id(0), None);
+    }
+
+    #[test]
+    fn algorithm_is_standalone_works() {
+        assert!(Algorithm::Gzip.is_standalone());
+        assert!(Algorithm::Zlib.is_standalone());
+        assert!(Algorithm::Lz4.is_standalone());
+        assert!(Algorithm::Snappy.is_standalone());
+        assert!(!Algorithm::SnappyRaw.is_standalone());
+        assert!(!Algorithm::Deflate.is_standalone());
+        assert!(!Algorithm::Huffman.is_standalone());
+    }
+
     #[test]
     fn algorithm_from_string_works() {
         let test_cases = vec![
@@ -122,6 +391,49 @@ mod test {
             ("bwtRLe", Algorithm::BwtRle),
             ("BwtRle", Algorithm::BwtRle),
             ("BWTrle", Algorithm::BwtRle),
+            ("bwtSAIS", Algorithm::BwtSais),
+            ("BlockSort", Algorithm::BlockSort),
+            ("adaptiveHuffman", Algorithm::AdaptiveHuffman),
+            ("Range", Algorithm::Range),
+            ("rANS", Algorithm::Rans),
+            ("tANS", Algorithm::Tans),
+            ("LzSS", Algorithm::Lzss),
+            ("Lz78", Algorithm::Lz78),
+            ("Deflate", Algorithm::Deflate),
+            ("GZip", Algorithm::Gzip),
+            ("zLib", Algorithm::Zlib),
+            ("Lz4", Algorithm::Lz4),
+            ("Snappy", Algorithm::Snappy),
+            ("SnappyRaw", Algorithm::SnappyRaw),
+            ("mtf", Algorithm::Mtf),
+            ("HuffmanRle", Algorithm::HuffmanRle),
+            ("PackBits", Algorithm::PackBits),
+            ("rleescape", Algorithm::RleEscape),
+            ("huffman15", Algorithm::LimitedHuffman(15)),
+            ("HUFFMAN1", Algorithm::LimitedHuffman(1)),
+            ("huffman63", Algorithm::LimitedHuffman(63)),
+            ("huffman0", Algorithm::Invalid),
+            ("huffman64", Algorithm::Invalid),
+            ("huffman+5", Algorithm::Invalid),
+            (
+                "bwt|huffman12",
+                Algorithm::Pipeline(vec![Algorithm::Bwt, Algorithm::LimitedHuffman(12)]),
+            ),
+            (
+                "bwt|mtf|rle|huffman",
+                Algorithm::Pipeline(vec![
+                    Algorithm::Bwt,
+                    Algorithm::Mtf,
+                    Algorithm::Rle,
+                    Algorithm::Huffman,
+                ]),
+            ),
+            (
+                "BWT|MTF|Huffman",
+                Algorithm::from("bwt|mtf|huffman".to_string()),
+            ),
+            ("bwt|", Algorithm::Invalid),
+            ("bwt|all", Algorithm::Invalid),
             ("All", Algorithm::All),
             ("aLl", Algorithm::All),
             ("alL", Algorithm::All),
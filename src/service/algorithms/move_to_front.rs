@@ -0,0 +1,88 @@
+use crate::errors::codec_error::CodecError;
+use crate::service::pkg::traits::{check_decoded_size, Codec};
+
+/// MoveToFront represents the Move-to-Front transform. Every byte is replaced by
+/// its position in a list of all byte values, and then moved to the front of the list,
+/// so the runs of equal bytes left by the BWT become runs of zeros.
+/// The output has the same length as the input, so there is no header.
+#[derive(Clone, Default)]
+pub struct MoveToFront {}
+
+impl MoveToFront {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// initial_list returns every byte value in increasing order
+    fn initial_list() -> [u8; 256] {
+        let mut list = [0u8; 256];
+        for (i, byte) in list.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        list
+    }
+}
+
+impl Codec for MoveToFront {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut list = Self::initial_list();
+
+        let encoded = data
+            .iter()
+            .map(|byte| {
+                // every byte value is in the list, so the search always succeeds
+                let position = list.iter().position(|b| b == byte).unwrap();
+                list.copy_within(0..position, 1);
+                list[0] = *byte;
+                position as u8
+            })
+            .collect();
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        // every position is one byte
+        check_decoded_size(data.len(), max_size)?;
+        let mut list = Self::initial_list();
+
+        let decoded = data
+            .iter()
+            .map(|position| {
+                let position = *position as usize;
+                let byte = list[position];
+                list.copy_within(0..position, 1);
+                list[0] = byte;
+                byte
+            })
+            .collect();
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::binary_samples;
+
+    #[test]
+    fn encoder_works() {
+        let mut mtf = MoveToFront::new();
+        assert_eq!(
+            mtf.encode(b"bananaaa").unwrap(),
+            vec![98, 98, 110, 1, 1, 1, 0, 0]
+        );
+        assert_eq!(mtf.encode(b"").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn decoder_works() {
+        let mut test_cases = binary_samples();
+        test_cases.push(b"annbaa".to_vec());
+
+        for test_case in test_cases {
+            let encoded = MoveToFront::new().encode(&test_case).unwrap();
+            let decoded = MoveToFront::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+}
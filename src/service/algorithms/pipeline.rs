@@ -0,0 +1,311 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{algorithms::Algorithm, io::new_codec, pkg::traits::Codec};
+
+/// SEPARATOR splits the stages of a pipeline spec such as "bwt|mtf|rle|huffman"
+pub const SEPARATOR: char = '|';
+
+/// MAX_STAGES bounds the stages of a pipeline, so a corrupted header cannot chain
+/// an unbounded number of decoders
+pub const MAX_STAGES: usize = 8;
+
+/// STAGE_GROWTH and STAGE_SLACK bound how much a stage grows the data it encodes, as RLE
+/// doubles data without runs and small data takes a header, so the data between two stages
+/// is bounded by the size limit of the output
+const STAGE_GROWTH: usize = 4;
+const STAGE_SLACK: usize = 1 << 12;
+
+/// parse_stages parses a pipeline spec into its stages, returning None if a stage is not
+/// an algorithm that encodes data on its own, or if there are fewer than 2 or too many
+pub fn parse_stages(spec: &str) -> Option<Vec<Algorithm>> {
+    let stages = spec
+        .split(SEPARATOR)
+        .map(|name| match Algorithm::from(name.trim().to_string()) {
+            Algorithm::Pipeline(_) | Algorithm::All | Algorithm::Invalid => None,
+            stage => Some(stage),
+        })
+        .collect::<Option<Vec<Algorithm>>>()?;
+
+    match stages.len() {
+        2..=MAX_STAGES => Some(stages),
+        _ => None,
+    }
+}
+
+/// Pipeline chains codecs: encoding runs the stages in order, each on the output of the
+/// one before, and decoding undoes them in reverse order. A described pipeline writes the
+/// ids of its stages before the data, so it can be decoded without knowing its stages:
+///
+/// | field        | size                 |
+/// |--------------|----------------------|
+/// | stage count  | 1 byte               |
+/// | stage ids    | 1 byte per stage     |
+/// | data         | output of last stage |
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Algorithm>,
+    described: bool,
+}
+
+impl Pipeline {
+    /// new chains stages that both sides know, such as the ones of a named algorithm
+    pub fn new(stages: Vec<Algorithm>) -> Self {
+        Self {
+            stages,
+            described: false,
+        }
+    }
+
+    /// described chains stages that are written ahead of the data. Decoding reads them
+    /// from there, so a described pipeline with no stages decodes any described data
+    pub fn described(stages: Vec<Algorithm>) -> Self {
+        Self {
+            stages,
+            described: true,
+        }
+    }
+
+    /// codec returns the codec of a stage
+    fn codec(stage: &Algorithm) -> Result<Box<dyn Codec>, CodecError> {
+        match stage {
+            Algorithm::Pipeline(_) => None,
+            stage => new_codec(stage.clone()),
+        }
+        .ok_or_else(|| {
+            CodecError::new(
+                &format!("{} cannot be a pipeline stage", stage),
+                CodecErrorKind::UnknownAlgorithm,
+            )
+        })
+    }
+
+    /// stage_limit bounds the data stage `index` encodes, the output of the stages before it
+    /// for data of at most max_size bytes
+    fn stage_limit(max_size: usize, index: usize) -> usize {
+        (0..index).fold(max_size, |limit, _| {
+            limit
+                .saturating_mul(STAGE_GROWTH)
+                .saturating_add(STAGE_SLACK)
+        })
+    }
+
+    /// read_stages reads the stages written ahead of the data of a described pipeline
+    fn read_stages(data: &[u8]) -> Result<(Vec<Algorithm>, &[u8]), CodecError> {
+        let corrupted = |message: &str| CodecError::new(message, CodecErrorKind::CorruptedData);
+        let (count, data) = data
+            .split_first()
+            .ok_or_else(|| corrupted("pipeline data is empty"))?;
+        let count = *count as usize;
+        if count > MAX_STAGES || data.len() < count {
+            return Err(corrupted("pipeline stages are not valid"));
+        }
+
+        let stages = data[..count]
+            .iter()
+            .map(|id| {
+                Algorithm::from_id(*id).ok_or_else(|| {
+                    CodecError::new(
+                        &format!("pipeline stage id {} is unknown", id),
+                        CodecErrorKind::UnknownAlgorithm,
+                    )
+                })
+            })
+            .collect::<Result<Vec<Algorithm>, CodecError>>()?;
+        Ok((stages, &data[count..]))
+    }
+}
+
+impl Codec for Pipeline {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = data.to_vec();
+        for stage in &self.stages {
+            encoded = Self::codec(stage)?.encode(&encoded)?;
+        }
+
+        if !self.described {
+            return Ok(encoded);
+        }
+        if self.stages.len() > MAX_STAGES {
+            return Err(CodecError::new(
+                "pipeline has too many stages",
+                CodecErrorKind::InvalidInput,
+            ));
+        }
+        let mut described = vec![self.stages.len() as u8];
+        // every stage has a codec, so it also has an id
+        described.extend(self.stages.iter().map(|stage| stage.id().unwrap()));
+        described.extend(encoded);
+        Ok(described)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let (stages, data) = match self.described {
+            true => Self::read_stages(data)?,
+            false => (self.stages.clone(), data),
+        };
+
+        let mut decoded = data.to_vec();
+        for (index, stage) in stages.iter().enumerate().rev() {
+            let limit = Self::stage_limit(max_size, index);
+            decoded = Self::codec(stage)?.decode_limited(&decoded, limit)?;
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::block_sort::BlockSort;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    const LINES: &[u8] = include_bytes!("../../../test_data/deflate/dynamic.txt");
+
+    #[test]
+    fn parse_stages_works() {
+        assert_eq!(
+            parse_stages("bwt|mtf|rle|huffman"),
+            Some(vec![
+                Algorithm::Bwt,
+                Algorithm::Mtf,
+                Algorithm::Rle,
+                Algorithm::Huffman
+            ])
+        );
+        assert_eq!(
+            parse_stages("BwtSais | RLE"),
+            Some(vec![Algorithm::BwtSais, Algorithm::Rle])
+        );
+
+        let invalid = ["huffman", "bwt|", "|rle", "bwt||rle", "bwt|zip", "rle|all"];
+        for spec in invalid {
+            assert_eq!(parse_stages(spec), None);
+        }
+        assert_eq!(parse_stages(&["rle"; MAX_STAGES + 1].join("|")), None);
+    }
+
+    #[test]
+    fn pipeline_works() {
+        let specs = [
+            "bwtsais|mtf|rle|huffman",
+            "bwtsais|rle",
+            "mtf|range",
+            "lzss|huffman",
+            "rle|gzip",
+        ];
+        let mut test_cases = binary_samples();
+        test_cases.extend([b"".to_vec(), b"banana".to_vec(), LINES.to_vec()]);
+
+        for spec in specs {
+            let stages = parse_stages(spec).unwrap();
+            for test_case in &test_cases {
+                let encoded = Pipeline::new(stages.clone()).encode(test_case).unwrap();
+                let decoded = Pipeline::new(stages.clone()).decode(&encoded).unwrap();
+                assert_eq!(&decoded, test_case);
+
+                // a described pipeline is decoded from its payload alone
+                let encoded = Pipeline::described(stages.clone())
+                    .encode(test_case)
+                    .unwrap();
+                let decoded = Pipeline::described(vec![]).decode(&encoded).unwrap();
+                assert_eq!(&decoded, test_case);
+            }
+        }
+    }
+
+    #[test]
+    fn bwt_rle_works() {
+        let test_cases = vec![
+            "banana",
+            "abracadabra!",
+            "aaaaabbbbbaaaaabbbbb",
+            "costs $5",
+            "",
+        ];
+        let mut test_cases = test_cases
+            .into_iter()
+            .map(|test_case| test_case.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        test_cases.extend(binary_samples());
+
+        // bwtrle is the pipeline of the two, with no stages written ahead of the data
+        for test_case in test_cases {
+            let encoded = new_codec(Algorithm::BwtRle)
+                .unwrap()
+                .encode(&test_case)
+                .unwrap();
+            let transformed = new_codec(Algorithm::BwtSais)
+                .unwrap()
+                .encode(&test_case)
+                .unwrap();
+            let expected = new_codec(Algorithm::Rle)
+                .unwrap()
+                .encode(&transformed)
+                .unwrap();
+            assert_eq!(encoded, expected);
+
+            let decoded = new_codec(Algorithm::BwtRle)
+                .unwrap()
+                .decode(&encoded)
+                .unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn stages_run_in_order() {
+        // the stages of the block sorting compressor, with plain RLE on the MTF ranks
+        let stages = parse_stages("bwtsais|mtf|rle|huffman").unwrap();
+        let encoded = Pipeline::described(stages).encode(LINES).unwrap();
+        assert_eq!(&encoded[..5], &[4, 6, 20, 1, 2]);
+        assert!(encoded.len() < LINES.len() / 2);
+
+        // the zero run coding of BlockSort suits MTF ranks better than RLE
+        let block_sort = BlockSort::new().encode(LINES).unwrap();
+        assert!(block_sort.len() < encoded.len());
+
+        // the reverse order is another pipeline altogether
+        let stages = parse_stages("huffman|rle|mtf|bwtsais").unwrap();
+        let reversed = Pipeline::described(stages).encode(LINES).unwrap();
+        assert!(reversed.len() > encoded.len());
+    }
+
+    #[test]
+    fn stages_are_limited() {
+        // rle doubles random data twice, which the limits of the later stages allow for
+        let data = XorShift::new(21).bytes(10_000);
+        let stages = parse_stages("rle|rle|huffman").unwrap();
+        let encoded = Pipeline::described(stages).encode(&data).unwrap();
+
+        let mut pipeline = Pipeline::described(vec![]);
+        assert_eq!(pipeline.decode_limited(&encoded, data.len()).unwrap(), data);
+        let err = pipeline
+            .decode_limited(&encoded, data.len() - 1)
+            .unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+
+        assert_eq!(Pipeline::stage_limit(100, 0), 100);
+        assert_eq!(
+            Pipeline::stage_limit(100, 2),
+            (400 + STAGE_SLACK) * 4 + STAGE_SLACK
+        );
+        assert_eq!(Pipeline::stage_limit(usize::MAX, 1), usize::MAX);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut pipeline = Pipeline::described(vec![]);
+        assert!(pipeline.decode(&[]).is_err());
+        assert!(pipeline.decode(&[3, 1, 2]).is_err());
+        assert!(pipeline.decode(&[MAX_STAGES as u8 + 1]).is_err());
+        assert_eq!(
+            pipeline.decode(&[2, 1, 0]).unwrap_err().kind,
+            CodecErrorKind::UnknownAlgorithm
+        );
+
+        // a pipeline cannot be a stage of another
+        let nested = Algorithm::Pipeline(vec![Algorithm::Rle, Algorithm::Huffman]);
+        let nested_id = nested.id().unwrap();
+        assert!(pipeline.decode(&[1, nested_id]).is_err());
+        assert!(Pipeline::new(vec![nested]).encode(b"banana").is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::{
+        byte_cursor::{write_varint, ByteCursor},
+        fenwick_tree::FenwickTree,
+    },
+    pkg::traits::{check_decoded_size, Codec},
+};
+
+/// TOP is the smallest range kept between two symbols, below it a byte is shifted out
+const TOP: u32 = 1 << 24;
+
+/// MAX_TOTAL bounds the total frequency of the model, so the range divided by it keeps 8 bits
+const MAX_TOTAL: u64 = 1 << 16;
+
+/// INCREMENT is added to the frequency of a symbol every time it is coded
+const INCREMENT: u64 = 32;
+
+/// AdaptiveModel is an order-0 model counting how often every byte was seen.
+/// Every byte starts with a count of 1 so any byte can be coded, and the counts
+/// are halved once their total grows past MAX_TOTAL, which also lets the model
+/// follow changes in the data.
+struct AdaptiveModel {
+    frequencies: FenwickTree,
+}
+
+impl AdaptiveModel {
+    fn new() -> Self {
+        Self {
+            frequencies: FenwickTree::from_counts(&[1; 256]),
+        }
+    }
+
+    /// interval returns the cumulative frequency and the frequency of the symbol
+    fn interval(&self, symbol: u8) -> (u32, u32) {
+        let cumulative = self.frequencies.prefix_sum(symbol as usize);
+        let frequency = self.frequencies.get(symbol as usize);
+        (cumulative as u32, frequency as u32)
+    }
+
+    fn total(&self) -> u32 {
+        self.frequencies.total() as u32
+    }
+
+    /// update counts one more occurrence of the symbol
+    fn update(&mut self, symbol: u8) {
+        self.frequencies.add(symbol as usize, INCREMENT);
+        if self.frequencies.total() > MAX_TOTAL {
+            let halved = self
+                .frequencies
+                .counts()
+                .into_iter()
+                .map(|count| count.div_ceil(2))
+                .collect::<Vec<u64>>();
+            self.frequencies = FenwickTree::from_counts(&halved);
+        }
+    }
+}
+
+/// RangeEncoder narrows a 32 bit range down to the interval of every symbol, shifting out
+/// the top byte of its low end whenever the range gets too small. A carry out of the low end
+/// is applied to the bytes held back in the cache, as they have not been written yet.
+struct RangeEncoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    bytes: Vec<u8>,
+}
+
+impl RangeEncoder {
+    fn new() -> Self {
+        Self {
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            bytes: Vec::new(),
+        }
+    }
+
+    /// encode narrows the range to the interval [cumulative, cumulative + frequency) of total
+    fn encode(&mut self, cumulative: u32, frequency: u32, total: u32) {
+        let r = self.range / total;
+        self.low += cumulative as u64 * r as u64;
+        self.range = r * frequency;
+        while self.range < TOP {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    /// shift_low moves the top byte of the low end into the cache, writing out the cached
+    /// bytes once no carry can reach them anymore
+    fn shift_low(&mut self) {
+        if self.low < 0xff00_0000 || self.low >= 1 << 32 {
+            let carry = (self.low >> 32) as u8;
+            let mut byte = self.cache;
+            while self.cache_size > 0 {
+                self.bytes.push(byte.wrapping_add(carry));
+                byte = 0xff;
+                self.cache_size -= 1;
+            }
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00ff_ffff) << 8;
+    }
+
+    /// finish flushes the low end and returns the coded bytes
+    fn finish(mut self) -> Vec<u8> {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+        self.bytes
+    }
+}
+
+/// RangeDecoder follows the range of the encoder and finds the interval holding the code
+struct RangeDecoder<'a> {
+    code: u32,
+    range: u32,
+    bytes: std::slice::Iter<'a, u8>,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, CodecError> {
+        let mut decoder = Self {
+            code: 0,
+            range: u32::MAX,
+            bytes: data.iter(),
+        };
+        // the first byte is the empty cache of the encoder, and the next four fill the code
+        for _ in 0..5 {
+            decoder.code = (decoder.code << 8) | decoder.next_byte()? as u32;
+        }
+        Ok(decoder)
+    }
+
+    fn next_byte(&mut self) -> Result<u8, CodecError> {
+        self.bytes.next().copied().ok_or_else(|| {
+            CodecError::new(
+                "range coded data ended before every symbol was decoded",
+                CodecErrorKind::CorruptedData,
+            )
+        })
+    }
+
+    /// target returns the cumulative frequency the code points at, scaling the range to total
+    fn target(&mut self, total: u32) -> Result<u32, CodecError> {
+        self.range /= total;
+        let target = self.code / self.range;
+        if target >= total {
+            return Err(CodecError::new(
+                "range coded data points past the model",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+        Ok(target)
+    }
+
+    /// decode removes the interval of the decoded symbol, after a call to target
+    fn decode(&mut self, cumulative: u32, frequency: u32) -> Result<(), CodecError> {
+        self.code -= cumulative * self.range;
+        self.range *= frequency;
+        while self.range < TOP {
+            self.code = (self.code << 8) | self.next_byte()? as u32;
+            self.range <<= 8;
+        }
+        Ok(())
+    }
+}
+
+/// RangeCoding represents range coding (arithmetic coding on bytes) with an adaptive
+/// order-0 model. Unlike Huffman encoding a symbol can take a fraction of a bit, so
+/// the output gets close to the order-0 entropy of the data.
+/// The payload is the number of bytes followed by the range coded bytes.
+#[derive(Default)]
+pub struct RangeCoding {}
+
+impl RangeCoding {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Codec for RangeCoding {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = Vec::new();
+        write_varint(&mut encoded, data.len() as u64);
+
+        let mut model = AdaptiveModel::new();
+        let mut encoder = RangeEncoder::new();
+        for byte in data {
+            let (cumulative, frequency) = model.interval(*byte);
+            encoder.encode(cumulative, frequency, model.total());
+            model.update(*byte);
+        }
+
+        encoded.extend(encoder.finish());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut cursor = ByteCursor::new(data);
+        let symbol_count = usize::try_from(cursor.read_varint()?).unwrap_or(usize::MAX);
+        check_decoded_size(symbol_count, max_size)?;
+        let mut decoder = RangeDecoder::new(cursor.remaining())?;
+
+        // a symbol can take less than a bit, so the capacity only grows with the data
+        let mut model = AdaptiveModel::new();
+        let mut decoded = Vec::with_capacity(symbol_count.min(data.len() * 8));
+        while decoded.len() < symbol_count {
+            let target = decoder.target(model.total())?;
+            // the target is below the total, so find always lands on a symbol
+            let byte = model.frequencies.find(target as u64).unwrap() as u8;
+            let (cumulative, frequency) = model.interval(byte);
+            decoder.decode(cumulative, frequency)?;
+            model.update(byte);
+            decoded.push(byte);
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::huffman::Huffman;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+    use crate::utils::utils::shannon_entropy;
+
+    #[test]
+    fn range_coding_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            vec![0xff; 10_000],
+            "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+                .repeat(50)
+                .into_bytes(),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = RangeCoding::new().encode(&test_case).unwrap();
+            let decoded = RangeCoding::new().decode(&encoded).unwrap();
+            assert_eq!(decoded, test_case);
+        }
+    }
+
+    #[test]
+    fn skewed_data_beats_huffman() {
+        // huffman needs a whole bit for every byte, while the entropy is far below it
+        let mut rng = XorShift::new(7);
+        let data = (0..100_000)
+            .map(|_| {
+                if rng.next_u64().is_multiple_of(50) {
+                    b'b'
+                } else {
+                    b'a'
+                }
+            })
+            .collect::<Vec<u8>>();
+
+        let range = RangeCoding::new().encode(&data).unwrap();
+        let huffman = Huffman::new().encode(&data).unwrap();
+        assert!(range.len() * 4 < huffman.len());
+
+        let bits_per_byte = range.len() as f64 * 8.0 / data.len() as f64;
+        assert!(bits_per_byte < shannon_entropy(&data) + 0.02);
+        assert_eq!(RangeCoding::new().decode(&range).unwrap(), data);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut range = RangeCoding::new();
+        assert!(range.decode(&[]).is_err());
+        assert!(range.decode(&[3, 0, 1]).is_err());
+
+        let mut encoded = RangeCoding::new().encode(b"abracadabra").unwrap();
+        encoded.truncate(encoded.len() - 3);
+        assert!(range.decode(&encoded).is_err());
+    }
+}
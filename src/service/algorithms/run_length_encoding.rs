@@ -1,121 +1,461 @@
-use crate::service::pkg::traits::Codec;
-use std::fmt::{Debug, Display, Formatter};
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::byte_cursor::ByteCursor,
+    pkg::traits::{check_decoded_size, Codec},
+};
 
-#[derive(Clone)]
-struct RunLengthEncodingPart(u8, u64);
+/// MAX_RUN is the longest run a single (byte, count) pair can hold
+const MAX_RUN: u64 = u8::MAX as u64;
 
-// impl Debug for RunLengthEncodingPart {
-//     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-//         write!(
-//             f,
-//             "{}-{}",
-//             (self.0 as char).to_string(), self.1 as usize
-//         )
-//     }
-// }
+/// PACKBITS_MAX is the most bytes a PackBits run or literal header covers
+const PACKBITS_MAX: usize = 128;
 
-impl RunLengthEncodingPart {
-    fn decoded_format(&self) -> String {
-        (self.0 as char).to_string().repeat(self.1 as usize)
-    }
+/// PACKBITS_NOOP is the header PackBits decoders skip
+const PACKBITS_NOOP: u8 = 0x80;
+
+/// ESCAPE_THRESHOLD is the shortest run written as a run in escape mode, as an escaped
+/// run takes 3 bytes
+const ESCAPE_THRESHOLD: usize = 4;
+
+/// ESCAPE_MAX_RUN is the longest run a single escape sequence can hold
+const ESCAPE_MAX_RUN: usize = ESCAPE_THRESHOLD + u8::MAX as usize - 1;
+
+/// RleMode selects how runs are written
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RleMode {
+    /// every run is a (byte, count) pair, so data without runs doubles in size
+    #[default]
+    Pairs,
+    /// Apple PackBits: a header byte n is followed by n + 1 literal bytes when it is
+    /// positive, and by a byte repeated 1 - n times when it is negative
+    PackBits,
+    /// runs of ESCAPE_THRESHOLD bytes or more are written as the escape byte, the count and
+    /// the byte, and every other byte as it is. The escape byte is the least frequent byte
+    /// of the data, written first, and stands for itself when followed by a zero count
+    Escape,
 }
 
-impl Display for RunLengthEncodingPart {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "({}{})",
-            (self.0 as char).to_string(), self.1 as usize
-        )
+#[derive(Clone)]
+struct RunLengthEncodingPart(u8, u64);
+
+impl RunLengthEncodingPart {
+    /// write_to appends the part as (byte, count) pairs, splitting runs longer than MAX_RUN
+    fn write_to(&self, out: &mut Vec<u8>) {
+        let mut remaining = self.1;
+        while remaining > 0 {
+            let count = remaining.min(MAX_RUN);
+            out.push(self.0);
+            out.push(count as u8);
+            remaining -= count;
+        }
     }
 }
 
-/// RLE is the zero product struct for the RUN LENGTH ENCODING algorithm
-#[derive(Clone)]
+/// RLE is the struct for the RUN LENGTH ENCODING algorithm, in one of the RleMode formats
+#[derive(Clone, Default)]
 pub struct RunLengthEncoding {
-    text: String,
-    encoded: Vec<RunLengthEncodingPart>,
-    decoded: String,
+    mode: RleMode,
 }
 
 impl RunLengthEncoding {
-    pub fn new(text: String) -> Self {
+    pub fn new() -> Self {
         Self {
-            text,
-            encoded: Vec::new(),
-            decoded: String::new(),
+            mode: RleMode::Pairs,
         }
     }
-}
 
-/// Codec trait implementation for the RLE algorithm
-impl Codec for RunLengthEncoding {
-    /// encode compresses a given list of text characters to get a smaller size
-    fn encode(&mut self) {
-        let n = self.text.len();
-        let text_chars = self.text.chars().collect::<Vec<char>>();
+    /// with_mode creates an encoder writing runs in the given format
+    pub fn with_mode(mode: RleMode) -> Self {
+        Self { mode }
+    }
+
+    /// max_encoded_len returns the largest output of the mode for data of length n,
+    /// reached by data without any run
+    pub fn max_encoded_len(&self, n: usize) -> usize {
+        match self.mode {
+            RleMode::Pairs => 2 * n,
+            // a header for every 128 literal bytes
+            RleMode::PackBits => n + n.div_ceil(PACKBITS_MAX),
+            // the escape byte, and a second byte for each of its at most n / 256 occurrences
+            RleMode::Escape => 1 + n + n / 256,
+        }
+    }
+
+    /// run_length returns the length of the run starting at position, up to max_run
+    fn run_length(data: &[u8], position: usize, max_run: usize) -> usize {
+        data[position..]
+            .iter()
+            .take(max_run)
+            .take_while(|byte| **byte == data[position])
+            .count()
+    }
+
+    /// encode_pack_bits writes runs of 3 bytes or more, or of 2 bytes where no literals are
+    /// pending, under a negative header, and every other byte under a literal header
+    fn encode_pack_bits(data: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(data.len() + data.len().div_ceil(PACKBITS_MAX));
+        let (mut position, mut literal_start) = (0, 0);
+
+        while position < data.len() {
+            let run = Self::run_length(data, position, PACKBITS_MAX);
+            if run >= 3 || (run == 2 && literal_start == position) {
+                Self::write_literals(&mut encoded, &data[literal_start..position]);
+                encoded.push((257 - run) as u8);
+                encoded.push(data[position]);
+                position += run;
+                literal_start = position;
+            } else {
+                position += 1;
+            }
+        }
+
+        Self::write_literals(&mut encoded, &data[literal_start..]);
+        encoded
+    }
+
+    /// write_literals writes literal bytes under a header for every 128 of them
+    fn write_literals(encoded: &mut Vec<u8>, literals: &[u8]) {
+        for chunk in literals.chunks(PACKBITS_MAX) {
+            encoded.push(chunk.len() as u8 - 1);
+            encoded.extend_from_slice(chunk);
+        }
+    }
+
+    /// decode_pack_bits expands the literal and run headers written by encode_pack_bits
+    fn decode_pack_bits(data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let truncated = |_| {
+            CodecError::new(
+                "packbits data ended after a header",
+                CodecErrorKind::CorruptedData,
+            )
+        };
+        let mut cursor = ByteCursor::new(data);
+        let mut decoded = Vec::with_capacity(data.len());
+
+        while !cursor.remaining().is_empty() {
+            let header = cursor.read_u8()?;
+            match header {
+                0..=0x7f => {
+                    let literals = cursor.read_bytes(header as usize + 1).map_err(truncated)?;
+                    check_decoded_size(decoded.len() + literals.len(), max_size)?;
+                    decoded.extend_from_slice(literals);
+                }
+                PACKBITS_NOOP => continue,
+                _ => {
+                    let byte = cursor.read_u8().map_err(truncated)?;
+                    let run = 257 - header as usize;
+                    check_decoded_size(decoded.len() + run, max_size)?;
+                    decoded.extend(std::iter::repeat_n(byte, run));
+                }
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// escape_byte returns the least frequent byte of the data, the smallest one on ties,
+    /// which appears at most n / 256 times
+    fn escape_byte(data: &[u8]) -> u8 {
+        let mut frequencies = [0usize; 256];
+        for byte in data {
+            frequencies[*byte as usize] += 1;
+        }
+        (0..=u8::MAX)
+            .min_by_key(|byte| frequencies[*byte as usize])
+            .unwrap()
+    }
+
+    /// encode_escape writes the escape byte, then the runs of ESCAPE_THRESHOLD bytes or more
+    /// as escape sequences and the other bytes as they are
+    fn encode_escape(data: &[u8]) -> Vec<u8> {
+        let escape = Self::escape_byte(data);
+        let mut encoded = Vec::with_capacity(1 + data.len() + data.len() / 256);
+        encoded.push(escape);
+
+        let mut position = 0;
+        while position < data.len() {
+            let byte = data[position];
+            let run = Self::run_length(data, position, ESCAPE_MAX_RUN);
+            if run >= ESCAPE_THRESHOLD {
+                encoded.extend([escape, (run - ESCAPE_THRESHOLD + 1) as u8, byte]);
+            } else if byte == escape {
+                for _ in 0..run {
+                    encoded.extend([escape, 0]);
+                }
+            } else {
+                encoded.extend(std::iter::repeat_n(byte, run));
+            }
+            position += run;
+        }
+
+        encoded
+    }
+
+    /// decode_escape expands the escape sequences written by encode_escape
+    fn decode_escape(data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let truncated = |_| {
+            CodecError::new(
+                "run length data ended in an escape sequence",
+                CodecErrorKind::CorruptedData,
+            )
+        };
+        let mut cursor = ByteCursor::new(data);
+        let escape = cursor.read_u8()?;
+        let mut decoded = Vec::with_capacity(data.len());
+
+        while !cursor.remaining().is_empty() {
+            check_decoded_size(decoded.len() + 1, max_size)?;
+            let byte = cursor.read_u8()?;
+            if byte != escape {
+                decoded.push(byte);
+                continue;
+            }
+            match cursor.read_u8().map_err(truncated)? {
+                0 => decoded.push(escape),
+                count => {
+                    let byte = cursor.read_u8().map_err(truncated)?;
+                    let run = count as usize + ESCAPE_THRESHOLD - 1;
+                    check_decoded_size(decoded.len() + run, max_size)?;
+                    decoded.extend(std::iter::repeat_n(byte, run));
+                }
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// runs groups consecutive equal bytes of the data into parts
+    fn runs(data: &[u8]) -> Vec<RunLengthEncodingPart> {
+        let n = data.len();
+        let mut parts = Vec::new();
 
         let mut i = 0usize;
         while i < n {
-            let mut char_count = 1u64;
-            while i < n - 1 && text_chars[i] == text_chars[i + 1] {
-                char_count += 1;
+            let mut byte_count = 1u64;
+            while i < n - 1 && data[i] == data[i + 1] {
+                byte_count += 1;
                 i += 1;
             }
-            self.encoded
-                .push(RunLengthEncodingPart(text_chars[i] as u8, char_count));
+            parts.push(RunLengthEncodingPart(data[i], byte_count));
             i += 1;
         }
+
+        parts
     }
+}
 
-    /// decode decompresses a given compressed text to get the original text
-    fn decode(&mut self) {
-        for part in self.encoded.iter() {
-            self.decoded.push_str(part.decoded_format().as_str());
+/// Codec trait implementation for the RLE algorithm
+impl Codec for RunLengthEncoding {
+    /// encode compresses the data into (byte, count) pairs, or into the format of the mode
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        match self.mode {
+            RleMode::Pairs => {}
+            RleMode::PackBits => return Ok(Self::encode_pack_bits(data)),
+            RleMode::Escape => return Ok(Self::encode_escape(data)),
         }
-    }
 
-    fn compressed(&self) -> String {
-        let mut s = String::new();
-        for part in self.encoded.iter() {
-            s.push_str(part.to_string().as_str());
+        let mut encoded = Vec::with_capacity(data.len());
+        for part in Self::runs(data) {
+            part.write_to(&mut encoded);
         }
-        s
+        Ok(encoded)
     }
 
-    fn decompressed(&self) -> String {
-        self.decoded.clone()
+    /// decode expands every (byte, count) pair back into its run, or the format of the mode
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        match self.mode {
+            RleMode::Pairs => {}
+            RleMode::PackBits => return Self::decode_pack_bits(data, max_size),
+            RleMode::Escape => return Self::decode_escape(data, max_size),
+        }
+
+        if !data.len().is_multiple_of(2) {
+            return Err(CodecError::new(
+                "run length data must be made of (byte, count) pairs",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+
+        let mut decoded = Vec::with_capacity(data.len());
+        for pair in data.chunks_exact(2) {
+            if pair[1] == 0 {
+                return Err(CodecError::new(
+                    "run length count cannot be zero",
+                    CodecErrorKind::CorruptedData,
+                ));
+            }
+            check_decoded_size(decoded.len() + pair[1] as usize, max_size)?;
+            decoded.extend(std::iter::repeat_n(pair[0], pair[1] as usize));
+        }
+        Ok(decoded)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{RleMode, RunLengthEncoding};
     use crate::service::pkg::traits::Codec;
+    use crate::utils::test_utils::{binary_samples, XorShift};
 
     #[test]
     fn encoder_works() {
-        let test_cases = vec![
-            ("abracadabra!", "RlePart(97, 1)RlePart(98, 1)RlePart(114, 1)RlePart(97, 1)RlePart(99, 1)RlePart(97, 1)RlePart(100, 1)RlePart(97, 1)RlePart(98, 1)RlePart(114, 1)RlePart(97, 1)RlePart(33, 1)"),
-            ("aabbc", "RlePart(97, 2)RlePart(98, 2)RlePart(99, 1)"),
-            ("aaaaaaaaaa", "RlePart(97, 10)"),
+        let test_cases: Vec<(&str, Vec<u8>)> = vec![
+            (
+                "abracadabra!",
+                vec![
+                    97, 1, 98, 1, 114, 1, 97, 1, 99, 1, 97, 1, 100, 1, 97, 1, 98, 1, 114, 1, 97, 1,
+                    33, 1,
+                ],
+            ),
+            ("aabbc", vec![97, 2, 98, 2, 99, 1]),
+            ("aaaaaaaaaa", vec![97, 10]),
         ];
 
         for test_case in test_cases {
-            let mut rle = super::RunLengthEncoding::new(test_case.0.to_string());
-            rle.encode();
-            assert_eq!(rle.compressed(), test_case.1);
+            let mut rle = super::RunLengthEncoding::new();
+            assert_eq!(rle.encode(test_case.0.as_bytes()).unwrap(), test_case.1);
         }
     }
 
+    #[test]
+    fn long_runs_are_split() {
+        let mut rle = super::RunLengthEncoding::new();
+        let encoded = rle.encode(&[7u8; 600]).unwrap();
+        assert_eq!(encoded, vec![7, 255, 7, 255, 7, 90]);
+    }
+
     #[test]
     fn decoder_works() {
-        let test_cases = vec!["abracadabra!", "aabbc", "aaaaaaaaaa"];
+        let test_cases = vec!["abracadabra!", "aabbc", "aaaaaaaaaa", ""];
 
         for test_case in test_cases {
-            let mut rle = super::RunLengthEncoding::new(test_case.to_string());
-            rle.encode();
-            rle.decode();
-            assert_eq!(rle.decompressed(), test_case);
+            let mut rle = super::RunLengthEncoding::new();
+            let encoded = rle.encode(test_case.as_bytes()).unwrap();
+            assert_eq!(rle.decode(&encoded).unwrap(), test_case.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut rle = super::RunLengthEncoding::new();
+        assert!(rle.decode(&[97]).is_err());
+        assert!(rle.decode(&[97, 0]).is_err());
+    }
+
+    #[test]
+    fn binary_data_works() {
+        let mut rle = super::RunLengthEncoding::new();
+        for sample in binary_samples() {
+            let encoded = rle.encode(&sample).unwrap();
+            assert_eq!(rle.decode(&encoded).unwrap(), sample);
+        }
+    }
+
+    #[test]
+    fn pack_bits_works() {
+        // the example of Apple's technical note
+        let data = [
+            0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0xaa, 0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0x22,
+            0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+        ];
+        let packed = [
+            0xfe, 0xaa, 0x02, 0x80, 0x00, 0x2a, 0xfd, 0xaa, 0x03, 0x80, 0x00, 0x2a, 0x22, 0xf7,
+            0xaa,
+        ];
+        let mut rle = RunLengthEncoding::with_mode(RleMode::PackBits);
+        assert_eq!(rle.encode(&data).unwrap(), packed);
+        assert_eq!(rle.decode(&packed).unwrap(), data);
+
+        // runs longer than 128 bytes take more than one header, and 0x80 is skipped
+        assert_eq!(rle.encode(&[7; 300]).unwrap(), [0x81, 7, 0x81, 7, 0xd5, 7]);
+        assert_eq!(rle.decode(&[0x80, 0x01, 1, 2, 0x80]).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn escape_works() {
+        // 0 is the least frequent byte, and the run of five b is an escape sequence
+        let mut rle = RunLengthEncoding::with_mode(RleMode::Escape);
+        let encoded = rle.encode(b"abbbbbc").unwrap();
+        assert_eq!(encoded, [0, b'a', 0, 2, b'b', b'c']);
+        assert_eq!(rle.decode(&encoded).unwrap(), b"abbbbbc");
+
+        // runs below the threshold are left as they are, and the escape byte stands for
+        // itself when followed by a zero count
+        let every_byte = (0..=255).chain([0, 0, 0, 1]).collect::<Vec<u8>>();
+        let encoded = rle.encode(&every_byte).unwrap();
+        assert_eq!(encoded[0], 2);
+        assert_eq!(&encoded[1..5], &[0, 1, 2, 0]);
+        assert_eq!(rle.decode(&encoded).unwrap(), every_byte);
+    }
+
+    #[test]
+    fn modes_work() {
+        let mut rng = XorShift::new(12);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"aa".to_vec(),
+            b"abracadabra!".to_vec(),
+            vec![0u8; 100_000],
+            rng.bytes(10_000),
+            (0..10_000).map(|_| (rng.next_u64() % 3) as u8).collect(),
+        ]);
+
+        for mode in [RleMode::Pairs, RleMode::PackBits, RleMode::Escape] {
+            for test_case in &test_cases {
+                let mut rle = RunLengthEncoding::with_mode(mode);
+                let encoded = rle.encode(test_case).unwrap();
+                assert_eq!(&rle.decode(&encoded).unwrap(), test_case);
+            }
         }
     }
+
+    #[test]
+    fn expansion_is_bounded() {
+        // data without runs, with runs of 2 and 3 among literals, and with every byte value
+        // equally frequent, the worst cases of the three modes
+        let mut rng = XorShift::new(13);
+        let test_cases = [
+            rng.bytes(10_000),
+            (0..10_000).map(|i| (i % 251) as u8).collect(),
+            b"aab".repeat(3_000),
+            b"abb".repeat(3_000),
+            b"aaab".repeat(3_000),
+            b"abcc".repeat(3_000),
+            (0..=255u8).cycle().take(256 * 40).collect(),
+            (0..10_000).map(|_| (rng.next_u64() % 2) as u8).collect(),
+        ];
+
+        for mode in [RleMode::Pairs, RleMode::PackBits, RleMode::Escape] {
+            let mut rle = RunLengthEncoding::with_mode(mode);
+            for test_case in &test_cases {
+                let encoded = rle.encode(test_case).unwrap();
+                assert!(encoded.len() <= rle.max_encoded_len(test_case.len()));
+            }
+            assert!(rle.encode(b"").unwrap().len() <= rle.max_encoded_len(0));
+        }
+
+        // data without runs grows by less than 1% with packbits and escape, and doubles with
+        // pairs
+        let random = rng.bytes(100_000);
+        let pairs = RunLengthEncoding::new().encode(&random).unwrap();
+        assert!(pairs.len() > 190_000);
+        for mode in [RleMode::PackBits, RleMode::Escape] {
+            let encoded = RunLengthEncoding::with_mode(mode).encode(&random).unwrap();
+            assert!(encoded.len() < 101_000);
+        }
+    }
+
+    #[test]
+    fn modes_reject_corrupted_data() {
+        let mut pack_bits = RunLengthEncoding::with_mode(RleMode::PackBits);
+        assert!(pack_bits.decode(&[0x02, 1, 2]).is_err());
+        assert!(pack_bits.decode(&[0xfe]).is_err());
+
+        let mut escape = RunLengthEncoding::with_mode(RleMode::Escape);
+        assert!(escape.decode(&[]).is_err());
+        assert!(escape.decode(&[0, b'a', 0]).is_err());
+        assert!(escape.decode(&[0, b'a', 0, 3]).is_err());
+    }
 }
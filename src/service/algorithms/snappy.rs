@@ -0,0 +1,505 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    data_structures::byte_cursor::{write_varint, ByteCursor},
+    pkg::traits::{check_decoded_size, Codec},
+};
+use crate::utils::checksum::crc32c;
+
+/// STREAM_IDENTIFIER is the chunk that starts every Snappy frame stream
+pub const STREAM_IDENTIFIER: [u8; 10] = *b"\xff\x06\x00\x00sNaPpY";
+
+/// the types of the chunks of a frame stream. Types 0x02 to 0x7f are reserved and cannot
+/// be skipped, types 0x80 to 0xfd are reserved and skipped like padding
+const CHUNK_COMPRESSED: u8 = 0x00;
+const CHUNK_UNCOMPRESSED: u8 = 0x01;
+const CHUNK_PADDING: u8 = 0xfe;
+const CHUNK_STREAM_IDENTIFIER: u8 = 0xff;
+
+/// MAX_CHUNK_DATA is the most data a chunk of a frame stream can hold
+const MAX_CHUNK_DATA: usize = 1 << 16;
+
+/// CRC_MASK is added to the rotated CRC-32C of every chunk
+const CRC_MASK: u32 = 0xa282_ead8;
+
+/// the element tags of the raw format, held in the lowest 2 bits of the tag byte
+const TAG_LITERAL: u8 = 0b00;
+const TAG_COPY_1: u8 = 0b01;
+const TAG_COPY_2: u8 = 0b10;
+
+/// MIN_MATCH is the length of the shortest match
+const MIN_MATCH: usize = 4;
+
+/// FRAGMENT_SIZE is the size of the slices of the input compressed independently,
+/// so every match reaches less than 64 KiB back
+const FRAGMENT_SIZE: usize = 1 << 16;
+
+/// INPUT_MARGIN is the distance from the end of a fragment within which no match starts
+const INPUT_MARGIN: usize = 15;
+
+/// HASH_BITS is the width of the hash table of the match finder
+const HASH_BITS: u32 = 14;
+
+/// SKIP_TRIGGER makes the match finder step faster over data without matches,
+/// moving one more byte at a time every 2^SKIP_TRIGGER misses
+const SKIP_TRIGGER: u32 = 5;
+
+/// is_snappy returns true if the bytes start like a Snappy frame stream
+pub fn is_snappy(bytes: &[u8]) -> bool {
+    bytes.starts_with(&STREAM_IDENTIFIER)
+}
+
+/// masked_crc returns the checksum of a chunk: its CRC-32C rotated and offset, so the
+/// checksum of data holding checksums does not collapse
+pub fn masked_crc(data: &[u8]) -> u32 {
+    crc32c(data).rotate_right(15).wrapping_add(CRC_MASK)
+}
+
+/// corrupted returns the error of data that is not valid Snappy data
+fn corrupted(message: &str) -> CodecError {
+    CodecError::new(message, CodecErrorKind::CorruptedData)
+}
+
+/// SnappyRaw represents the raw Snappy format: the length of the data as a varint, followed
+/// by elements that are either literals or copies of earlier bytes. Like LZ4 it aims for
+/// speed over ratio, with a single-probe match finder and elements written as whole bytes.
+///
+/// | tag bits | element                                                           |
+/// |----------|-------------------------------------------------------------------|
+/// | `00`     | literal, its length minus 1 in the tag or in the next 1 to 4 bytes |
+/// | `01`     | copy of 4 to 11 bytes, reaching back up to 2047 bytes             |
+/// | `10`     | copy of 1 to 64 bytes, with a 2 byte offset                       |
+/// | `11`     | copy of 1 to 64 bytes, with a 4 byte offset                       |
+#[derive(Default)]
+pub struct SnappyRaw {}
+
+impl SnappyRaw {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// hash returns the slot of the 4 bytes at position in the hash table
+    fn hash(data: &[u8], position: usize) -> usize {
+        let bytes = u32::from_le_bytes(data[position..position + 4].try_into().unwrap());
+        (bytes.wrapping_mul(0x1e35_a7bd) >> (u32::BITS - HASH_BITS)) as usize
+    }
+
+    /// compress returns the data in the raw format
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::with_capacity(32 + data.len() + data.len() / 6);
+        write_varint(&mut compressed, data.len() as u64);
+        for fragment in data.chunks(FRAGMENT_SIZE) {
+            Self::compress_fragment(fragment, &mut compressed);
+        }
+        compressed
+    }
+
+    /// compress_fragment writes the elements of a fragment, whose matches stay inside it
+    fn compress_fragment(fragment: &[u8], compressed: &mut Vec<u8>) {
+        let mut anchor = 0;
+
+        if fragment.len() >= INPUT_MARGIN {
+            let mut table = vec![usize::MAX; 1 << HASH_BITS];
+            let limit = fragment.len() - INPUT_MARGIN;
+
+            let mut position = 0;
+            let mut misses = 0;
+            while position < limit {
+                let slot = Self::hash(fragment, position);
+                let candidate = table[slot];
+                table[slot] = position;
+
+                let found = candidate != usize::MAX
+                    && fragment[candidate..candidate + MIN_MATCH]
+                        == fragment[position..position + MIN_MATCH];
+                if !found {
+                    misses += 1;
+                    position += 1 + (misses >> SKIP_TRIGGER);
+                    continue;
+                }
+
+                let length = fragment[candidate..]
+                    .iter()
+                    .zip(&fragment[position..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                Self::write_literal(compressed, &fragment[anchor..position]);
+                Self::write_copy(compressed, position - candidate, length);
+                position += length;
+                anchor = position;
+                misses = 0;
+
+                // the last byte of the match starts the next lookups
+                if position < limit {
+                    table[Self::hash(fragment, position - 1)] = position - 1;
+                }
+            }
+        }
+
+        Self::write_literal(compressed, &fragment[anchor..]);
+    }
+
+    /// write_literal writes a literal element, its length minus 1 in the tag up to 59
+    /// and in the 1 to 4 bytes after the tag above
+    fn write_literal(compressed: &mut Vec<u8>, literal: &[u8]) {
+        if literal.is_empty() {
+            return;
+        }
+        let n = literal.len() - 1;
+        if n < 60 {
+            compressed.push((n as u8) << 2 | TAG_LITERAL);
+        } else {
+            let bytes = (usize::BITS - n.leading_zeros()).div_ceil(8) as usize;
+            compressed.push((59 + bytes as u8) << 2 | TAG_LITERAL);
+            compressed.extend_from_slice(&(n as u32).to_le_bytes()[..bytes]);
+        }
+        compressed.extend_from_slice(literal);
+    }
+
+    /// write_copy writes a match as copies of at most 64 bytes, never leaving one shorter
+    /// than 4 bytes for the last copy
+    fn write_copy(compressed: &mut Vec<u8>, offset: usize, mut length: usize) {
+        while length >= 68 {
+            Self::write_short_copy(compressed, offset, 64);
+            length -= 64;
+        }
+        if length > 64 {
+            Self::write_short_copy(compressed, offset, 60);
+            length -= 60;
+        }
+        Self::write_short_copy(compressed, offset, length);
+    }
+
+    /// write_short_copy writes a copy of at most 64 bytes, in 2 bytes when it is short and near
+    fn write_short_copy(compressed: &mut Vec<u8>, offset: usize, length: usize) {
+        if length < 12 && offset < 2048 {
+            compressed.push(((offset >> 8) as u8) << 5 | ((length - 4) as u8) << 2 | TAG_COPY_1);
+            compressed.push(offset as u8);
+        } else {
+            compressed.push(((length - 1) as u8) << 2 | TAG_COPY_2);
+            compressed.extend_from_slice(&(offset as u16).to_le_bytes());
+        }
+    }
+
+    /// decompress decodes data in the raw format holding at most max_size bytes
+    pub fn decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let truncated = || corrupted("snappy data ended in the middle of an element");
+        let mut cursor = ByteCursor::new(data);
+        let length = cursor.read_varint()?;
+        if length > max_size as u64 {
+            return Err(corrupted("snappy data is larger than its maximum size"));
+        }
+        let length = length as usize;
+
+        // the length is not trusted for more than a bound of what the elements can produce
+        let mut decompressed = Vec::with_capacity(length.min(data.len().saturating_mul(32)));
+        while !cursor.remaining().is_empty() {
+            let tag = cursor.read_u8()?;
+            let (offset, copy_length) = match tag & 0b11 {
+                TAG_LITERAL => {
+                    let mut n = (tag >> 2) as usize;
+                    if n >= 60 {
+                        let bytes = cursor.read_bytes(n - 59).map_err(|_| truncated())?;
+                        n = bytes
+                            .iter()
+                            .rev()
+                            .fold(0, |n, byte| n << 8 | *byte as usize);
+                    }
+                    if decompressed.len() + n + 1 > length {
+                        return Err(corrupted("snappy data is longer than its length"));
+                    }
+                    decompressed
+                        .extend_from_slice(cursor.read_bytes(n + 1).map_err(|_| truncated())?);
+                    continue;
+                }
+                TAG_COPY_1 => {
+                    let low = cursor.read_u8().map_err(|_| truncated())?;
+                    (
+                        ((tag >> 5) as usize) << 8 | low as usize,
+                        ((tag >> 2) & 0b111) as usize + 4,
+                    )
+                }
+                TAG_COPY_2 => {
+                    let offset = cursor.read_u16_le().map_err(|_| truncated())?;
+                    (offset as usize, (tag >> 2) as usize + 1)
+                }
+                _ => {
+                    let offset = cursor.read_u32_le().map_err(|_| truncated())?;
+                    (offset as usize, (tag >> 2) as usize + 1)
+                }
+            };
+
+            if offset == 0 || offset > decompressed.len() {
+                return Err(corrupted("snappy copy points outside of the data"));
+            }
+            if decompressed.len() + copy_length > length {
+                return Err(corrupted("snappy data is longer than its length"));
+            }
+
+            // the copy may overlap the bytes it produces, so copy one byte at a time
+            let start = decompressed.len() - offset;
+            for i in 0..copy_length {
+                decompressed.push(decompressed[start + i]);
+            }
+        }
+
+        if decompressed.len() != length {
+            return Err(corrupted("snappy data is shorter than its length"));
+        }
+        Ok(decompressed)
+    }
+}
+
+impl Codec for SnappyRaw {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        Ok(Self::compress(data))
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        Self::decompress(data, max_size.min(u32::MAX as usize))
+    }
+}
+
+/// Snappy represents the Snappy framing format, read and written by Snappy libraries and
+/// tools such as `snzip`. A stream is a list of chunks, each a type byte, a 3 byte little
+/// endian length and its body:
+///
+/// | type        | body                                                        |
+/// |-------------|-------------------------------------------------------------|
+/// | `ff`        | the stream identifier `sNaPpY`, first in the stream          |
+/// | `00`        | masked CRC-32C of the data, then up to 64 KiB in the raw format |
+/// | `01`        | masked CRC-32C of the data, then up to 64 KiB stored as is   |
+/// | `80` - `fe` | padding and reserved chunks, skipped                         |
+///
+/// Chunks are stored as is when compressing saves less than an eighth of them, as the
+/// reference implementation does. Decoding reads streams following each other, and empty
+/// data as an empty stream.
+#[derive(Default)]
+pub struct Snappy {}
+
+impl Snappy {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// write_chunk writes a chunk of data with its checksum
+    fn write_chunk(encoded: &mut Vec<u8>, chunk_type: u8, checksum: u32, body: &[u8]) {
+        encoded.push(chunk_type);
+        encoded.extend_from_slice(&((body.len() + 4) as u32).to_le_bytes()[..3]);
+        encoded.extend_from_slice(&checksum.to_le_bytes());
+        encoded.extend_from_slice(body);
+    }
+}
+
+impl Codec for Snappy {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = STREAM_IDENTIFIER.to_vec();
+        for chunk in data.chunks(MAX_CHUNK_DATA) {
+            let checksum = masked_crc(chunk);
+            let compressed = SnappyRaw::compress(chunk);
+            match compressed.len() < chunk.len() - chunk.len() / 8 {
+                true => Self::write_chunk(&mut encoded, CHUNK_COMPRESSED, checksum, &compressed),
+                false => Self::write_chunk(&mut encoded, CHUNK_UNCOMPRESSED, checksum, chunk),
+            }
+        }
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        if !data.is_empty() && !is_snappy(data) {
+            return Err(CodecError::new(
+                "data is not a snappy frame stream",
+                CodecErrorKind::InvalidMagic,
+            ));
+        }
+
+        let mut cursor = ByteCursor::new(data);
+        let mut decoded = Vec::new();
+        while !cursor.remaining().is_empty() {
+            let chunk_type = cursor.read_u8()?;
+            let length = cursor.read_bytes(3)?;
+            let length = u32::from_le_bytes([length[0], length[1], length[2], 0]) as usize;
+            let body = cursor.read_bytes(length)?;
+
+            let chunk = match chunk_type {
+                CHUNK_STREAM_IDENTIFIER => {
+                    if body != &STREAM_IDENTIFIER[4..] {
+                        return Err(corrupted("snappy stream identifier is not valid"));
+                    }
+                    continue;
+                }
+                CHUNK_COMPRESSED | CHUNK_UNCOMPRESSED if body.len() >= 4 => {
+                    let chunk = match chunk_type {
+                        CHUNK_COMPRESSED => SnappyRaw::decompress(&body[4..], MAX_CHUNK_DATA)?,
+                        _ => body[4..].to_vec(),
+                    };
+                    if chunk.len() > MAX_CHUNK_DATA {
+                        return Err(corrupted("snappy chunk is larger than its maximum size"));
+                    }
+                    if u32::from_le_bytes(body[..4].try_into().unwrap()) != masked_crc(&chunk) {
+                        return Err(CodecError::new(
+                            "snappy chunk does not match its checksum",
+                            CodecErrorKind::ChecksumMismatch,
+                        ));
+                    }
+                    chunk
+                }
+                CHUNK_COMPRESSED | CHUNK_UNCOMPRESSED => {
+                    return Err(corrupted("snappy chunk is too short for its checksum"));
+                }
+                0x02..=0x7f => {
+                    return Err(corrupted("snappy stream has a reserved chunk"));
+                }
+                // padding and skippable chunks hold nothing to decode
+                0x80..=CHUNK_PADDING => continue,
+            };
+            check_decoded_size(decoded.len() + chunk.len(), max_size)?;
+            decoded.extend(chunk);
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::algorithms::lz4::Lz4;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    const LINES: &[u8] = include_bytes!("../../../test_data/deflate/dynamic.txt");
+    const LINES_SZ: &[u8] = include_bytes!("../../../test_data/snappy/lines.sz");
+    const LINES_SNAPPY: &[u8] = include_bytes!("../../../test_data/snappy/lines.snappy");
+
+    #[test]
+    fn snappy_files_are_read() {
+        // written by the snap crate, the raw format and a stream of a single chunk
+        assert_eq!(SnappyRaw::new().decode(LINES_SNAPPY).unwrap(), LINES);
+        assert_eq!(Snappy::new().decode(LINES_SZ).unwrap(), LINES);
+
+        // data over several chunks
+        let chunks = include_bytes!("../../../test_data/snappy/chunks.sz");
+        assert_eq!(Snappy::new().decode(chunks).unwrap(), LINES.repeat(8));
+
+        // data that does not compress is stored in an uncompressed chunk
+        let random = include_bytes!("../../../test_data/snappy/random.sz");
+        assert_eq!(random[STREAM_IDENTIFIER.len()], CHUNK_UNCOMPRESSED);
+        assert_eq!(
+            Snappy::new().decode(random).unwrap(),
+            include_bytes!("../../../test_data/lz4/random.bin")
+        );
+
+        // the snap crate writes nothing at all for empty data
+        assert_eq!(Snappy::new().decode(b"").unwrap(), b"");
+    }
+
+    #[test]
+    fn snappy_works() {
+        let mut rng = XorShift::new(9);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"".to_vec(),
+            b"a".to_vec(),
+            b"abcabcabcabcabcabcabc".to_vec(),
+            vec![0u8; 300_000],
+            rng.bytes(100_000),
+            (0..200_000)
+                .map(|_| b'a' + (rng.next_u64() % 3) as u8)
+                .collect(),
+            LINES.repeat(6),
+        ]);
+
+        for test_case in test_cases {
+            let encoded = SnappyRaw::new().encode(&test_case).unwrap();
+            assert_eq!(SnappyRaw::new().decode(&encoded).unwrap(), test_case);
+
+            let encoded = Snappy::new().encode(&test_case).unwrap();
+            assert!(is_snappy(&encoded));
+            assert_eq!(Snappy::new().decode(&encoded).unwrap(), test_case);
+        }
+    }
+
+    #[test]
+    fn elements_follow_the_format() {
+        // a literal, a copy of 4 bytes from 4 bytes back, and the literal of the last bytes
+        let data = b"abcdabcdxyz0123456789";
+        let compressed = SnappyRaw::compress(data);
+        assert_eq!(
+            compressed,
+            [
+                &[21, 0x0c, b'a', b'b', b'c', b'd', 0x01, 4, 0x30][..],
+                &data[8..]
+            ]
+            .concat()
+        );
+
+        // literals of 61 bytes or more hold their length after the tag
+        let data = (0..100).collect::<Vec<u8>>();
+        assert_eq!(&SnappyRaw::compress(&data)[..4], &[100, 0xf0, 99, 0]);
+
+        // long matches are split into copies of at most 64 bytes with a 2 byte offset
+        let zeros = vec![0u8; 1000];
+        let compressed = SnappyRaw::compress(&zeros);
+        assert_eq!(&compressed[..7], &[0xe8, 0x07, 0x00, 0x00, 0xfe, 1, 0]);
+        assert_eq!(compressed.len(), 52);
+        assert_eq!(SnappyRaw::decompress(&compressed, 1000).unwrap(), zeros);
+
+        // copies with a 4 byte offset are read, although they are never written
+        let copy_4 = [8, 0x0c, b'a', b'b', b'c', b'd', 0x0f, 4, 0, 0, 0];
+        assert_eq!(SnappyRaw::decompress(&copy_4, 8).unwrap(), b"abcdabcd");
+    }
+
+    #[test]
+    fn snappy_compresses_like_the_reference() {
+        // the same match finder as the reference implementation writes the same bytes
+        let compressed = SnappyRaw::compress(LINES);
+        assert_eq!(compressed, LINES_SNAPPY);
+        assert_eq!(Snappy::new().encode(LINES).unwrap(), LINES_SZ);
+
+        // snappy and lz4 trade ratio for speed alike
+        let lz4 = Lz4::new().encode(LINES).unwrap();
+        assert!(compressed.len() < LINES.len() / 2);
+        assert!(compressed.len() < lz4.len() + lz4.len() / 5);
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut snappy = Snappy::new();
+        assert_eq!(
+            snappy.decode(b"TCZ\x1a").unwrap_err().kind,
+            CodecErrorKind::InvalidMagic
+        );
+        assert!(snappy.decode(&LINES_SZ[..LINES_SZ.len() - 1]).is_err());
+
+        // a flipped byte of the data or of its checksum
+        for position in [12, LINES_SZ.len() - 1] {
+            let mut corrupted = LINES_SZ.to_vec();
+            corrupted[position] ^= 0x10;
+            assert!(snappy.decode(&corrupted).is_err());
+        }
+        let mut corrupted = LINES_SZ.to_vec();
+        corrupted[STREAM_IDENTIFIER.len() + 4] ^= 1;
+        assert_eq!(
+            snappy.decode(&corrupted).unwrap_err().kind,
+            CodecErrorKind::ChecksumMismatch
+        );
+
+        // padding and skippable chunks are skipped, reserved chunks are not
+        let padding = [CHUNK_PADDING, 2, 0, 0, 0, 0];
+        let skippable = [0x80, 1, 0, 0, 7];
+        let data = [LINES_SZ, &padding, &skippable, &STREAM_IDENTIFIER].concat();
+        assert_eq!(snappy.decode(&data).unwrap(), LINES);
+        let reserved = [LINES_SZ, &[0x02, 1, 0, 0, 7]].concat();
+        assert!(snappy.decode(&reserved).is_err());
+
+        // raw data that is shorter or longer than its length, or copies from before its start
+        let mut raw = SnappyRaw::new();
+        assert!(raw.decode(&[]).is_err());
+        assert!(raw.decode(&LINES_SNAPPY[..LINES_SNAPPY.len() - 1]).is_err());
+        assert!(raw.decode(&[2, 0x04, b'a', b'b', 0x00, b'c']).is_err());
+        assert!(raw
+            .decode(&[8, 0x0c, b'a', b'b', b'c', b'd', 0x01, 5])
+            .is_err());
+        assert!(raw.decode(&[4, 0x0e, 0, 0]).is_err());
+        assert!(SnappyRaw::decompress(&[0xff, 0xff, 0x04], MAX_CHUNK_DATA).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::{
+    algorithms::deflate::Deflate,
+    data_structures::{bit_stream::BitReader, byte_cursor::ByteCursor},
+    pkg::traits::Codec,
+};
+use crate::utils::checksum::adler32;
+
+/// HEADER is written before every stream: DEFLATE with a 32 KiB window, at the default level
+const HEADER: [u8; 2] = [0x78, 0x9c];
+
+/// DEFLATED is the compression method of every zlib stream
+const DEFLATED: u8 = 8;
+
+/// MAX_WINDOW_INFO is the largest window of DEFLATE, log2 of 32 KiB minus 8
+const MAX_WINDOW_INFO: u8 = 7;
+
+/// FLAG_DICTIONARY marks a stream compressed against a preset dictionary
+const FLAG_DICTIONARY: u8 = 1 << 5;
+
+/// is_zlib returns true if the bytes start with a valid zlib header
+pub fn is_zlib(bytes: &[u8]) -> bool {
+    match bytes {
+        [method, flags, ..] => {
+            method & 0x0f == DEFLATED
+                && method >> 4 <= MAX_WINDOW_INFO
+                && u16::from_be_bytes([*method, *flags]).is_multiple_of(31)
+        }
+        _ => false,
+    }
+}
+
+/// Zlib represents the zlib format of RFC 1950: a 2 byte header, a DEFLATE stream and the
+/// Adler-32 checksum of the data in big endian. It is read by zlib and by the tools and
+/// formats built on it, such as PNG.
+#[derive(Default)]
+pub struct Zlib {}
+
+impl Zlib {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Codec for Zlib {
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut encoded = HEADER.to_vec();
+        encoded.extend(Deflate::new().encode(data)?);
+        encoded.extend_from_slice(&adler32(data).to_be_bytes());
+        Ok(encoded)
+    }
+
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        if !is_zlib(data) {
+            return Err(CodecError::new(
+                "data is not a zlib stream",
+                CodecErrorKind::InvalidMagic,
+            ));
+        }
+        if data[1] & FLAG_DICTIONARY != 0 {
+            return Err(CodecError::new(
+                "zlib streams with a preset dictionary are not supported",
+                CodecErrorKind::InvalidInput,
+            ));
+        }
+
+        let mut cursor = ByteCursor::new(&data[HEADER.len()..]);
+        let mut reader = BitReader::new(cursor.remaining());
+        let decoded = Deflate::inflate(&mut reader, max_size)?;
+        cursor.read_bytes(reader.consumed_bytes())?;
+
+        if cursor.read_u32_be()? != adler32(&decoded) {
+            return Err(CodecError::new(
+                "zlib data does not match its checksum",
+                CodecErrorKind::ChecksumMismatch,
+            ));
+        }
+        if !cursor.remaining().is_empty() {
+            return Err(CodecError::new(
+                "zlib stream has trailing bytes after its checksum",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::binary_samples;
+
+    const LINES: &[u8] = include_bytes!("../../../test_data/deflate/dynamic.txt");
+    const LINES_ZLIB: &[u8] = include_bytes!("../../../test_data/zlib/lines.zlib");
+
+    #[test]
+    fn zlib_streams_are_read() {
+        assert_eq!(Zlib::new().decode(LINES_ZLIB).unwrap(), LINES);
+
+        // level 1 is marked in the header, which does not change how the stream is read
+        let fast = include_bytes!("../../../test_data/zlib/fast.zlib");
+        assert_eq!(&fast[..2], &[0x78, 0x01]);
+        assert_eq!(
+            Zlib::new().decode(fast).unwrap(),
+            include_bytes!("../../../test_data/zlib/fast.txt")
+        );
+    }
+
+    #[test]
+    fn zlib_works() {
+        let mut test_cases = binary_samples();
+        test_cases.extend([b"".to_vec(), b"a".to_vec(), LINES.to_vec()]);
+
+        for test_case in test_cases {
+            let encoded = Zlib::new().encode(&test_case).unwrap();
+            assert!(is_zlib(&encoded));
+            assert_eq!(Zlib::new().decode(&encoded).unwrap(), test_case);
+        }
+
+        // the checksum is the same as the one of zlib
+        let encoded = Zlib::new().encode(LINES).unwrap();
+        assert_eq!(
+            &encoded[encoded.len() - 4..],
+            &LINES_ZLIB[LINES_ZLIB.len() - 4..]
+        );
+    }
+
+    #[test]
+    fn decoder_rejects_corrupted_data() {
+        let mut zlib = Zlib::new();
+        assert!(!is_zlib(b"TCZ\x1a"));
+        assert!(!is_zlib(&[0x1f, 0x8b]));
+        assert_eq!(
+            zlib.decode(&[0x78, 0x9d]).unwrap_err().kind,
+            CodecErrorKind::InvalidMagic
+        );
+        assert!(zlib.decode(&LINES_ZLIB[..LINES_ZLIB.len() - 1]).is_err());
+
+        // a preset dictionary
+        assert_eq!(
+            zlib.decode(&[0x78, 0xbb, 0, 0, 0, 1]).unwrap_err().kind,
+            CodecErrorKind::InvalidInput
+        );
+
+        let mut corrupted = LINES_ZLIB.to_vec();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert_eq!(
+            zlib.decode(&corrupted).unwrap_err().kind,
+            CodecErrorKind::ChecksumMismatch
+        );
+
+        let trailing = [LINES_ZLIB, b"junk"].concat();
+        assert!(zlib.decode(&trailing).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::service::data_structures::bit_stream::{BitReader, BitWriter};
+
+/// ALPHABET_SIZE is the number of symbols an adaptive tree can hold
+const ALPHABET_SIZE: usize = 256;
+
+/// MAX_NODES is the size of a tree holding every symbol and the NYT leaf
+const MAX_NODES: usize = 2 * ALPHABET_SIZE + 1;
+
+/// ROOT is the number of the root, the highest number of the tree
+const ROOT: usize = MAX_NODES - 1;
+
+/// RAW_BITS is the width of the raw value following the NYT code, wide enough for every
+/// symbol and END_OF_STREAM
+const RAW_BITS: u32 = 9;
+
+/// END_OF_STREAM is the raw value after the NYT code that ends the data. It never gets a leaf
+pub const END_OF_STREAM: u16 = 256;
+
+/// Kind tells what sits at a node of the adaptive tree
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    // the leaf of the symbols not transmitted yet
+    Nyt,
+    Leaf(u8),
+    Internal { left: usize, right: usize },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct AdaptiveNode {
+    weight: u64,
+    parent: usize,
+    kind: Kind,
+}
+
+/// AdaptiveHuffmanTree is a Huffman tree updated after every symbol with the FGK algorithm.
+/// Nodes are stored by their number, which grows with the weight (the sibling property),
+/// so the tree is rebalanced by swapping the content of two slots.
+/// Symbols seen for the first time are coded with the code of the NYT leaf followed by
+/// their raw value, and the end of the data with the NYT code followed by END_OF_STREAM.
+pub struct AdaptiveHuffmanTree {
+    nodes: Vec<AdaptiveNode>,
+    leaves: Vec<Option<usize>>,
+    nyt: usize,
+}
+
+impl AdaptiveHuffmanTree {
+    /// new creates a tree holding only the NYT leaf
+    pub fn new() -> Self {
+        let empty = AdaptiveNode {
+            weight: 0,
+            parent: ROOT,
+            kind: Kind::Nyt,
+        };
+        Self {
+            nodes: vec![empty; MAX_NODES],
+            leaves: vec![None; ALPHABET_SIZE],
+            nyt: ROOT,
+        }
+    }
+
+    /// write_symbol writes the code of the symbol, followed by its raw value when it is new
+    pub fn write_symbol(&self, writer: &mut BitWriter, symbol: u8) {
+        match self.leaves[symbol as usize] {
+            Some(leaf) => self.write_code(writer, leaf),
+            None => {
+                self.write_code(writer, self.nyt);
+                writer.write_bits(symbol as u64, RAW_BITS);
+            }
+        }
+    }
+
+    /// write_end writes the NYT code followed by END_OF_STREAM, which the NYT leaf
+    /// always has since END_OF_STREAM never gets a leaf
+    pub fn write_end(&self, writer: &mut BitWriter) {
+        self.write_code(writer, self.nyt);
+        writer.write_bits(END_OF_STREAM as u64, RAW_BITS);
+    }
+
+    /// write_code writes the path from the root to the node, 0 going left and 1 going right
+    fn write_code(&self, writer: &mut BitWriter, node: usize) {
+        let mut path = Vec::new();
+        let mut node = node;
+        while node != ROOT {
+            let parent = self.nodes[node].parent;
+            path.push(
+                matches!(self.nodes[parent].kind, Kind::Internal { right, .. } if right == node),
+            );
+            node = parent;
+        }
+
+        for bit in path.into_iter().rev() {
+            writer.write_bit(bit);
+        }
+    }
+
+    /// read_symbol walks the tree along the bits of the reader, reading the raw value of a new symbol
+    /// after the NYT leaf. The raw value may be END_OF_STREAM, or above it in corrupted data.
+    /// It returns None when the stream ends
+    pub fn read_symbol(&self, reader: &mut BitReader) -> Option<u16> {
+        let mut node = ROOT;
+        loop {
+            match self.nodes[node].kind {
+                Kind::Leaf(symbol) => return Some(symbol as u16),
+                Kind::Nyt => return Some(reader.read_bits(RAW_BITS)? as u16),
+                Kind::Internal { left, right } => {
+                    node = if reader.read_bit()? { right } else { left };
+                }
+            }
+        }
+    }
+
+    /// update adds one to the weight of the symbol, adding a leaf for it when it is new
+    pub fn update(&mut self, symbol: u8) {
+        let mut node = match self.leaves[symbol as usize] {
+            Some(leaf) => leaf,
+            None => self.split_nyt(symbol),
+        };
+
+        loop {
+            // move the node past every node of the same weight, unless that node is its parent
+            let leader = self.leader(node);
+            if leader != node && leader != self.nodes[node].parent {
+                self.swap(node, leader);
+                node = leader;
+            }
+
+            self.nodes[node].weight += 1;
+            if node == ROOT {
+                break;
+            }
+            node = self.nodes[node].parent;
+        }
+    }
+
+    /// split_nyt turns the NYT leaf into a node holding a new NYT leaf and a leaf for the symbol,
+    /// and returns the leaf of the symbol
+    fn split_nyt(&mut self, symbol: u8) -> usize {
+        let parent = self.nyt;
+        let (nyt, leaf) = (parent - 2, parent - 1);
+
+        self.nodes[parent].kind = Kind::Internal {
+            left: nyt,
+            right: leaf,
+        };
+        self.nodes[nyt] = AdaptiveNode {
+            weight: 0,
+            parent,
+            kind: Kind::Nyt,
+        };
+        self.nodes[leaf] = AdaptiveNode {
+            weight: 0,
+            parent,
+            kind: Kind::Leaf(symbol),
+        };
+
+        self.nyt = nyt;
+        self.leaves[symbol as usize] = Some(leaf);
+        leaf
+    }
+
+    /// leader returns the highest numbered node with the same weight as the node
+    fn leader(&self, node: usize) -> usize {
+        let weight = self.nodes[node].weight;
+        let mut leader = node;
+        while leader < ROOT && self.nodes[leader + 1].weight == weight {
+            leader += 1;
+        }
+        leader
+    }
+
+    /// swap exchanges the subtrees at two slots, each slot keeping its parent
+    fn swap(&mut self, a: usize, b: usize) {
+        let (weight, kind) = (self.nodes[a].weight, self.nodes[a].kind);
+        self.nodes[a].weight = self.nodes[b].weight;
+        self.nodes[a].kind = self.nodes[b].kind;
+        self.nodes[b].weight = weight;
+        self.nodes[b].kind = kind;
+
+        for slot in [a, b] {
+            match self.nodes[slot].kind {
+                Kind::Nyt => self.nyt = slot,
+                Kind::Leaf(symbol) => self.leaves[symbol as usize] = Some(slot),
+                Kind::Internal { left, right } => {
+                    self.nodes[left].parent = slot;
+                    self.nodes[right].parent = slot;
+                }
+            }
+        }
+    }
+
+    /// depth returns the length of the code of the symbol, or None if it has not been seen
+    #[cfg(test)]
+    fn depth(&self, symbol: u8) -> Option<usize> {
+        let mut node = self.leaves[symbol as usize]?;
+        let mut depth = 0;
+        while node != ROOT {
+            node = self.nodes[node].parent;
+            depth += 1;
+        }
+        Some(depth)
+    }
+}
+
+impl Default for AdaptiveHuffmanTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// assert_sibling_property checks that weights never decrease with the node numbers
+    /// and that every internal node weighs as much as its children
+    fn assert_sibling_property(tree: &AdaptiveHuffmanTree) {
+        let used = &tree.nodes[tree.nyt..];
+        assert!(used.windows(2).all(|pair| pair[0].weight <= pair[1].weight));
+
+        for (slot, node) in tree.nodes.iter().enumerate().skip(tree.nyt) {
+            if let Kind::Internal { left, right } = node.kind {
+                assert_eq!(
+                    node.weight,
+                    tree.nodes[left].weight + tree.nodes[right].weight
+                );
+                assert_eq!(tree.nodes[left].parent, slot);
+                assert_eq!(tree.nodes[right].parent, slot);
+            }
+        }
+    }
+
+    #[test]
+    fn sibling_property_holds() {
+        let mut tree = AdaptiveHuffmanTree::new();
+        for symbol in b"abracadabra! mississippi".iter().chain(&[0, 255, 0, 0]) {
+            tree.update(*symbol);
+            assert_sibling_property(&tree);
+        }
+        assert_eq!(tree.nodes[ROOT].weight, 28);
+
+        // the most frequent symbols end up closest to the root
+        assert!(tree.depth(b'a') <= tree.depth(b'd'));
+        assert!(tree.depth(b's') <= tree.depth(b'!'));
+        assert_eq!(tree.depth(b'z'), None);
+    }
+
+    #[test]
+    fn every_symbol_fits() {
+        let mut tree = AdaptiveHuffmanTree::new();
+        for symbol in (0..=255u8).chain(0..=255u8) {
+            tree.update(symbol);
+        }
+        assert_sibling_property(&tree);
+        assert_eq!(tree.nyt, 0);
+        assert!((0..=255u8)
+            .all(|symbol| tree.depth(symbol) == Some(8) || tree.depth(symbol) == Some(9)));
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let text = b"adaptive huffman trees change as they go";
+        let mut writer = BitWriter::new();
+        let mut encoder = AdaptiveHuffmanTree::new();
+        for symbol in text {
+            encoder.write_symbol(&mut writer, *symbol);
+            encoder.update(*symbol);
+        }
+        encoder.write_end(&mut writer);
+        let bytes = writer.finish();
+
+        let mut reader = BitReader::new(&bytes);
+        let mut decoder = AdaptiveHuffmanTree::new();
+        for symbol in text {
+            assert_eq!(decoder.read_symbol(&mut reader), Some(*symbol as u16));
+            decoder.update(*symbol);
+        }
+        assert_eq!(decoder.read_symbol(&mut reader), Some(END_OF_STREAM));
+    }
+
+    #[test]
+    fn end_fits_a_full_tree() {
+        let mut tree = AdaptiveHuffmanTree::new();
+        for symbol in 0..=255u8 {
+            tree.update(symbol);
+        }
+
+        let mut writer = BitWriter::new();
+        tree.write_end(&mut writer);
+        let bytes = writer.finish();
+        assert_eq!(
+            tree.read_symbol(&mut BitReader::new(&bytes)),
+            Some(END_OF_STREAM)
+        );
+    }
+}
@@ -0,0 +1,217 @@
+/// BitWriter packs bits into a vector of bytes, filling each byte from its least significant bit
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    n_buffered: u32,
+}
+
+impl BitWriter {
+    /// new initializes an empty bit writer
+    pub fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            buffer: 0,
+            n_buffered: 0,
+        }
+    }
+
+    /// write_bit appends a single bit to the stream
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(bit as u64, 1);
+    }
+
+    /// write_bits appends the lowest `n_bits` bits of `value`, least significant bit first
+    pub fn write_bits(&mut self, value: u64, n_bits: u32) {
+        debug_assert!(n_bits <= 32, "at most 32 bits can be written at once");
+        let mask = (1u64 << n_bits) - 1;
+        self.buffer |= (value & mask) << self.n_buffered;
+        self.n_buffered += n_bits;
+
+        // flush every complete byte out of the buffer
+        while self.n_buffered >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.n_buffered -= 8;
+        }
+    }
+
+    /// align_to_byte pads the current byte with zero bits, so the next bit starts a new byte
+    pub fn align_to_byte(&mut self) {
+        // the buffer never holds a whole byte between writes
+        if self.n_buffered > 0 {
+            self.write_bits(0, 8 - self.n_buffered);
+        }
+    }
+
+    /// write_bytes appends whole bytes, the stream must be aligned to a byte
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert!(
+            self.n_buffered == 0,
+            "bytes can only be written on a byte boundary"
+        );
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// finish pads the last byte with zero bits and returns the packed bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.n_buffered > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+impl Default for BitWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// BitReader reads bits back from bytes packed by a BitWriter
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// new initializes a bit reader positioned at the first bit of `bytes`
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    /// read_bit returns the next bit or None if the stream is exhausted
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.position / 8)?;
+        let bit = (byte >> (self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Some(bit)
+    }
+
+    /// read_bits returns the next `n_bits` bits as a number or None if the stream is exhausted
+    pub fn read_bits(&mut self, n_bits: u32) -> Option<u64> {
+        if self.remaining() < n_bits as usize {
+            return None;
+        }
+
+        let mut value = 0u64;
+        for i in 0..n_bits {
+            if self.read_bit()? {
+                value |= 1 << i;
+            }
+        }
+        Some(value)
+    }
+
+    /// peek_bits returns the next `n_bits` bits without consuming them, padding with zero bits
+    /// past the end of the stream
+    pub fn peek_bits(&self, n_bits: u32) -> u64 {
+        debug_assert!(n_bits <= 32, "at most 32 bits can be peeked at once");
+        let start = (self.position / 8).min(self.bytes.len());
+
+        // five bytes hold the 32 bits even when the position is in the middle of a byte
+        let mut word = 0u64;
+        for (i, byte) in self.bytes[start..].iter().take(5).enumerate() {
+            word |= (*byte as u64) << (8 * i);
+        }
+        (word >> (self.position % 8)) & ((1u64 << n_bits) - 1)
+    }
+
+    /// skip_bits consumes up to `n_bits` bits
+    pub fn skip_bits(&mut self, n_bits: usize) {
+        self.position += n_bits.min(self.remaining());
+    }
+
+    /// align_to_byte skips the rest of the current byte, so the next bit starts a new byte
+    pub fn align_to_byte(&mut self) {
+        self.skip_bits((8 - self.position % 8) % 8);
+    }
+
+    /// read_bytes returns the next `n_bytes` whole bytes or None if the stream is exhausted,
+    /// the stream must be aligned to a byte
+    pub fn read_bytes(&mut self, n_bytes: usize) -> Option<&'a [u8]> {
+        debug_assert!(
+            self.position.is_multiple_of(8),
+            "bytes can only be read on a byte boundary"
+        );
+        let start = self.position / 8;
+        let bytes = self.bytes.get(start..start + n_bytes)?;
+        self.position += 8 * n_bytes;
+        Some(bytes)
+    }
+
+    /// consumed_bytes returns the number of bytes read so far, the current byte included
+    pub fn consumed_bytes(&self) -> usize {
+        self.position.div_ceil(8)
+    }
+
+    /// remaining returns the number of unread bits
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn write_read_bits_works() {
+        let mut writer = BitWriter::new();
+        writer.write_bit(true);
+        writer.write_bits(0b1011, 4);
+        writer.write_bits(300, 9);
+        writer.write_bit(false);
+
+        let bytes = writer.finish();
+        assert_eq!(bytes.len(), 2);
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(reader.read_bit(), Some(true));
+        assert_eq!(reader.read_bits(4), Some(0b1011));
+        assert_eq!(reader.read_bits(9), Some(300));
+        assert_eq!(reader.read_bit(), Some(false));
+        assert_eq!(reader.remaining(), 1);
+        assert_eq!(reader.read_bits(2), None);
+    }
+
+    #[test]
+    fn peek_skip_bits_works() {
+        let mut writer = BitWriter::new();
+        writer.write_bits(0b101, 3);
+        writer.write_bits(0xabcd_ef12, 32);
+        let bytes = writer.finish();
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(reader.peek_bits(3), 0b101);
+        reader.skip_bits(3);
+        assert_eq!(reader.peek_bits(32), 0xabcd_ef12);
+        assert_eq!(reader.read_bits(32), Some(0xabcd_ef12));
+
+        // peeking past the end pads with zero bits
+        assert_eq!(reader.remaining(), 5);
+        assert_eq!(reader.peek_bits(8), 0);
+        reader.skip_bits(100);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn aligned_bytes_work() {
+        let mut writer = BitWriter::new();
+        writer.write_bits(0b11, 2);
+        writer.align_to_byte();
+        writer.align_to_byte();
+        writer.write_bytes(b"abc");
+        writer.write_bit(true);
+        let bytes = writer.finish();
+        assert_eq!(bytes, vec![0b11, b'a', b'b', b'c', 1]);
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(reader.read_bits(2), Some(0b11));
+        assert_eq!(reader.consumed_bytes(), 1);
+        reader.align_to_byte();
+        assert_eq!(reader.read_bytes(3), Some(b"abc".as_slice()));
+        assert_eq!(reader.consumed_bytes(), 4);
+        assert_eq!(reader.read_bytes(2), None);
+        assert_eq!(reader.read_bit(), Some(true));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+
+/// MAX_VARINT_SIZE is the most bytes a varint of a u64 takes
+pub const MAX_VARINT_SIZE: usize = 10;
+
+/// write_varint appends `value` as a LEB128 variable length integer
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// ByteCursor reads header fields from the front of a compressed payload
+pub struct ByteCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteCursor<'a> {
+    /// new initializes a cursor positioned at the first byte of `bytes`
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn truncated() -> CodecError {
+        CodecError::new(
+            "compressed data ended unexpectedly",
+            CodecErrorKind::CorruptedData,
+        )
+    }
+
+    /// read_u8 reads a single byte
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        let byte = *self.bytes.get(self.position).ok_or_else(Self::truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    /// read_bytes reads the next `n` bytes
+    pub fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if self.bytes.len() - self.position < n {
+            return Err(Self::truncated());
+        }
+        let bytes = &self.bytes[self.position..self.position + n];
+        self.position += n;
+        Ok(bytes)
+    }
+
+    /// read_u16_le reads a little endian 16 bit integer
+    pub fn read_u16_le(&mut self) -> Result<u16, CodecError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// read_u32_le reads a little endian 32 bit integer
+    pub fn read_u32_le(&mut self) -> Result<u32, CodecError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// read_u32_be reads a big endian 32 bit integer
+    pub fn read_u32_be(&mut self) -> Result<u32, CodecError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// read_u64_le reads a little endian 64 bit integer
+    pub fn read_u64_le(&mut self) -> Result<u64, CodecError> {
+        let bytes = self.read_bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// read_varint reads a LEB128 variable length integer
+    pub fn read_varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CodecError::new(
+            "variable length integer is too long",
+            CodecErrorKind::CorruptedData,
+        ))
+    }
+
+    /// remaining returns every byte that has not been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip_works() {
+        let values = [0u64, 1, 127, 128, 300, 16384, u32::MAX as u64, u64::MAX];
+
+        let mut out = Vec::new();
+        for value in values {
+            write_varint(&mut out, value);
+        }
+        out.push(42);
+
+        let mut cursor = ByteCursor::new(&out);
+        for value in values {
+            assert_eq!(cursor.read_varint().unwrap(), value);
+        }
+        assert_eq!(cursor.remaining(), &[42]);
+        assert_eq!(cursor.read_u8().unwrap(), 42);
+        assert!(cursor.read_u8().is_err());
+    }
+
+    #[test]
+    fn integers_work() {
+        let bytes = [0x34, 0x12, 0x12, 0x34, 0x56, 0x78];
+        let mut cursor = ByteCursor::new(&bytes);
+        assert_eq!(cursor.read_u16_le().unwrap(), 0x1234);
+        assert_eq!(cursor.read_u32_be().unwrap(), 0x1234_5678);
+        assert!(cursor.read_u16_le().is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! A canonical prefix code is fully described by the code length of every
+//! symbol: codes are handed out in increasing order, shorter codes first and
+//! symbols of the same length in increasing order. Only the lengths have to be
+//! stored, and decoding needs tables instead of a tree.
+//!
+//! Codes are written most significant bit first into the bit stream.
+
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::data_structures::bit_stream::{BitReader, BitWriter};
+
+/// MAX_CODE_LENGTH is the longest code length a canonical code can hold
+pub const MAX_CODE_LENGTH: usize = 63;
+
+/// LOOKUP_BITS is the number of bits resolved by a single lookup while decoding
+const LOOKUP_BITS: u32 = 10;
+
+/// canonical_codes returns the code of every symbol from its code length, symbols of length 0 get no code
+pub fn canonical_codes(lengths: &[u8]) -> Vec<u64> {
+    let max_length = lengths.iter().copied().max().unwrap_or(0) as usize;
+    let mut counts = vec![0u64; max_length + 1];
+    for length in lengths.iter().filter(|length| **length > 0) {
+        counts[*length as usize] += 1;
+    }
+
+    // the first code of every length follows the last code of the previous length
+    let mut next_code = vec![0u64; max_length + 1];
+    let mut code = 0u64;
+    for length in 1..=max_length {
+        code = (code + counts[length - 1]) << 1;
+        next_code[length] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|length| match *length as usize {
+            0 => 0,
+            length => {
+                next_code[length] += 1;
+                next_code[length] - 1
+            }
+        })
+        .collect()
+}
+
+/// reverse_bits reverses the lowest `length` bits of the code
+fn reverse_bits(code: u64, length: u8) -> u64 {
+    match length {
+        0 => 0,
+        length => code.reverse_bits() >> (64 - length as u32),
+    }
+}
+
+/// CanonicalEncoder writes the canonical code of symbols into a bit stream
+pub struct CanonicalEncoder {
+    lengths: Vec<u8>,
+    // the codes are kept bit reversed, as the bit writer fills bytes from the least significant bit
+    reversed_codes: Vec<u64>,
+}
+
+impl CanonicalEncoder {
+    /// new builds the codes from the code length of every symbol
+    pub fn new(lengths: &[u8]) -> Self {
+        let reversed_codes = canonical_codes(lengths)
+            .into_iter()
+            .zip(lengths)
+            .map(|(code, length)| reverse_bits(code, *length))
+            .collect();
+
+        Self {
+            lengths: lengths.to_vec(),
+            reversed_codes,
+        }
+    }
+
+    /// write_symbol writes the code of the symbol, which must have a code length
+    pub fn write_symbol(&self, writer: &mut BitWriter, symbol: u16) {
+        let length = self.lengths[symbol as usize] as u32;
+        let code = self.reversed_codes[symbol as usize];
+
+        // the writer takes at most 32 bits at once
+        writer.write_bits(code, length.min(32));
+        if length > 32 {
+            writer.write_bits(code >> 32, length - 32);
+        }
+    }
+}
+
+/// CanonicalDecoder reads symbols written by a CanonicalEncoder with the same code lengths.
+/// Codes of at most LOOKUP_BITS bits are decoded with a single table lookup, and
+/// longer ones by walking the first code of every length.
+pub struct CanonicalDecoder {
+    max_length: usize,
+    // counts[length] is the number of symbols with that code length
+    counts: Vec<u64>,
+    // symbols sorted by code, that is by code length and then by symbol
+    symbols: Vec<u16>,
+    lookup_bits: u32,
+    // lookup maps the next lookup_bits bits of the stream to a symbol and its code length,
+    // a length of 0 sends the decoding to the slower walk
+    lookup: Vec<(u16, u8)>,
+}
+
+impl CanonicalDecoder {
+    /// new builds the decoding tables, rejecting lengths that cannot form a prefix code
+    pub fn new(lengths: &[u8]) -> Result<Self, CodecError> {
+        let max_length = lengths.iter().copied().max().unwrap_or(0) as usize;
+        if max_length > MAX_CODE_LENGTH {
+            return Err(CodecError::new(
+                "code length is longer than the maximum code length",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+
+        let mut counts = vec![0u64; max_length + 1];
+        for length in lengths.iter().filter(|length| **length > 0) {
+            counts[*length as usize] += 1;
+        }
+
+        // more codes of a length than there are free codes left cannot form a prefix code
+        let mut free_codes = 1u128;
+        for count in counts.iter().skip(1) {
+            free_codes <<= 1;
+            if (*count as u128) > free_codes {
+                return Err(CodecError::new(
+                    "code lengths are over-subscribed",
+                    CodecErrorKind::CorruptedData,
+                ));
+            }
+            free_codes -= *count as u128;
+        }
+
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..=max_length {
+            for (symbol, symbol_length) in lengths.iter().enumerate() {
+                if *symbol_length as usize == length {
+                    symbols.push(symbol as u16);
+                }
+            }
+        }
+
+        let lookup_bits = LOOKUP_BITS.min(max_length as u32);
+        let mut lookup = vec![(0u16, 0u8); 1 << lookup_bits];
+        let codes = canonical_codes(lengths);
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length == 0 || *length as u32 > lookup_bits {
+                continue;
+            }
+
+            // every entry starting with the code holds the symbol, whatever the bits after it
+            let reversed = reverse_bits(codes[symbol], *length) as usize;
+            for entry in (reversed..lookup.len()).step_by(1 << length) {
+                lookup[entry] = (symbol as u16, *length);
+            }
+        }
+
+        Ok(Self {
+            max_length,
+            counts,
+            symbols,
+            lookup_bits,
+            lookup,
+        })
+    }
+
+    /// read_symbol reads the next symbol, or returns None when the stream ends or holds no valid code
+    pub fn read_symbol(&self, reader: &mut BitReader) -> Option<u16> {
+        let (symbol, length) = self.lookup[reader.peek_bits(self.lookup_bits) as usize];
+        if length > 0 && length as usize <= reader.remaining() {
+            reader.skip_bits(length as usize);
+            return Some(symbol);
+        }
+
+        // walk the lengths, the code is valid once it falls within the codes of its length
+        let (mut code, mut first, mut index) = (0u64, 0u64, 0u64);
+        for length in 1..=self.max_length {
+            code |= reader.read_bit()? as u64;
+            let count = self.counts[length];
+            if code < first + count {
+                return Some(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn canonical_codes_work() {
+        // the example of RFC 1951: lengths (3, 3, 3, 3, 3, 2, 4, 4) for symbols A to H
+        let codes = canonical_codes(&[3, 3, 3, 3, 3, 2, 4, 4]);
+        assert_eq!(codes, vec![2, 3, 4, 5, 6, 0, 14, 15]);
+
+        assert_eq!(canonical_codes(&[0, 1, 0, 1]), vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn encoder_decoder_work() {
+        // lengths past LOOKUP_BITS use the slower walk
+        let mut lengths = (1..=20u8).collect::<Vec<u8>>();
+        lengths.push(20);
+        lengths.insert(3, 0);
+        let symbols = (0..lengths.len() as u16)
+            .filter(|symbol| lengths[*symbol as usize] > 0)
+            .collect::<Vec<u16>>();
+
+        let encoder = CanonicalEncoder::new(&lengths);
+        let mut writer = BitWriter::new();
+        for symbol in symbols.iter().chain(symbols.iter().rev()) {
+            encoder.write_symbol(&mut writer, *symbol);
+        }
+        let bytes = writer.finish();
+
+        let decoder = CanonicalDecoder::new(&lengths).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        for symbol in symbols.iter().chain(symbols.iter().rev()) {
+            assert_eq!(decoder.read_symbol(&mut reader), Some(*symbol));
+        }
+    }
+
+    #[test]
+    fn long_codes_work() {
+        let mut lengths = (1..=40u8).collect::<Vec<u8>>();
+        lengths.push(40);
+
+        let encoder = CanonicalEncoder::new(&lengths);
+        let mut writer = BitWriter::new();
+        encoder.write_symbol(&mut writer, 40);
+        encoder.write_symbol(&mut writer, 0);
+        let bytes = writer.finish();
+
+        let decoder = CanonicalDecoder::new(&lengths).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(decoder.read_symbol(&mut reader), Some(40));
+        assert_eq!(decoder.read_symbol(&mut reader), Some(0));
+    }
+
+    #[test]
+    fn invalid_lengths_are_rejected() {
+        assert!(CanonicalDecoder::new(&[1, 1, 1]).is_err());
+        assert!(CanonicalDecoder::new(&[64, 1]).is_err());
+
+        // an incomplete code is accepted, but its unused codes are not
+        let decoder = CanonicalDecoder::new(&[1, 0, 2]).unwrap();
+        let bytes = [0b11];
+        assert_eq!(decoder.read_symbol(&mut BitReader::new(&bytes)), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+/// ROOT is the node of the empty phrase
+pub const ROOT: usize = 0;
+
+/// DictionaryTrie holds the phrases of a Lempel-Ziv dictionary. Every node is a phrase made of
+/// the phrase of its parent followed by one byte, so extending the current match by a byte is a
+/// single lookup. Nodes are numbered in insertion order, starting with ROOT.
+pub struct DictionaryTrie {
+    children: HashMap<(usize, u8), usize>,
+    parents: Vec<usize>,
+    bytes: Vec<u8>,
+    first_bytes: Vec<u8>,
+    lengths: Vec<usize>,
+}
+
+impl DictionaryTrie {
+    /// new creates a trie holding only the empty phrase
+    pub fn new() -> Self {
+        Self {
+            children: HashMap::new(),
+            parents: vec![ROOT],
+            bytes: vec![0],
+            first_bytes: vec![0],
+            lengths: vec![0],
+        }
+    }
+
+    /// with_single_bytes creates a trie holding every single byte phrase, byte b being node b + 1
+    pub fn with_single_bytes() -> Self {
+        let mut trie = Self::new();
+        for byte in 0..=u8::MAX {
+            trie.insert(ROOT, byte);
+        }
+        trie
+    }
+
+    /// child returns the node of the phrase of node followed by byte, if it is in the trie
+    pub fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        self.children.get(&(node, byte)).copied()
+    }
+
+    /// insert adds the phrase of parent followed by byte and returns its node
+    pub fn insert(&mut self, parent: usize, byte: u8) -> usize {
+        let node = self.parents.len();
+        self.children.insert((parent, byte), node);
+        self.parents.push(parent);
+        self.bytes.push(byte);
+        self.first_bytes.push(if parent == ROOT {
+            byte
+        } else {
+            self.first_bytes[parent]
+        });
+        self.lengths.push(self.lengths[parent] + 1);
+        node
+    }
+
+    /// parent returns the node of the phrase without its last byte
+    pub fn parent(&self, node: usize) -> usize {
+        self.parents[node]
+    }
+
+    /// last_byte returns the last byte of the phrase of a node other than ROOT
+    pub fn last_byte(&self, node: usize) -> u8 {
+        self.bytes[node]
+    }
+
+    /// first_byte returns the first byte of the phrase of a node other than ROOT
+    pub fn first_byte(&self, node: usize) -> u8 {
+        self.first_bytes[node]
+    }
+
+    /// phrase_len returns the number of bytes of the phrase of node
+    pub fn phrase_len(&self, node: usize) -> usize {
+        self.lengths[node]
+    }
+
+    /// phrase returns the bytes of the phrase of node
+    pub fn phrase(&self, node: usize) -> Vec<u8> {
+        let mut phrase = Vec::new();
+        self.append_phrase(node, &mut phrase);
+        phrase
+    }
+
+    /// append_phrase writes the bytes of the phrase of node at the end of output, filling them
+    /// from the last byte back to the first so no temporary buffer is needed
+    pub fn append_phrase(&self, node: usize, output: &mut Vec<u8>) {
+        let start = output.len();
+        output.resize(start + self.lengths[node], 0);
+
+        let mut node = node;
+        for slot in output[start..].iter_mut().rev() {
+            *slot = self.bytes[node];
+            node = self.parents[node];
+        }
+    }
+
+    /// len returns the number of nodes, ROOT included
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// is_empty returns true when the trie holds only the empty phrase
+    pub fn is_empty(&self) -> bool {
+        self.len() == 1
+    }
+}
+
+impl Default for DictionaryTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trie_works() {
+        let mut trie = DictionaryTrie::new();
+        assert!(trie.is_empty());
+
+        let a = trie.insert(ROOT, b'a');
+        let ab = trie.insert(a, b'b');
+        let abc = trie.insert(ab, b'c');
+        let b = trie.insert(ROOT, b'b');
+
+        assert_eq!((a, ab, abc, b), (1, 2, 3, 4));
+        assert_eq!(trie.child(a, b'b'), Some(ab));
+        assert_eq!(trie.child(b, b'c'), None);
+        assert_eq!(trie.phrase(abc), b"abc");
+        assert_eq!(trie.phrase(ROOT), b"");
+        assert_eq!((trie.parent(abc), trie.last_byte(abc)), (ab, b'c'));
+        assert_eq!((trie.first_byte(abc), trie.phrase_len(abc)), (b'a', 3));
+
+        let mut output = b"xy".to_vec();
+        trie.append_phrase(ab, &mut output);
+        trie.append_phrase(ROOT, &mut output);
+        assert_eq!(output, b"xyab");
+        assert_eq!(trie.len(), 5);
+    }
+
+    #[test]
+    fn single_bytes_work() {
+        let trie = DictionaryTrie::with_single_bytes();
+        assert_eq!(trie.len(), 257);
+        for byte in 0..=u8::MAX {
+            assert_eq!(trie.child(ROOT, byte), Some(byte as usize + 1));
+            assert_eq!(trie.phrase(byte as usize + 1), vec![byte]);
+        }
+    }
+}
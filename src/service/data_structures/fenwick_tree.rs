@@ -0,0 +1,140 @@
+/// FenwickTree (binary indexed tree) holds a list of counts and answers prefix sums
+/// and updates in logarithmic time. Slot i of the tree holds the sum of the
+/// lowbit(i) counts ending at index i - 1.
+pub struct FenwickTree {
+    tree: Vec<u64>,
+}
+
+impl FenwickTree {
+    /// new creates a tree of `len` counts set to 0
+    pub fn new(len: usize) -> Self {
+        Self {
+            tree: vec![0; len + 1],
+        }
+    }
+
+    /// from_counts builds a tree holding the counts in linear time
+    pub fn from_counts(counts: &[u64]) -> Self {
+        let mut tree = vec![0u64; counts.len() + 1];
+        tree[1..].copy_from_slice(counts);
+        for i in 1..tree.len() {
+            let parent = i + lowbit(i);
+            if parent < tree.len() {
+                tree[parent] += tree[i];
+            }
+        }
+        Self { tree }
+    }
+
+    /// len returns the number of counts
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    /// is_empty returns true when the tree holds no counts
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// add adds delta to the count at index
+    pub fn add(&mut self, index: usize, delta: u64) {
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += lowbit(i);
+        }
+    }
+
+    /// prefix_sum returns the sum of the counts before index
+    pub fn prefix_sum(&self, index: usize) -> u64 {
+        let mut sum = 0;
+        let mut i = index;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= lowbit(i);
+        }
+        sum
+    }
+
+    /// get returns the count at index
+    pub fn get(&self, index: usize) -> u64 {
+        self.prefix_sum(index + 1) - self.prefix_sum(index)
+    }
+
+    /// total returns the sum of every count
+    pub fn total(&self) -> u64 {
+        self.prefix_sum(self.len())
+    }
+
+    /// find returns the index whose range of prefix sums holds the value, that is the
+    /// index i with prefix_sum(i) <= value < prefix_sum(i + 1), or None past the total
+    pub fn find(&self, value: u64) -> Option<usize> {
+        if value >= self.total() {
+            return None;
+        }
+
+        // walk down the implicit tree, skipping every slot whose sum stays under the value
+        let (mut position, mut remaining) = (0usize, value);
+        let mut step = self.len().checked_next_power_of_two().unwrap_or(0);
+        while step > 0 {
+            let next = position + step;
+            if next < self.tree.len() && self.tree[next] <= remaining {
+                position = next;
+                remaining -= self.tree[next];
+            }
+            step >>= 1;
+        }
+        Some(position)
+    }
+
+    /// counts returns every count in order
+    pub fn counts(&self) -> Vec<u64> {
+        (0..self.len()).map(|index| self.get(index)).collect()
+    }
+}
+
+/// lowbit returns the lowest set bit of i
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefix_sums_work() {
+        let counts = [3u64, 0, 5, 1, 0, 0, 7, 2, 4];
+        let mut tree = FenwickTree::from_counts(&counts);
+        assert_eq!(tree.len(), 9);
+        assert_eq!(tree.counts(), counts);
+        for index in 0..=counts.len() {
+            assert_eq!(tree.prefix_sum(index), counts[..index].iter().sum::<u64>());
+        }
+
+        tree.add(1, 10);
+        tree.add(8, 1);
+        assert_eq!(tree.get(1), 10);
+        assert_eq!(tree.total(), 33);
+
+        let mut built = FenwickTree::new(9);
+        for (index, count) in tree.counts().into_iter().enumerate() {
+            built.add(index, count);
+        }
+        assert_eq!(built.counts(), tree.counts());
+    }
+
+    #[test]
+    fn find_works() {
+        let counts = [3u64, 0, 5, 1, 0, 0, 7, 2, 4];
+        let tree = FenwickTree::from_counts(&counts);
+
+        for value in 0..tree.total() {
+            let index = tree.find(value).unwrap();
+            assert!(tree.prefix_sum(index) <= value && value < tree.prefix_sum(index + 1));
+            assert!(counts[index] > 0);
+        }
+        assert_eq!(tree.find(tree.total()), None);
+        assert_eq!(FenwickTree::new(0).find(0), None);
+    }
+}
@@ -0,0 +1,134 @@
+/// HASH_LENGTH is the number of bytes hashed to find match candidates
+pub const HASH_LENGTH: usize = 3;
+
+/// HASH_BITS is the width of the hash of HASH_LENGTH bytes
+const HASH_BITS: u32 = 15;
+
+/// NONE marks an empty head or the end of a chain
+const NONE: usize = usize::MAX;
+
+/// HashChain finds earlier occurrences of the bytes at a position within a sliding window.
+/// head holds the last position inserted for every hash of HASH_LENGTH bytes, and prev links
+/// every position of the window to the previous position with the same hash, so the
+/// candidates of a position are walked from the nearest to the farthest.
+pub struct HashChain {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    window_size: usize,
+    max_chain: usize,
+}
+
+impl HashChain {
+    /// new creates a hash chain over a window of window_size bytes, a power of two,
+    /// following at most max_chain candidates for every search
+    pub fn new(window_size: usize, max_chain: usize) -> Self {
+        debug_assert!(window_size.is_power_of_two());
+        Self {
+            head: vec![NONE; 1 << HASH_BITS],
+            prev: vec![NONE; window_size],
+            window_size,
+            max_chain,
+        }
+    }
+
+    /// hash returns the hash of the HASH_LENGTH bytes at position
+    fn hash(data: &[u8], position: usize) -> usize {
+        let bytes = &data[position..position + HASH_LENGTH];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        (value.wrapping_mul(0x9e37_79b1) >> (u32::BITS - HASH_BITS)) as usize
+    }
+
+    /// insert adds the position to the chain of its hash, positions must be inserted in order.
+    /// The last HASH_LENGTH - 1 positions of the data have no hash and are skipped
+    pub fn insert(&mut self, data: &[u8], position: usize) {
+        if position + HASH_LENGTH > data.len() {
+            return;
+        }
+
+        let hash = Self::hash(data, position);
+        self.prev[position & (self.window_size - 1)] = self.head[hash];
+        self.head[hash] = position;
+    }
+
+    /// longest_match returns the distance and the length of the longest earlier occurrence of the
+    /// bytes at position within the window, up to max_length bytes. Only matches of at least
+    /// HASH_LENGTH bytes are found
+    pub fn longest_match(
+        &self,
+        data: &[u8],
+        position: usize,
+        max_length: usize,
+    ) -> Option<(usize, usize)> {
+        let max_length = max_length.min(data.len() - position);
+        if max_length < HASH_LENGTH {
+            return None;
+        }
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.head[Self::hash(data, position)];
+        for _ in 0..self.max_chain {
+            // the chain leaves the window, or reaches a slot reused by a newer position
+            if candidate == NONE || candidate >= position || position - candidate > self.window_size
+            {
+                break;
+            }
+
+            let length = data[candidate..]
+                .iter()
+                .zip(&data[position..position + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.map_or(0, |(_, best_length)| best_length) {
+                best = Some((position - candidate, length));
+                if length == max_length {
+                    break;
+                }
+            }
+
+            let next = self.prev[candidate & (self.window_size - 1)];
+            if next != NONE && next >= candidate {
+                break;
+            }
+            candidate = next;
+        }
+
+        // a hash collision can leave a match shorter than the hashed bytes
+        best.filter(|(_, length)| *length >= HASH_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn longest_match_works() {
+        let data = b"abcdeabcdfabcdeabc";
+        let mut chain = HashChain::new(16, 32);
+        for position in 0..10 {
+            chain.insert(data, position);
+        }
+
+        // "abcdeabc" at 10 is found 10 bytes back, "abcd" at 5 is only 4 bytes long
+        assert_eq!(chain.longest_match(data, 10, 100), Some((10, 8)));
+        assert_eq!(chain.longest_match(data, 10, 6), Some((10, 6)));
+        assert_eq!(chain.longest_match(data, 16, 100), None);
+    }
+
+    #[test]
+    fn matches_stay_in_the_window() {
+        let data = [b"xyz".as_slice(), &[0u8; 20], b"xyz"].concat();
+        let mut chain = HashChain::new(16, 32);
+        for position in 0..23 {
+            chain.insert(&data, position);
+        }
+        assert_eq!(chain.longest_match(&data, 23, 10), None);
+
+        // overlapping matches are found, as the decoder copies byte by byte
+        let mut chain = HashChain::new(16, 32);
+        for position in 0..4 {
+            chain.insert(&data, position);
+        }
+        assert_eq!(chain.longest_match(&data, 4, 100), Some((1, 19)));
+    }
+}
@@ -5,7 +5,7 @@ use std::rc::Rc;
 /// Node is the trait for node behaviours in the huffman tree
 pub trait Node {
     fn get_frequency(&self) -> i32;
-    fn get_character(&self) -> Option<char>;
+    fn get_symbol(&self) -> Option<u16>;
     fn get_left(&self) -> Link;
     fn get_right(&self) -> Link;
     fn is_leaf(&self) -> bool;
@@ -40,20 +40,15 @@ pub type Link = Option<Rc<RefCell<BoxNode>>>;
 /// HuffmanLeafNode represents a LEAF node in the huffman tree
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
 pub struct HuffmanLeaf {
-    character: char,
+    symbol: u16,
     node: HuffmanNode,
 }
 
 impl HuffmanLeaf {
-    pub fn new(character: char, frequency: i32) -> Self {
+    pub fn new(symbol: u16, frequency: i32) -> Self {
         let mut node = HuffmanNode::new(None, None);
         node.frequency = frequency;
-        Self { character, node }
-    }
-
-    /// get_character returns the character of the leaf node
-    pub fn get_character(&self) -> char {
-        self.character
+        Self { symbol, node }
     }
 }
 
@@ -62,8 +57,8 @@ impl Node for HuffmanLeaf {
         self.node.frequency
     }
 
-    fn get_character(&self) -> Option<char> {
-        Some(self.character)
+    fn get_symbol(&self) -> Option<u16> {
+        Some(self.symbol)
     }
 
     fn get_left(&self) -> Link {
@@ -98,13 +93,13 @@ impl HuffmanNode {
             false => {
                 let left_node = left_node.unwrap();
                 let right_node = right_node.unwrap();
-                let node = Self {
-                    frequency: left_node.borrow().get_frequency()
-                        + right_node.borrow().get_frequency(),
+                let frequency =
+                    left_node.borrow().get_frequency() + right_node.borrow().get_frequency();
+                Self {
+                    frequency,
                     left: Some(Rc::clone(&left_node)),
                     right: Some(Rc::clone(&right_node)),
-                };
-                node
+                }
             }
         }
     }
@@ -115,7 +110,7 @@ impl Node for HuffmanNode {
         self.frequency
     }
 
-    fn get_character(&self) -> Option<char> {
+    fn get_symbol(&self) -> Option<u16> {
         None
     }
 
@@ -138,8 +133,8 @@ mod test {
 
     #[test]
     fn huffman_leaf_works() {
-        let leaf = HuffmanLeaf::new('a', 2);
-        assert_eq!(leaf.get_character(), 'a');
+        let leaf = HuffmanLeaf::new(u16::from(b'a'), 2);
+        assert_eq!(leaf.get_symbol(), Some(u16::from(b'a')));
         assert_eq!(leaf.get_frequency(), 2);
     }
 
@@ -163,10 +158,10 @@ mod test {
 
     #[test]
     fn huffman_leaf_huffman_node_works() {
-        let leaf_a = HuffmanLeaf::new('a', 2);
-        let leaf_b = HuffmanLeaf::new('b', 3);
-        let leaf_c = HuffmanLeaf::new('c', 1);
-        let leaf_d = HuffmanLeaf::new('d', 5);
+        let leaf_a = HuffmanLeaf::new(u16::from(b'a'), 2);
+        let leaf_b = HuffmanLeaf::new(u16::from(b'b'), 3);
+        let leaf_c = HuffmanLeaf::new(u16::from(b'c'), 1);
+        let leaf_d = HuffmanLeaf::new(u16::from(b'd'), 5);
 
         let node_ab = HuffmanNode::new(
             Some(Rc::new(RefCell::new(Box::new(leaf_a)))),
@@ -1,2 +1,11 @@
+pub mod adaptive_tree;
+pub mod bit_stream;
+pub mod byte_cursor;
+pub mod canonical_code;
+pub mod dictionary_trie;
+pub mod fenwick_tree;
+pub mod hash_chain;
 pub mod huffman_node;
+pub mod package_merge;
 pub mod priority_queue;
+pub mod suffix_array;
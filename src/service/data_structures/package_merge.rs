@@ -0,0 +1,143 @@
+/// Item is an entry of a package-merge list, either a symbol or a package
+/// made of the items 2i and 2i + 1 of the list one level deeper
+#[derive(Clone, Copy)]
+enum Item {
+    Leaf(usize),
+    Package(usize),
+}
+
+/// limited_code_lengths returns optimal prefix code lengths for the frequencies where no
+/// code is longer than max_length, using the package-merge algorithm.
+/// Symbols with a frequency of 0 get a length of 0, and a single symbol gets a length of 1.
+/// It returns None when max_length is too short to give every symbol a code.
+pub fn limited_code_lengths(frequencies: &[u64], max_length: u8) -> Option<Vec<u8>> {
+    let mut leaves = frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, frequency)| **frequency > 0)
+        .map(|(symbol, frequency)| (*frequency, Item::Leaf(symbol)))
+        .collect::<Vec<(u64, Item)>>();
+    let n = leaves.len();
+
+    let mut lengths = vec![0u8; frequencies.len()];
+    match n {
+        0 => return Some(lengths),
+        _ if max_length == 0 || (max_length < 64 && n > 1usize << max_length) => return None,
+        1 => {
+            if let Item::Leaf(symbol) = leaves[0].1 {
+                lengths[symbol] = 1;
+            }
+            return Some(lengths);
+        }
+        _ => {}
+    }
+
+    // the sort is stable, so equal frequencies keep the order of their symbols
+    leaves.sort_by_key(|(frequency, _)| *frequency);
+
+    // every level pairs up the items of the level below into packages and merges them with the leaves,
+    // the last list built is the one of codes of length 1
+    let mut lists = vec![leaves.clone()];
+    for _ in 1..max_length {
+        let previous = lists.last().unwrap();
+        let packages = previous
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, pair)| (pair[0].0 + pair[1].0, Item::Package(i)));
+
+        let mut merged = Vec::with_capacity(n + previous.len() / 2);
+        let mut leaves = leaves.iter().copied().peekable();
+        let mut packages = packages.peekable();
+        loop {
+            let take_leaf = match (leaves.peek(), packages.peek()) {
+                (Some(leaf), Some(package)) => leaf.0 <= package.0,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            match take_leaf {
+                true => merged.push(leaves.next().unwrap()),
+                false => merged.push(packages.next().unwrap()),
+            }
+        }
+        lists.push(merged);
+    }
+
+    // the 2n - 2 lightest items of the top list hold every leaf as many times as its code length
+    let top = lists.len() - 1;
+    let mut stack = (0..2 * n - 2)
+        .map(|i| (top, i))
+        .collect::<Vec<(usize, usize)>>();
+    while let Some((level, i)) = stack.pop() {
+        match lists[level][i].1 {
+            Item::Leaf(symbol) => lengths[symbol] += 1,
+            Item::Package(package) => {
+                stack.push((level - 1, 2 * package));
+                stack.push((level - 1, 2 * package + 1));
+            }
+        }
+    }
+
+    Some(lengths)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kraft_sum(lengths: &[u8]) -> f64 {
+        lengths
+            .iter()
+            .filter(|length| **length > 0)
+            .map(|length| 0.5f64.powi(*length as i32))
+            .sum()
+    }
+
+    fn cost(frequencies: &[u64], lengths: &[u8]) -> u64 {
+        frequencies
+            .iter()
+            .zip(lengths)
+            .map(|(frequency, length)| frequency * *length as u64)
+            .sum()
+    }
+
+    #[test]
+    fn unbounded_lengths_match_huffman() {
+        // with a loose limit the lengths are the ones of a huffman tree
+        let frequencies = [5, 9, 12, 13, 16, 45];
+        let lengths = limited_code_lengths(&frequencies, 15).unwrap();
+        assert_eq!(lengths, vec![4, 4, 3, 3, 3, 1]);
+        assert_eq!(cost(&frequencies, &lengths), 224);
+    }
+
+    #[test]
+    fn fibonacci_lengths_are_limited() {
+        // fibonacci frequencies give the deepest huffman tree, one level per symbol
+        let mut frequencies = vec![1u64, 1];
+        while frequencies.len() < 40 {
+            let n = frequencies.len();
+            frequencies.push(frequencies[n - 1] + frequencies[n - 2]);
+        }
+
+        let unbounded = limited_code_lengths(&frequencies, 63).unwrap();
+        assert_eq!(*unbounded.iter().max().unwrap(), 39);
+
+        for max_length in [6, 8, 12, 15, 20] {
+            let lengths = limited_code_lengths(&frequencies, max_length).unwrap();
+            assert!(lengths
+                .iter()
+                .all(|length| (1..=max_length).contains(length)));
+            assert_eq!(kraft_sum(&lengths), 1.0);
+            assert!(cost(&frequencies, &lengths) >= cost(&frequencies, &unbounded));
+        }
+    }
+
+    #[test]
+    fn edge_cases_work() {
+        assert_eq!(limited_code_lengths(&[0, 0], 15), Some(vec![0, 0]));
+        assert_eq!(limited_code_lengths(&[0, 7], 15), Some(vec![0, 1]));
+        assert_eq!(limited_code_lengths(&[3, 0, 3], 1), Some(vec![1, 0, 1]));
+        assert_eq!(limited_code_lengths(&[1, 1, 1], 1), None);
+        assert_eq!(limited_code_lengths(&[1; 256], 8), Some(vec![8; 256]));
+    }
+}
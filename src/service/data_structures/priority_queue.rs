@@ -111,7 +111,13 @@ impl<T: Ord> Heap<T> {
     }
 
     fn get_children(index: usize) -> (usize, usize) {
-        ((index * 2), (index * 2) + 1)
+        ((index * 2) + 1, (index * 2) + 2)
+    }
+}
+
+impl<T: Ord> Default for Heap<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -141,12 +147,12 @@ mod test {
         heap.push(4);
         heap.push(2);
         assert_eq!(heap.size(), 3);
-        assert_eq!(heap.is_empty(), false);
+        assert!(!heap.is_empty());
         assert_eq!(heap.pop(), Some(4));
         assert_eq!(heap.pop(), Some(2));
         assert_eq!(heap.pop(), Some(1));
         assert_eq!(heap.pop(), None);
-        assert_eq!(heap.is_empty(), true);
+        assert!(heap.is_empty());
     }
 
     #[test]
@@ -0,0 +1,194 @@
+/// EMPTY marks a slot of the suffix array that has not been filled yet
+const EMPTY: usize = usize::MAX;
+
+/// suffix_array returns the starting indices of the suffixes of the text in sorted order.
+/// It is built with SA-IS (induced sorting), which runs in linear time and memory.
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let symbols = text
+        .iter()
+        .map(|byte| *byte as usize)
+        .collect::<Vec<usize>>();
+    sa_is(&symbols, u8::MAX as usize)
+}
+
+/// sa_is sorts the suffixes of a text made of symbols in 0..=upper.
+/// The end of the text acts as a virtual sentinel smaller than every symbol.
+fn sa_is(text: &[usize], upper: usize) -> Vec<usize> {
+    let n = text.len();
+    match n {
+        0 => return vec![],
+        1 => return vec![0],
+        2 => {
+            return if text[0] < text[1] {
+                vec![0, 1]
+            } else {
+                vec![1, 0]
+            }
+        }
+        _ => {}
+    }
+
+    // a suffix is S-type when it is smaller than the suffix following it, and L-type otherwise
+    let mut is_s = vec![false; n];
+    for i in (0..n - 1).rev() {
+        is_s[i] = match text[i].cmp(&text[i + 1]) {
+            std::cmp::Ordering::Equal => is_s[i + 1],
+            ordering => ordering == std::cmp::Ordering::Less,
+        };
+    }
+
+    // every bucket holds the L-type suffixes of a symbol first, then its S-type suffixes.
+    // sum_l[c] is where the L-type suffixes of c start and sum_s[c] where its S-type ones start
+    let mut sum_l = vec![0usize; upper + 1];
+    let mut sum_s = vec![0usize; upper + 1];
+    for i in 0..n {
+        if is_s[i] {
+            // an S-type symbol is never the largest one, so c + 1 stays in range
+            sum_l[text[i] + 1] += 1;
+        } else {
+            sum_s[text[i]] += 1;
+        }
+    }
+    for c in 0..=upper {
+        sum_s[c] += sum_l[c];
+        if c < upper {
+            sum_l[c + 1] += sum_s[c];
+        }
+    }
+
+    // a leftmost S-type (LMS) position is an S-type suffix preceded by an L-type one
+    let is_lms = |i: usize| i > 0 && is_s[i] && !is_s[i - 1];
+    let lms = (1..n).filter(|i| is_lms(*i)).collect::<Vec<usize>>();
+    let mut lms_index = vec![EMPTY; n];
+    for (index, position) in lms.iter().enumerate() {
+        lms_index[*position] = index;
+    }
+
+    let mut sa = vec![EMPTY; n];
+    induce(text, &is_s, &sum_l, &sum_s, &lms, &mut sa);
+
+    if !lms.is_empty() {
+        // the induced order sorts the LMS substrings, so name each by its rank among them
+        let sorted_lms = sa
+            .iter()
+            .copied()
+            .filter(|i| lms_index[*i] != EMPTY)
+            .collect::<Vec<usize>>();
+
+        let lms_end = |i: usize| lms.get(lms_index[i] + 1).copied().unwrap_or(n);
+        let mut reduced = vec![0usize; lms.len()];
+        let mut name = 0usize;
+        for pair in sorted_lms.windows(2) {
+            let (mut l, mut r) = (pair[0], pair[1]);
+            let (end_l, end_r) = (lms_end(l), lms_end(r));
+
+            let mut same = end_l - l == end_r - r;
+            if same {
+                while l < end_l && text[l] == text[r] {
+                    l += 1;
+                    r += 1;
+                }
+                same = l < n && r < n && text[l] == text[r];
+            }
+
+            if !same {
+                name += 1;
+            }
+            reduced[lms_index[pair[1]]] = name;
+        }
+
+        // sort the reduced text recursively to get the exact order of the LMS suffixes
+        let reduced_sa = sa_is(&reduced, name);
+        let sorted_lms = reduced_sa
+            .into_iter()
+            .map(|i| lms[i])
+            .collect::<Vec<usize>>();
+        induce(text, &is_s, &sum_l, &sum_s, &sorted_lms, &mut sa);
+    }
+
+    sa
+}
+
+/// induce places the LMS suffixes in the given order and induces the order of
+/// the L-type suffixes from left to right, then of the S-type ones from right to left
+fn induce(
+    text: &[usize],
+    is_s: &[bool],
+    sum_l: &[usize],
+    sum_s: &[usize],
+    lms: &[usize],
+    sa: &mut [usize],
+) {
+    let n = text.len();
+    sa.fill(EMPTY);
+
+    let mut buckets = sum_s.to_vec();
+    for i in lms {
+        sa[buckets[text[*i]]] = *i;
+        buckets[text[*i]] += 1;
+    }
+
+    // the last suffix is L-type as it is followed by the virtual sentinel
+    let mut buckets = sum_l.to_vec();
+    sa[buckets[text[n - 1]]] = n - 1;
+    buckets[text[n - 1]] += 1;
+    for i in 0..n {
+        let v = sa[i];
+        if v != EMPTY && v >= 1 && !is_s[v - 1] {
+            sa[buckets[text[v - 1]]] = v - 1;
+            buckets[text[v - 1]] += 1;
+        }
+    }
+
+    let mut buckets = sum_l.to_vec();
+    for i in (0..n).rev() {
+        let v = sa[i];
+        if v != EMPTY && v >= 1 && is_s[v - 1] {
+            buckets[text[v - 1] + 1] -= 1;
+            sa[buckets[text[v - 1] + 1]] = v - 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::{binary_samples, XorShift};
+
+    fn naive_suffix_array(text: &[u8]) -> Vec<usize> {
+        let mut suffixes = (0..text.len()).collect::<Vec<usize>>();
+        suffixes.sort_by(|a, b| text[*a..].cmp(&text[*b..]));
+        suffixes
+    }
+
+    #[test]
+    fn suffix_array_works() {
+        assert_eq!(suffix_array(b"banana"), vec![5, 3, 1, 0, 4, 2]);
+        assert_eq!(
+            suffix_array(b"mississippi"),
+            vec![10, 7, 4, 1, 0, 9, 8, 6, 3, 5, 2]
+        );
+        assert!(suffix_array(b"").is_empty());
+    }
+
+    #[test]
+    fn matches_naive_sort() {
+        let mut rng = XorShift::new(42);
+        let mut test_cases = binary_samples();
+        test_cases.extend([
+            b"aaaaaaaaaaaa".to_vec(),
+            b"abababababab$".to_vec(),
+            b"abracadabra!".to_vec(),
+            "TOBEORNOTTOBEORTOBEORNOT".repeat(20).into_bytes(),
+        ]);
+        // small alphabets give long repeats and deep recursion
+        for len in [3, 4, 5, 17, 100, 1000] {
+            test_cases.push((0..len).map(|_| b'a' + rng.next_u64() as u8 % 2).collect());
+            test_cases.push((0..len).map(|_| b'a' + rng.next_u64() as u8 % 3).collect());
+        }
+
+        for test_case in test_cases {
+            assert_eq!(suffix_array(&test_case), naive_suffix_array(&test_case));
+        }
+    }
+}
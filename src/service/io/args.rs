@@ -1,42 +1,84 @@
-use clap::Parser;
+use crate::service::algorithms::Algorithm;
+use clap::{Parser, Subcommand};
 use std::fmt::Debug;
 
 #[derive(Parser, Debug, Default, Clone)]
 #[clap(author = "Author Name", version, about)]
-/// A text compressor
+/// A text compressor. Starts the REST API when no command is given
 pub struct Argument {
-    /// name of the text file to compress
-    file_name: String,
-    /// multi-thread support. It is false by default
-    #[clap(short, long, action)]
-    multithread: bool,
-    /// whether to use the rest API or CLI
-    #[clap(short, long, action)]
-    cli: bool,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-impl Argument {
-    pub fn file_name(&self) -> String {
-        self.file_name.clone()
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// compress a file into a .tcz container
+    Compress {
+        /// name of the file to compress
+        input: String,
+        /// name of the compressed file to write
+        #[clap(short, long)]
+        output: String,
+        /// algorithm to compress with: huffman, adaptivehuffman, range, rans, tans, lzw, lz78,
+        /// lzss, deflate, snappyraw, bwt, bwtsais, mtf, rle, packbits, rleescape, huffmanrle,
+        /// bwtrle or blocksort, or gzip, zlib, lz4 and snappy to write a file other tools can
+        /// read instead of a container.
+        /// Huffman followed by a number, such as huffman15, limits the codes to that many bits.
+        /// Algorithms joined by '|', such as "bwtsais|mtf|rle|huffman", run one after another
+        #[clap(short, long, default_value = "huffman", value_parser = parse_algorithm)]
+        algorithm: Algorithm,
+        /// compress block by block into a stream instead of a container, for files larger than memory
+        #[clap(short, long, action)]
+        stream: bool,
+    },
+    /// decompress a .tcz container, a stream, or a gzip, zlib, lz4 or snappy file back into the original file
+    Decompress {
+        /// name of the compressed file to read
+        input: String,
+        /// name of the decompressed file to write
+        #[clap(short, long)]
+        output: String,
+    },
+    /// benchmark every algorithm on a file and write the metrics as JSON
+    Bench {
+        /// name of the text file to benchmark
+        input: String,
+        /// name of the JSON file to write the metrics to
+        #[clap(short, long, default_value = "out_data.json")]
+        output: String,
+        /// multi-thread support. It is false by default
+        #[clap(short, long, action)]
+        multithread: bool,
+    },
+}
+
+/// parse_algorithm accepts the name of any algorithm that can compress a file on its own
+fn parse_algorithm(name: &str) -> Result<Algorithm, String> {
+    match Algorithm::from(name.to_string()) {
+        Algorithm::All | Algorithm::Invalid => Err(format!("unknown algorithm '{}'", name)),
+        algorithm => Ok(algorithm),
     }
+}
 
-    pub fn should_multithread(&self) -> bool {
-        self.multithread
+impl Argument {
+    pub fn command(&self) -> Option<Command> {
+        self.command.clone()
     }
 
     pub fn use_cli(&self) -> bool {
-        self.cli
+        self.command.is_some()
     }
 
-    /// validate_file_name checks that the file name is a valid one and eats whitespaces
-    pub fn validate_file_name(&mut self) -> Result<(), String> {
-        let mut name = String::new();
-        for word in self.file_name.split_whitespace() {
-            name.push_str(word);
-        }
-        self.file_name = name;
+    /// validate checks that every file name of the command is a valid one
+    pub fn validate(&self) -> Result<(), String> {
+        let file_names = match &self.command {
+            Some(Command::Compress { input, output, .. }) => vec![input, output],
+            Some(Command::Decompress { input, output }) => vec![input, output],
+            Some(Command::Bench { input, output, .. }) => vec![input, output],
+            None => vec![],
+        };
 
-        if self.file_name.is_empty() {
+        if file_names.iter().any(|name| name.trim().is_empty()) {
             return Err(String::from("file name cannot be empty"));
         }
 
@@ -50,18 +92,135 @@ mod test {
 
     #[test]
     fn parsing_argument_works() {
-        let file_name = "dummy.txt".to_string();
-        let multithread = true;
-        let cli = true;
-
-        let args = Argument {
-            file_name: file_name.clone(),
-            multithread,
-            cli,
-        };
+        let args = Argument::try_parse_from(["tcz"]).unwrap();
+        assert!(!args.use_cli());
+
+        let args = Argument::try_parse_from([
+            "tcz",
+            "compress",
+            "dummy.txt",
+            "-o",
+            "dummy.tcz",
+            "--algorithm",
+            "BwtRle",
+        ])
+        .unwrap();
+        assert!(args.use_cli());
+        assert_eq!(
+            args.command(),
+            Some(Command::Compress {
+                input: "dummy.txt".to_string(),
+                output: "dummy.tcz".to_string(),
+                algorithm: Algorithm::BwtRle,
+                stream: false,
+            })
+        );
+
+        let args = Argument::try_parse_from(["tcz", "compress", "dummy.txt", "-o", "d.tcz", "-s"])
+            .unwrap();
+        assert_eq!(
+            args.command(),
+            Some(Command::Compress {
+                input: "dummy.txt".to_string(),
+                output: "d.tcz".to_string(),
+                algorithm: Algorithm::Huffman,
+                stream: true,
+            })
+        );
+
+        let args = Argument::try_parse_from([
+            "tcz",
+            "compress",
+            "dummy.txt",
+            "-o",
+            "dummy.tcz",
+            "-a",
+            "bwtsais|mtf|rle|huffman",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command(),
+            Some(Command::Compress {
+                input: "dummy.txt".to_string(),
+                output: "dummy.tcz".to_string(),
+                algorithm: Algorithm::Pipeline(vec![
+                    Algorithm::BwtSais,
+                    Algorithm::Mtf,
+                    Algorithm::Rle,
+                    Algorithm::Huffman,
+                ]),
+                stream: false,
+            })
+        );
+
+        let args = Argument::try_parse_from(["tcz", "decompress", "dummy.tcz", "-o", "dummy.txt"])
+            .unwrap();
+        assert_eq!(
+            args.command(),
+            Some(Command::Decompress {
+                input: "dummy.tcz".to_string(),
+                output: "dummy.txt".to_string(),
+            })
+        );
+
+        let args = Argument::try_parse_from(["tcz", "bench", "dummy.txt", "-m"]).unwrap();
+        assert_eq!(
+            args.command(),
+            Some(Command::Bench {
+                input: "dummy.txt".to_string(),
+                output: "out_data.json".to_string(),
+                multithread: true,
+            })
+        );
+
+        let args = Argument::try_parse_from([
+            "tcz",
+            "compress",
+            "a.txt",
+            "-o",
+            "a.tcz",
+            "-a",
+            "huffman15",
+        ])
+        .unwrap();
+        assert!(matches!(
+            args.command(),
+            Some(Command::Compress {
+                algorithm: Algorithm::LimitedHuffman(15),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        let unknown_algorithm =
+            Argument::try_parse_from(["tcz", "compress", "a.txt", "-o", "a.tcz", "-a", "zip"]);
+        assert!(unknown_algorithm.is_err());
+
+        let all_algorithms =
+            Argument::try_parse_from(["tcz", "compress", "a.txt", "-o", "a.tcz", "-a", "all"]);
+        assert!(all_algorithms.is_err());
+
+        let unlimited_huffman = Argument::try_parse_from([
+            "tcz",
+            "compress",
+            "a.txt",
+            "-o",
+            "a.tcz",
+            "-a",
+            "huffman64",
+        ]);
+        assert!(unlimited_huffman.is_err());
+
+        let unknown_stage =
+            Argument::try_parse_from(["tcz", "compress", "a.txt", "-o", "a.tcz", "-a", "bwt|zip"]);
+        assert!(unknown_stage.is_err());
+
+        let missing_output = Argument::try_parse_from(["tcz", "decompress", "a.tcz"]);
+        assert!(missing_output.is_err());
 
-        assert_eq!(args.file_name, file_name);
-        assert_eq!(args.multithread, multithread);
-        assert_eq!(args.cli, cli);
+        let empty_name = Argument::try_parse_from(["tcz", "decompress", " ", "-o", "a.txt"]);
+        assert!(empty_name.unwrap().validate().is_err());
     }
 }
@@ -0,0 +1,292 @@
+//! The `.tcz` container wraps the output of any algorithm so it can be
+//! stored on disk and decompressed later without knowing how it was made.
+//!
+//! Every integer is little endian:
+//!
+//! | field           | size                  |
+//! |-----------------|-----------------------|
+//! | magic `TCZ\x1a` | 4 bytes               |
+//! | format version  | 1 byte                |
+//! | algorithm id    | 1 byte                |
+//! | original length | 8 bytes               |
+//! | CRC-32          | 4 bytes               |
+//! | chunk count     | 4 bytes               |
+//! | chunk lengths   | 4 bytes per chunk     |
+//! | chunks          | sum of chunk lengths  |
+//!
+//! The CRC-32 is computed over the original data, and every chunk is an
+//! independently compressed slice of at most `CHUNK_SIZE` original bytes.
+
+use super::new_codec;
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::algorithms::Algorithm;
+use crate::service::data_structures::byte_cursor::ByteCursor;
+use crate::utils::checksum::crc32;
+
+/// MAGIC identifies a container file
+pub const MAGIC: [u8; 4] = *b"TCZ\x1a";
+
+/// VERSION is the container format version written by this build. Version 1 chunks hold
+/// payloads of algorithms whose layout has changed since, such as bwtrle before it became a
+/// pipeline, so they are rejected rather than decoded into the wrong data
+pub const VERSION: u8 = 2;
+
+/// CHUNK_SIZE is the number of original bytes compressed into each chunk
+pub const CHUNK_SIZE: usize = 1 << 14;
+
+/// Container holds the compressed chunks of some data and the metadata needed to restore it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub algorithm: Algorithm,
+    pub original_length: u64,
+    pub checksum: u32,
+    pub chunks: Vec<Vec<u8>>,
+}
+
+impl Container {
+    /// compress splits the data into chunks and compresses each with the algorithm
+    pub fn compress(data: &[u8], algorithm: Algorithm) -> Result<Self, CodecError> {
+        let mut codec = new_codec(algorithm.clone()).ok_or_else(|| {
+            CodecError::new(
+                "algorithm cannot be stored in a container",
+                CodecErrorKind::UnknownAlgorithm,
+            )
+        })?;
+
+        let chunks = data
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| codec.encode(chunk))
+            .collect::<Result<Vec<Vec<u8>>, CodecError>>()?;
+
+        Ok(Self {
+            algorithm,
+            original_length: data.len() as u64,
+            checksum: crc32(data),
+            chunks,
+        })
+    }
+
+    /// decompress restores the original data and verifies its length and checksum
+    pub fn decompress(&self) -> Result<Vec<u8>, CodecError> {
+        let mut codec = new_codec(self.algorithm.clone()).ok_or_else(|| {
+            CodecError::new(
+                "container algorithm has no codec",
+                CodecErrorKind::UnknownAlgorithm,
+            )
+        })?;
+
+        // every chunk holds at most CHUNK_SIZE bytes, so a corrupted one cannot decode to more
+        let mut data = Vec::new();
+        for chunk in &self.chunks {
+            data.extend(codec.decode_limited(chunk, CHUNK_SIZE)?);
+        }
+
+        if data.len() as u64 != self.original_length {
+            return Err(CodecError::new(
+                "decompressed length does not match the container header",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+        if crc32(&data) != self.checksum {
+            return Err(CodecError::new(
+                "decompressed data does not match the container checksum",
+                CodecErrorKind::ChecksumMismatch,
+            ));
+        }
+
+        Ok(data)
+    }
+
+    /// to_bytes serializes the container into the on-disk format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_length = self.chunks.iter().map(|chunk| chunk.len()).sum::<usize>();
+        let mut bytes = Vec::with_capacity(22 + 4 * self.chunks.len() + payload_length);
+
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.algorithm.id().unwrap());
+        bytes.extend_from_slice(&self.original_length.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        for chunk in &self.chunks {
+            bytes.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        }
+        for chunk in &self.chunks {
+            bytes.extend_from_slice(chunk);
+        }
+
+        bytes
+    }
+
+    /// from_bytes parses a container, rejecting foreign, newer or truncated files
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut cursor = ByteCursor::new(bytes);
+
+        if cursor.read_bytes(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(CodecError::new(
+                "file is not a text compressor container",
+                CodecErrorKind::InvalidMagic,
+            ));
+        }
+
+        let version = cursor.read_u8()?;
+        if version != VERSION {
+            return Err(CodecError::new(
+                &format!("container version {} is not supported", version),
+                CodecErrorKind::UnsupportedVersion,
+            ));
+        }
+
+        let algorithm = Algorithm::from_id(cursor.read_u8()?).ok_or_else(|| {
+            CodecError::new(
+                "container algorithm is unknown",
+                CodecErrorKind::UnknownAlgorithm,
+            )
+        })?;
+        let original_length = cursor.read_u64_le()?;
+        let checksum = cursor.read_u32_le()?;
+
+        // read the lengths first so a bogus chunk count fails on the missing bytes
+        let chunk_count = cursor.read_u32_le()? as usize;
+        let mut chunk_lengths = Vec::new();
+        for _ in 0..chunk_count {
+            chunk_lengths.push(cursor.read_u32_le()? as usize);
+        }
+
+        let mut chunks = Vec::with_capacity(chunk_count);
+        for chunk_length in chunk_lengths {
+            chunks.push(cursor.read_bytes(chunk_length)?.to_vec());
+        }
+
+        if !cursor.remaining().is_empty() {
+            return Err(CodecError::new(
+                "container has trailing bytes after the last chunk",
+                CodecErrorKind::CorruptedData,
+            ));
+        }
+
+        Ok(Self {
+            algorithm,
+            original_length,
+            checksum,
+            chunks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::XorShift;
+
+    fn sample() -> Vec<u8> {
+        "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+            .repeat(1000)
+            .into_bytes()
+    }
+
+    #[test]
+    fn container_round_trip_works() {
+        let algorithms = [
+            Algorithm::Rle,
+            Algorithm::Huffman,
+            Algorithm::Bwt,
+            Algorithm::Lzw,
+            Algorithm::BwtRle,
+            Algorithm::BwtSais,
+            Algorithm::BlockSort,
+            Algorithm::AdaptiveHuffman,
+            Algorithm::Range,
+            Algorithm::Rans,
+            Algorithm::Tans,
+            Algorithm::Lzss,
+            Algorithm::Lz78,
+            Algorithm::Deflate,
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+            Algorithm::SnappyRaw,
+            Algorithm::Mtf,
+            Algorithm::HuffmanRle,
+            Algorithm::PackBits,
+            Algorithm::RleEscape,
+        ];
+
+        for algorithm in algorithms {
+            let data = sample();
+            let container = Container::compress(&data, algorithm.clone()).unwrap();
+            assert_eq!(container.chunks.len(), data.len().div_ceil(CHUNK_SIZE));
+
+            let parsed = Container::from_bytes(&container.to_bytes()).unwrap();
+            assert_eq!(parsed, container);
+            assert_eq!(parsed.decompress().unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn pipeline_round_trip_works() {
+        let data = sample();
+        let algorithm = Algorithm::from("bwtsais|mtf|rle|huffman".to_string());
+        let container = Container::compress(&data, algorithm).unwrap();
+
+        // the container only knows a pipeline, whose chunks hold the ids of its stages
+        let parsed = Container::from_bytes(&container.to_bytes()).unwrap();
+        assert_eq!(parsed.algorithm, Algorithm::Pipeline(vec![]));
+        assert_eq!(parsed.decompress().unwrap(), data);
+    }
+
+    #[test]
+    fn empty_data_works() {
+        let container = Container::compress(b"", Algorithm::Huffman).unwrap();
+        let parsed = Container::from_bytes(&container.to_bytes()).unwrap();
+        assert!(parsed.decompress().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_containers_are_rejected() {
+        let bytes = Container::compress(&sample(), Algorithm::Lzw)
+            .unwrap()
+            .to_bytes();
+
+        let mut foreign = bytes.clone();
+        foreign[0] = b'X';
+        let err = Container::from_bytes(&foreign).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::InvalidMagic);
+
+        for version in [1, VERSION + 1] {
+            let mut other = bytes.clone();
+            other[4] = version;
+            let err = Container::from_bytes(&other).unwrap_err();
+            assert_eq!(err.kind, CodecErrorKind::UnsupportedVersion);
+        }
+
+        let mut unknown = bytes.clone();
+        unknown[5] = 0;
+        let err = Container::from_bytes(&unknown).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::UnknownAlgorithm);
+
+        let err = Container::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::CorruptedData);
+
+        // corrupt the stored checksum so the payload still decodes
+        let mut tampered = bytes.clone();
+        tampered[14] ^= 0xff;
+        let err = Container::from_bytes(&tampered)
+            .unwrap()
+            .decompress()
+            .unwrap_err();
+        assert_eq!(err.kind, CodecErrorKind::ChecksumMismatch);
+    }
+
+    #[test]
+    fn binary_data_works() {
+        // random bytes spanning several chunks
+        let data = XorShift::new(7).bytes(3 * CHUNK_SIZE + 123);
+        for algorithm in [Algorithm::Huffman, Algorithm::Lzw, Algorithm::Rle] {
+            let container = Container::compress(&data, algorithm).unwrap();
+            let parsed = Container::from_bytes(&container.to_bytes()).unwrap();
+            assert_eq!(parsed.decompress().unwrap(), data);
+        }
+    }
+}
@@ -1,14 +1,15 @@
+use crate::service::io::container::Container;
 use crate::service::pkg::traits::{Reader, Writer};
 use std::error::Error;
 use std::fmt::{Debug, Formatter};
 use std::fs;
 use std::io::Write;
 
-/// File is a struct representing file operations on text data
+/// File is a struct representing file operations on text or binary data
 pub struct File {
     in_path: String,
     out_path: String,
-    content: String,
+    content: Vec<u8>,
 }
 
 impl File {
@@ -16,14 +17,25 @@ impl File {
         Self {
             in_path: in_path.to_string(),
             out_path: out_path.to_string(),
-            content: String::new(),
+            content: Vec::new(),
         }
     }
+
+    /// read_container reads and parses the container stored at the input path
+    pub fn read_container(&self) -> Result<Container, Box<dyn Error>> {
+        let bytes = fs::read(&self.in_path)?;
+        Ok(Container::from_bytes(&bytes)?)
+    }
+
+    /// write_container serializes the container to the output path
+    pub fn write_container(&self, container: &Container) -> Result<(), Box<dyn Error>> {
+        self.write(&container.to_bytes())
+    }
 }
 
 impl Debug for File {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.content)
+        write!(f, "{}", String::from_utf8_lossy(&self.content))
     }
 }
 
@@ -36,8 +48,8 @@ impl PartialEq<Self> for File {
 impl Eq for File {}
 
 impl Reader for File {
-    fn read(&mut self) -> Result<String, Box<dyn Error>> {
-        let content = fs::read_to_string(self.in_path.clone())?;
+    fn read(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let content = fs::read(self.in_path.clone())?;
         self.content = content.clone();
         Ok(content)
     }
@@ -53,6 +65,8 @@ impl Writer for File {
 
 #[cfg(test)]
 mod test {
+    use crate::service::algorithms::Algorithm;
+    use crate::service::io::container::Container;
     use crate::service::pkg::traits::{Reader, Writer};
     use log::info;
 
@@ -60,7 +74,7 @@ mod test {
     fn read_works() {
         let mut file = super::File::new("test_data.txt", "out_data.txt");
         match file.read() {
-            Ok(data) => {
+            Ok(_) => {
                 info!("File read successfully");
             }
             Err(e) => {
@@ -69,11 +83,47 @@ mod test {
         }
     }
 
+    #[test]
+    fn container_read_write_works() {
+        let path = std::env::temp_dir().join("text_compressor_file_test.tcz");
+        let path = path.to_str().unwrap();
+        let data = b"abracadabra! abracadabra! abracadabra!";
+
+        let container = Container::compress(data, Algorithm::Huffman).unwrap();
+        super::File::new(path, path)
+            .write_container(&container)
+            .expect("cannot write container to file");
+
+        let read = super::File::new(path, path)
+            .read_container()
+            .expect("cannot read container from file");
+        assert_eq!(read.decompress().unwrap(), data);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn write_works() {
         let mut file = super::File::new("test_data.txt", "out_data.txt");
         let data = file.read().expect("could not read file!");
-        file.write(data.as_bytes())
-            .expect("cannot write data to file");
+        file.write(&data).expect("cannot write data to file");
+    }
+
+    #[test]
+    fn binary_read_write_works() {
+        let path = std::env::temp_dir().join("text_compressor_file_binary_test.bin");
+        let path = path.to_str().unwrap();
+        // invalid UTF-8 and every byte value survive a write and a read
+        let data = [
+            &[0xff, 0xfe, 0x00, 0xc3][..],
+            &(0..=255).collect::<Vec<u8>>(),
+        ]
+        .concat();
+
+        let mut file = super::File::new(path, path);
+        file.write(&data).expect("cannot write data to file");
+        assert_eq!(file.read().expect("could not read file!"), data);
+
+        std::fs::remove_file(path).unwrap();
     }
 }
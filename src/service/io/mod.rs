@@ -1,23 +1,129 @@
 pub mod args;
+pub mod container;
 pub mod file;
+pub mod stream;
 
 use super::{
     algorithms::{
-        burrows_wheeler_run_length::BurrowsWheelerRunLength,
-        burrows_wheeler_transform::quadratic_log::BurrowsWheelerTransform, huffman::Huffman,
-        lempel_ziv_welch::LempelZivWelch, run_length_encoding::RunLengthEncoding, Algorithm,
+        adaptive_huffman::AdaptiveHuffman,
+        asymmetric_numeral_systems::{rans::Rans, tans::Tans},
+        block_sort::BlockSort,
+        burrows_wheeler_transform::{quadratic_log::BurrowsWheelerTransform, sa_is},
+        deflate::Deflate,
+        gzip::Gzip,
+        huffman::Huffman,
+        huffman_encoding_run_length::HuffmanRunLength,
+        lempel_ziv_78::LempelZiv78,
+        lempel_ziv_storer_szymanski::LempelZivStorerSzymanski,
+        lempel_ziv_welch::LempelZivWelch,
+        lz4::Lz4,
+        move_to_front::MoveToFront,
+        pipeline::Pipeline,
+        range_coding::RangeCoding,
+        run_length_encoding::{RleMode, RunLengthEncoding},
+        snappy::{Snappy, SnappyRaw},
+        zlib::Zlib,
+        Algorithm,
     },
     pkg::traits::Codec,
 };
 
-// new_codec takes in an input and choice of algorithm and returns the algorithm implementation
-pub fn new_codec(text: String, algorithm: Algorithm) -> Option<Box<dyn Codec>> {
+// new_codec takes in a choice of algorithm and returns the algorithm implementation
+pub fn new_codec(algorithm: Algorithm) -> Option<Box<dyn Codec>> {
     match algorithm {
-        Algorithm::Rle => Some(Box::new(RunLengthEncoding::new(text))),
-        Algorithm::Huffman => Some(Box::new(Huffman::new(text))),
-        Algorithm::Bwt => Some(Box::new(BurrowsWheelerTransform::new(text))),
-        Algorithm::Lzw => Some(Box::new(LempelZivWelch::new(text))),
-        Algorithm::BwtRle => Some(Box::new(BurrowsWheelerRunLength::new(text))),
+        Algorithm::Rle => Some(Box::new(RunLengthEncoding::new())),
+        Algorithm::Huffman => Some(Box::new(Huffman::new())),
+        Algorithm::Bwt => Some(Box::new(BurrowsWheelerTransform::new())),
+        Algorithm::Lzw => Some(Box::new(LempelZivWelch::new())),
+        Algorithm::BwtRle => Some(Box::new(Pipeline::new(vec![
+            Algorithm::BwtSais,
+            Algorithm::Rle,
+        ]))),
+        Algorithm::BwtSais => Some(Box::new(sa_is::BurrowsWheelerTransform::new())),
+        Algorithm::BlockSort => Some(Box::new(BlockSort::new())),
+        Algorithm::AdaptiveHuffman => Some(Box::new(AdaptiveHuffman::new())),
+        Algorithm::Range => Some(Box::new(RangeCoding::new())),
+        Algorithm::Rans => Some(Box::new(Rans::new())),
+        Algorithm::Tans => Some(Box::new(Tans::new())),
+        Algorithm::Lzss => Some(Box::new(LempelZivStorerSzymanski::new())),
+        Algorithm::Lz78 => Some(Box::new(LempelZiv78::new())),
+        Algorithm::Deflate => Some(Box::new(Deflate::new())),
+        Algorithm::Gzip => Some(Box::new(Gzip::new())),
+        Algorithm::Zlib => Some(Box::new(Zlib::new())),
+        Algorithm::Lz4 => Some(Box::new(Lz4::new())),
+        Algorithm::Snappy => Some(Box::new(Snappy::new())),
+        Algorithm::SnappyRaw => Some(Box::new(SnappyRaw::new())),
+        Algorithm::Mtf => Some(Box::new(MoveToFront::new())),
+        Algorithm::HuffmanRle => Some(Box::new(HuffmanRunLength::new())),
+        Algorithm::PackBits => Some(Box::new(RunLengthEncoding::with_mode(RleMode::PackBits))),
+        Algorithm::RleEscape => Some(Box::new(RunLengthEncoding::with_mode(RleMode::Escape))),
+        Algorithm::LimitedHuffman(max_code_length) => Some(Box::new(
+            Huffman::with_max_code_length(max_code_length).ok()?,
+        )),
+        Algorithm::Pipeline(stages) => Some(Box::new(Pipeline::described(stages))),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::errors::codec_error::CodecErrorKind;
+
+    const TEXT: &str = "TOBEORNOTTOBEORTOBEORNOT abracadabra! aaaaaaaabbbbbbbccccdd";
+
+    fn algorithms() -> Vec<Algorithm> {
+        vec![
+            Algorithm::Rle,
+            Algorithm::Huffman,
+            Algorithm::Bwt,
+            Algorithm::Lzw,
+            Algorithm::BwtRle,
+            Algorithm::BwtSais,
+            Algorithm::BlockSort,
+            Algorithm::AdaptiveHuffman,
+            Algorithm::Range,
+            Algorithm::Rans,
+            Algorithm::Tans,
+            Algorithm::Lzss,
+            Algorithm::Lz78,
+            Algorithm::Deflate,
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+            Algorithm::SnappyRaw,
+            Algorithm::Mtf,
+            Algorithm::HuffmanRle,
+            Algorithm::PackBits,
+            Algorithm::RleEscape,
+            Algorithm::LimitedHuffman(12),
+            Algorithm::from("bwtsais|mtf|rle|huffman".to_string()),
+        ]
+    }
+
+    #[test]
+    fn payload_decodes_with_a_new_codec() {
+        for algorithm in algorithms() {
+            let encoded = new_codec(algorithm.clone())
+                .unwrap()
+                .encode(TEXT.as_bytes())
+                .unwrap();
+            let decoded = new_codec(algorithm).unwrap().decode(&encoded).unwrap();
+            assert_eq!(decoded, TEXT.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decoded_size_is_limited() {
+        for algorithm in algorithms() {
+            let mut codec = new_codec(algorithm.clone()).unwrap();
+            let encoded = codec.encode(TEXT.as_bytes()).unwrap();
+
+            let decoded = codec.decode_limited(&encoded, TEXT.len()).unwrap();
+            assert_eq!(decoded, TEXT.as_bytes(), "{:?}", algorithm);
+            let err = codec.decode_limited(&encoded, TEXT.len() - 1).unwrap_err();
+            assert_eq!(err.kind, CodecErrorKind::CorruptedData, "{:?}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! The stream format compresses data block by block, so files larger than
+//! memory can be compressed and restored with a bounded amount of memory.
+//!
+//! Every integer is little endian. The header is followed by any number of
+//! blocks and ends with an empty block:
+//!
+//! | field            | size     |
+//! |------------------|----------|
+//! | magic `TCZS`     | 4 bytes  |
+//! | format version   | 1 byte   |
+//! | algorithm id     | 1 byte   |
+//!
+//! | block field      | size           |
+//! |------------------|----------------|
+//! | original length  | 4 bytes        |
+//! | encoded length   | 4 bytes        |
+//! | CRC-32           | 4 bytes        |
+//! | payload          | encoded length |
+//!
+//! Each block holds at most `CHUNK_SIZE` original bytes and its CRC-32 is
+//! computed over them. A block with an original length of zero ends the stream.
+
+use super::container::CHUNK_SIZE;
+use super::new_codec;
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+use crate::service::algorithms::Algorithm;
+use crate::service::pkg::traits::Codec;
+use crate::utils::checksum::crc32;
+use std::io::{self, Read, Write};
+
+/// MAGIC identifies a compressed stream
+pub const MAGIC: [u8; 4] = *b"TCZS";
+
+/// VERSION is the stream format version written by this build. Version 1 blocks hold the
+/// former payload layouts of the algorithms, so they are rejected like a newer version
+pub const VERSION: u8 = 2;
+
+/// BLOCK_HEADER_SIZE is the number of bytes in front of every block payload
+const BLOCK_HEADER_SIZE: usize = 12;
+
+/// is_stream reports whether the bytes start like a compressed stream
+pub fn is_stream(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+fn codec_for(algorithm: Algorithm) -> Result<Box<dyn Codec>, CodecError> {
+    new_codec(algorithm).ok_or_else(|| {
+        CodecError::new(
+            "algorithm cannot be used in a stream",
+            CodecErrorKind::UnknownAlgorithm,
+        )
+    })
+}
+
+/// StreamEncoder compresses everything written to it into the stream format.
+/// finish must be called once every byte is written to emit the last block.
+pub struct StreamEncoder<W: Write> {
+    inner: W,
+    codec: Box<dyn Codec>,
+    block: Vec<u8>,
+}
+
+impl<W: Write> StreamEncoder<W> {
+    /// new writes the stream header to the inner writer
+    pub fn new(mut inner: W, algorithm: Algorithm) -> io::Result<Self> {
+        let id = algorithm.id();
+        let codec = codec_for(algorithm)?;
+
+        inner.write_all(&MAGIC)?;
+        inner.write_all(&[VERSION, id.unwrap()])?;
+
+        Ok(Self {
+            inner,
+            codec,
+            block: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    /// write_block compresses the buffered bytes into a block, an empty buffer is skipped
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+
+        let encoded = self.codec.encode(&self.block)?;
+        self.inner
+            .write_all(&(self.block.len() as u32).to_le_bytes())?;
+        self.inner
+            .write_all(&(encoded.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&crc32(&self.block).to_le_bytes())?;
+        self.inner.write_all(&encoded)?;
+
+        self.block.clear();
+        Ok(())
+    }
+
+    /// finish writes the pending block and the end of the stream, then returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_block()?;
+        self.inner.write_all(&[0u8; BLOCK_HEADER_SIZE])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for StreamEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(CHUNK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+        if self.block.len() == CHUNK_SIZE {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    /// flush compresses the buffered bytes into a shorter block and flushes the inner writer
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+/// StreamDecoder restores the original data of a stream read from the inner reader
+pub struct StreamDecoder<R: Read> {
+    inner: R,
+    codec: Box<dyn Codec>,
+    block: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> StreamDecoder<R> {
+    /// new reads and checks the stream header from the inner reader
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 6];
+        read_exact_or_corrupted(&mut inner, &mut header)?;
+
+        if !is_stream(&header) {
+            return Err(CodecError::new(
+                "data is not a text compressor stream",
+                CodecErrorKind::InvalidMagic,
+            )
+            .into());
+        }
+        if header[4] != VERSION {
+            return Err(CodecError::new(
+                &format!("stream version {} is not supported", header[4]),
+                CodecErrorKind::UnsupportedVersion,
+            )
+            .into());
+        }
+        let algorithm = Algorithm::from_id(header[5]).ok_or_else(|| {
+            CodecError::new(
+                "stream algorithm is unknown",
+                CodecErrorKind::UnknownAlgorithm,
+            )
+        })?;
+
+        Ok(Self {
+            inner,
+            codec: codec_for(algorithm)?,
+            block: Vec::new(),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    /// read_block decodes the next block, or marks the stream finished at the empty block
+    fn read_block(&mut self) -> io::Result<()> {
+        let mut header = [0u8; BLOCK_HEADER_SIZE];
+        read_exact_or_corrupted(&mut self.inner, &mut header)?;
+        let original_length = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let encoded_length = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+        let checksum = u32::from_le_bytes(header[8..12].try_into().unwrap());
+
+        if original_length == 0 {
+            self.finished = true;
+            self.block.clear();
+            self.position = 0;
+            return Ok(());
+        }
+        if original_length > CHUNK_SIZE {
+            return Err(corrupted("stream block is larger than the block size").into());
+        }
+
+        // take bounds the allocation by the bytes really present rather than the header
+        let mut encoded = Vec::new();
+        (&mut self.inner)
+            .take(encoded_length)
+            .read_to_end(&mut encoded)?;
+        if encoded.len() as u64 != encoded_length {
+            return Err(corrupted("stream ended in the middle of a block").into());
+        }
+
+        let block = self.codec.decode_limited(&encoded, original_length)?;
+        if block.len() != original_length {
+            return Err(corrupted("decompressed length does not match the block header").into());
+        }
+        if crc32(&block) != checksum {
+            return Err(CodecError::new(
+                "decompressed block does not match its checksum",
+                CodecErrorKind::ChecksumMismatch,
+            )
+            .into());
+        }
+
+        self.block = block;
+        self.position = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.block.len() && !self.finished {
+            self.read_block()?;
+        }
+
+        let n = buf.len().min(self.block.len() - self.position);
+        buf[..n].copy_from_slice(&self.block[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+fn corrupted(message: &str) -> CodecError {
+    CodecError::new(message, CodecErrorKind::CorruptedData)
+}
+
+/// read_exact_or_corrupted fills buf, reporting a stream that ends early as corrupted data
+fn read_exact_or_corrupted<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<()> {
+    reader.read_exact(buf).map_err(|err| match err.kind() {
+        io::ErrorKind::UnexpectedEof => corrupted("stream ended before its end block").into(),
+        _ => err,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        "TOBEORNOTTOBEORTOBEORNOT abracadabra! "
+            .repeat(1500)
+            .into_bytes()
+    }
+
+    fn compress(data: &[u8], algorithm: Algorithm) -> Vec<u8> {
+        let mut encoder = StreamEncoder::new(Vec::new(), algorithm).unwrap();
+        // write in uneven pieces so blocks are filled across several calls
+        for piece in data.chunks(1000) {
+            encoder.write_all(piece).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    fn decompress(stream: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decoder = StreamDecoder::new(stream)?;
+        let mut data = Vec::new();
+        let mut buf = [0u8; 777];
+        loop {
+            let n = decoder.read(&mut buf)?;
+            if n == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    fn error_kind(err: io::Error) -> CodecErrorKind {
+        err.into_inner()
+            .unwrap()
+            .downcast::<CodecError>()
+            .unwrap()
+            .kind
+    }
+
+    #[test]
+    fn stream_round_trip_works() {
+        let algorithms = [
+            Algorithm::Rle,
+            Algorithm::Huffman,
+            Algorithm::Bwt,
+            Algorithm::Lzw,
+            Algorithm::BwtRle,
+            Algorithm::BwtSais,
+            Algorithm::BlockSort,
+            Algorithm::AdaptiveHuffman,
+            Algorithm::Range,
+            Algorithm::Rans,
+            Algorithm::Tans,
+            Algorithm::Lzss,
+            Algorithm::Lz78,
+            Algorithm::Deflate,
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+            Algorithm::SnappyRaw,
+            Algorithm::Mtf,
+            Algorithm::HuffmanRle,
+            Algorithm::PackBits,
+            Algorithm::RleEscape,
+            Algorithm::from("bwtsais|mtf|rle|huffman".to_string()),
+        ];
+
+        for algorithm in algorithms {
+            let data = sample();
+            let stream = compress(&data, algorithm);
+            assert!(is_stream(&stream));
+            assert_eq!(decompress(&stream).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn empty_stream_works() {
+        let stream = compress(b"", Algorithm::Huffman);
+        assert_eq!(stream.len(), MAGIC.len() + 2 + BLOCK_HEADER_SIZE);
+        assert!(decompress(&stream).unwrap().is_empty());
+    }
+
+    #[test]
+    fn flush_emits_a_short_block() {
+        let mut encoder = StreamEncoder::new(Vec::new(), Algorithm::Lzw).unwrap();
+        encoder.write_all(b"abracadabra!").unwrap();
+        encoder.flush().unwrap();
+        encoder.write_all(b" abracadabra!").unwrap();
+        let stream = encoder.finish().unwrap();
+        assert_eq!(decompress(&stream).unwrap(), b"abracadabra! abracadabra!");
+    }
+
+    #[test]
+    fn invalid_streams_are_rejected() {
+        let stream = compress(&sample(), Algorithm::Huffman);
+
+        let mut foreign = stream.clone();
+        foreign[0] = b'X';
+        let err = decompress(&foreign).unwrap_err();
+        assert_eq!(error_kind(err), CodecErrorKind::InvalidMagic);
+
+        for version in [1, VERSION + 1] {
+            let mut other = stream.clone();
+            other[4] = version;
+            let err = decompress(&other).unwrap_err();
+            assert_eq!(error_kind(err), CodecErrorKind::UnsupportedVersion);
+        }
+
+        let mut unknown = stream.clone();
+        unknown[5] = 0;
+        let err = decompress(&unknown).unwrap_err();
+        assert_eq!(error_kind(err), CodecErrorKind::UnknownAlgorithm);
+
+        let err = decompress(&stream[..stream.len() - 1]).unwrap_err();
+        assert_eq!(error_kind(err), CodecErrorKind::CorruptedData);
+
+        // corrupt the checksum of the first block so its payload still decodes
+        let mut tampered = stream.clone();
+        tampered[6 + 8] ^= 0xff;
+        let err = decompress(&tampered).unwrap_err();
+        assert_eq!(error_kind(err), CodecErrorKind::ChecksumMismatch);
+    }
+}
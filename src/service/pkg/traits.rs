@@ -1,18 +1,46 @@
+use crate::errors::codec_error::{CodecError, CodecErrorKind};
+
+/// MAX_DECODED_SIZE is the most bytes a codec decodes, so a corrupted size or count read
+/// from a header fails instead of allocating without bound
+pub const MAX_DECODED_SIZE: usize = 1 << 30;
+
 /// Codec is a public trait that holds interfaces for
-/// encoding and decoding a vector of characters.
+/// encoding and decoding a slice of bytes. The encoded
+/// output is the packed binary representation, so its
+/// length is the real size of the compressed data.
 pub trait Codec {
-    fn encode(&mut self);
-    fn decode(&mut self);
-    fn compressed(&self) -> String;
-    fn decompressed(&self) -> String;
+    fn encode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError>;
+
+    /// decode decodes data holding at most MAX_DECODED_SIZE bytes
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        self.decode_limited(data, MAX_DECODED_SIZE)
+    }
+
+    /// decode_limited decodes data holding at most max_size bytes. Data that decodes to
+    /// more fails as corrupted before the output grows past max_size
+    fn decode_limited(&mut self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError>;
+}
+
+/// check_decoded_size fails when a decoder would grow its output to size bytes, past max_size
+pub fn check_decoded_size(size: usize, max_size: usize) -> Result<(), CodecError> {
+    if size > max_size {
+        return Err(CodecError::new(
+            &format!(
+                "decoded data is larger than the limit of {} bytes",
+                max_size
+            ),
+            CodecErrorKind::CorruptedData,
+        ));
+    }
+    Ok(())
 }
 
 /// Reader is a public trait that holds interfaces for
-/// reading text input. Any object that can read text input
+/// reading input as raw bytes. Any object that can read input
 /// can implement this method, be it a file reader or an
 /// argument parser.
 pub trait Reader {
-    fn read(&mut self) -> Result<String, Box<dyn std::error::Error>>;
+    fn read(&mut self) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
 }
 
 /// Writer is a public trait that holds interfaces for
@@ -2,12 +2,22 @@ pub mod multi_thread;
 pub mod single_thread;
 
 use crate::{
+    errors::codec_error::{CodecError, CodecErrorKind},
     models::{compression_metric::CompressionMetric, threader::ThreadType},
-    service::{algorithms::Algorithm, pkg::traits::Reader},
+    service::{algorithms::Algorithm, io::new_codec, pkg::traits::Codec},
 };
-use std::error::Error;
 
-#[derive(Clone)]
+/// codec_for returns the codec of an algorithm, or an error for one that cannot be benchmarked
+fn codec_for(algorithm: &Algorithm) -> Result<Box<dyn Codec>, CodecError> {
+    new_codec(algorithm.clone()).ok_or_else(|| {
+        CodecError::new(
+            &format!("algorithm {} cannot be benchmarked", algorithm),
+            CodecErrorKind::UnknownAlgorithm,
+        )
+    })
+}
+
+#[derive(Clone, Default)]
 pub struct Threader {}
 
 impl Threader {
@@ -15,43 +25,186 @@ impl Threader {
         Self {}
     }
 
+    /// benchmark_algorithms compresses and decompresses the data with every algorithm.
+    /// A codec failing or not restoring the data is reported as an error rather than a panic
     pub fn benchmark_algorithms(
         &self,
-        text: String,
+        data: Vec<u8>,
         thread_type: ThreadType,
-    ) -> Vec<CompressionMetric> {
-        // let mut file = File::new(&file_name, "test_data/out_data.txt");
-        // let text = file.read().expect("cannot read file!");
-
+    ) -> Result<Vec<CompressionMetric>, CodecError> {
         let algorithms = [
             Algorithm::Rle,
             Algorithm::Lzw,
             Algorithm::Bwt,
             Algorithm::Huffman,
             Algorithm::BwtRle,
+            Algorithm::BwtSais,
+            Algorithm::BlockSort,
+            Algorithm::AdaptiveHuffman,
+            Algorithm::Range,
+            Algorithm::Rans,
+            Algorithm::Tans,
+            Algorithm::Lzss,
+            Algorithm::Lz78,
+            Algorithm::Deflate,
+            Algorithm::Gzip,
+            Algorithm::Zlib,
+            Algorithm::Lz4,
+            Algorithm::Snappy,
+            Algorithm::SnappyRaw,
+            Algorithm::HuffmanRle,
+            Algorithm::PackBits,
+            Algorithm::RleEscape,
+            Algorithm::LimitedHuffman(15),
+            Algorithm::Pipeline(vec![
+                Algorithm::BwtSais,
+                Algorithm::Mtf,
+                Algorithm::Rle,
+                Algorithm::Huffman,
+            ]),
         ];
 
         let mut metrics = Vec::with_capacity(algorithms.len());
 
-        algorithms.into_iter().for_each(|algorithm| {
-            metrics.push({
-                match thread_type {
-                    ThreadType::MultiThreaded => {
-                        multi_thread::compute_algorithm(text.clone(), algorithm)
-                    }
-                    ThreadType::SingleThreaded => {
-                        single_thread::compute_algorithm(text.clone(), algorithm)
-                    }
-                }
-            });
-        });
+        for algorithm in algorithms {
+            let metric = match thread_type {
+                ThreadType::MultiThreaded => multi_thread::compute_algorithm(&data, algorithm)?,
+                ThreadType::SingleThreaded => single_thread::compute_algorithm(&data, algorithm)?,
+            };
+
+            if metric.decoded != data {
+                return Err(CodecError::new(
+                    &format!("{} did not restore the original data", metric.algorithm),
+                    CodecErrorKind::CorruptedData,
+                ));
+            }
+            metrics.push(metric);
+        }
+
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_utils::binary_samples;
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    #[test]
+    fn text_with_the_old_sentinel_works() {
+        let text = "#!/bin/sh\necho \"total: $TOTAL costs $5\"\n".repeat(500);
+
+        for thread_type in [ThreadType::SingleThreaded, ThreadType::MultiThreaded] {
+            let metrics = Threader::new()
+                .benchmark_algorithms(text.clone().into_bytes(), thread_type)
+                .unwrap();
+            assert_eq!(metrics.len(), 24);
+        }
+    }
+
+    #[test]
+    fn binary_data_works() {
+        for sample in binary_samples() {
+            let metrics = Threader::new()
+                .benchmark_algorithms(sample, ThreadType::MultiThreaded)
+                .unwrap();
+            assert!(metrics.iter().all(|metric| metric.input_size > 0));
+        }
+    }
+
+    #[test]
+    fn lzw_throughput_is_reported() {
+        // long runs build long phrases, which the trie decodes without copying every prefix
+        let data = "TOBEORNOTTOBEORTOBEORNOT".repeat(2_000).into_bytes();
+        let metrics = Threader::new()
+            .benchmark_algorithms(data, ThreadType::SingleThreaded)
+            .unwrap();
 
-        for metric in metrics.iter() {
-            if !metric.decoded.is_empty() {
-                assert_eq!(metric.decoded, text);
+        let lzw = metrics
+            .iter()
+            .find(|metric| metric.algorithm == Algorithm::Lzw.format())
+            .unwrap();
+        assert!(lzw.throughput > 0.0);
+        assert!(lzw.compression_ratio > 10.0);
+    }
+
+    /// string_keyed_lzw encodes and decodes data the way LZW did before the trie, cloning the
+    /// pattern and hashing the whole of it for every byte
+    fn string_keyed_lzw(data: &[u8]) -> Vec<u8> {
+        if data.is_empty() {
+            return Vec::new();
+        }
+
+        let mut codes = (0..=u8::MAX).fold(HashMap::new(), |mut hashmap, idx| {
+            hashmap.insert(vec![idx], idx as u64);
+            hashmap
+        });
+        let mut pattern = vec![data[0]];
+        let mut encoded = Vec::new();
+        for curr_byte in &data[1..] {
+            let mut new_pattern = pattern.clone();
+            new_pattern.push(*curr_byte);
+            if codes.contains_key(&new_pattern) {
+                pattern = new_pattern;
+            } else {
+                encoded.push(codes[&pattern]);
+                codes.insert(new_pattern, codes.len() as u64);
+                pattern = vec![*curr_byte];
             }
         }
+        encoded.push(codes[&pattern]);
+
+        let mut phrases = (0..=u8::MAX).fold(HashMap::new(), |mut hashmap, idx| {
+            hashmap.insert(idx as u64, vec![idx]);
+            hashmap
+        });
+        let mut previous = phrases[&encoded[0]].clone();
+        let mut decoded = previous.clone();
+        for code in &encoded[1..] {
+            let current = match phrases.get(code) {
+                Some(phrase) => phrase.clone(),
+                None => [&previous[..], &previous[..1]].concat(),
+            };
+            decoded.extend_from_slice(&current);
+            phrases.insert(
+                phrases.len() as u64,
+                [&previous[..], &current[..1]].concat(),
+            );
+            previous = current;
+        }
+        decoded
+    }
+
+    #[test]
+    fn string_keyed_lzw_works() {
+        for sample in binary_samples() {
+            assert_eq!(string_keyed_lzw(&sample), sample);
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with cargo test --release -- --ignored --nocapture"]
+    fn lzw_trie_outruns_string_keys() {
+        // a long run builds ever longer phrases, each of which the old encoder hashed per byte
+        let text = format!(
+            "{}{}",
+            "a".repeat(200_000),
+            "TOBEORNOTTOBEORTOBEORNOT".repeat(10_000)
+        );
+
+        let start_time = Instant::now();
+        assert_eq!(string_keyed_lzw(text.as_bytes()), text.as_bytes());
+        let seconds = start_time.elapsed().as_secs_f64();
+        let string_keyed = text.len() as f64 / seconds / 1e6;
+
+        let trie = single_thread::compute_algorithm(text.as_bytes(), Algorithm::Lzw)
+            .unwrap()
+            .throughput;
 
-        metrics
+        println!("lzw throughput with string keys: {:.2} MB/s", string_keyed);
+        println!("lzw throughput with the trie: {:.2} MB/s", trie);
+        assert!(trie > string_keyed);
     }
 }
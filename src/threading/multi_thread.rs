@@ -1,18 +1,23 @@
 use crate::{
-    models::{compression_metric::CompressionMetric, part::Part},
-    service::{algorithms::Algorithm, io::new_codec},
+    errors::codec_error::{CodecError, CodecErrorKind},
+    models::compression_metric::CompressionMetric,
+    service::algorithms::Algorithm,
     utils::utils::split_into_parts,
 };
-use std::error::Error;
-use std::sync::{mpsc, Arc, Mutex};
+use std::sync::mpsc;
 use std::thread;
 use std::time::Instant;
 
+use super::codec_for;
+
 /// compute_algorithm benchmarks how long a particular algorithm took to run
-pub fn compute_algorithm(text: String, algorithm: Algorithm) -> CompressionMetric {
+pub fn compute_algorithm(
+    data: &[u8],
+    algorithm: Algorithm,
+) -> Result<CompressionMetric, CodecError> {
     // start the timer for benchmarking the time spent
     let start_time = Instant::now();
-    let parts = split_into_parts(text);
+    let parts = split_into_parts(data);
 
     // create channels for sending the compressed and decompressed data among threads
     let (compressed_tx, compressed_rx) = mpsc::channel();
@@ -28,75 +33,56 @@ pub fn compute_algorithm(text: String, algorithm: Algorithm) -> CompressionMetri
         let decompressed_tx_clone = decompressed_tx.clone();
         let algo = algorithm.clone();
 
-        let handle = thread::spawn(move || {
-            let mut codec = new_codec(part.1.clone(), algo)
-                .expect("codec should not be none");
+        let handle = thread::spawn(move || -> Result<(), CodecError> {
+            // encode the data part and send the compressed data to the compressed channel
+            let encoded = codec_for(&algo)?.encode(&part.1)?;
 
-            // encode the text part and send the compressed data to the compressed channel
-            codec.encode();
-            compressed_tx_clone
-                .send(Part(part.0, codec.compressed()))
-                .expect("compressed data should be sent to the compressed transmitter");
+            // decode the encoded part with a separate codec so only the payload is shared
+            let decoded = codec_for(&algo)?.decode(&encoded)?;
 
-            // decode the encoded part and send the decompressed data to the decompressed channel
-            codec.decode();
-            decompressed_tx_clone
-                .send(Part(part.0, codec.decompressed()))
-                .expect("decompressed data should be sent to the decompressed transmitter");
+            // the receivers live until every worker is joined, so sending cannot fail
+            let _ = compressed_tx_clone.send((part.0, encoded));
+            let _ = decompressed_tx_clone.send((part.0, decoded));
+            Ok(())
         });
         handles.push(handle);
     }
 
-    // drop the two sender channels so the aggregator thread does not wait to receive more data
+    // drop the two sender channels so the receivers stop once every worker is done
     drop(compressed_tx);
     drop(decompressed_tx);
 
-    // create the encoded and decoded results
-    let decoded_result = Arc::new(Mutex::new(Vec::new()));
-    let encoded_result = Arc::new(Mutex::new(Vec::new()));
-
-    // clone the results to send across the threads
-    let shared_decoded_result = Arc::clone(&decoded_result);
-    let shared_encoded_result = Arc::clone(&encoded_result);
-
-    // Spawn the aggregator thread
-    let aggregator_handle = thread::spawn(move || {
-        let mut encoded_result = shared_encoded_result.lock().unwrap();
-        for encoded_message in compressed_rx {
-            encoded_result.push(encoded_message);
-        }
-
-        let mut decoded_result = shared_decoded_result.lock().unwrap();
-        for decoded_message in decompressed_rx {
-            decoded_result.push(decoded_message);
-        }
-    });
-
-    // Wait for all threads to finish
+    // wait for all threads to finish and report the first failure
+    let mut result = Ok(());
     for handle in handles {
-        handle.join().unwrap();
+        let outcome = handle.join().unwrap_or_else(|_| {
+            Err(CodecError::new(
+                "a worker thread stopped before finishing its part",
+                CodecErrorKind::Internal,
+            ))
+        });
+        result = result.and(outcome);
     }
+    result?;
 
-    // Wait for the aggregator thread to finish
-    aggregator_handle.join().unwrap();
-
-    let mut encoded_result = encoded_result.lock().unwrap();
-    encoded_result.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut encoded_result = compressed_rx.iter().collect::<Vec<(usize, Vec<u8>)>>();
+    encoded_result.sort_by_key(|p| p.0);
     let encoded_result = encoded_result
-        .iter()
-        .map(|p| p.1.clone())
-        .collect::<Vec<String>>()
-        .join("");
+        .into_iter()
+        .flat_map(|p| p.1)
+        .collect::<Vec<u8>>();
 
-    let mut decoded_result = decoded_result.lock().unwrap();
-    decoded_result.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut decoded_result = decompressed_rx.iter().collect::<Vec<(usize, Vec<u8>)>>();
+    decoded_result.sort_by_key(|p| p.0);
     let decoded_result = decoded_result
-        .iter()
-        .map(|p| p.1.clone())
-        .collect::<Vec<String>>()
-        .join("");
-
-    let metric = CompressionMetric::new(algorithm, encoded_result, decoded_result, start_time);
-
-    metric
+        .into_iter()
+        .flat_map(|p| p.1)
+        .collect::<Vec<u8>>();
+
+    Ok(CompressionMetric::new(
+        algorithm,
+        encoded_result,
+        decoded_result,
+        start_time,
+    ))
 }
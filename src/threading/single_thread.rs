@@ -1,26 +1,26 @@
 use crate::{
-    models::compression_metric::CompressionMetric,
-    service::{algorithms::Algorithm, io::new_codec},
+    errors::codec_error::CodecError, models::compression_metric::CompressionMetric,
+    service::algorithms::Algorithm,
 };
-use std::error::Error;
 use std::time::Instant;
 
+use super::codec_for;
+
 /// compute_algorithm benchmarks how long a particular algorithm took to run
-pub fn compute_algorithm(text: String, algorithm: Algorithm) -> CompressionMetric {
+pub fn compute_algorithm(
+    data: &[u8],
+    algorithm: Algorithm,
+) -> Result<CompressionMetric, CodecError> {
     let start_time = Instant::now();
 
-    let mut codec = new_codec(text, algorithm.clone()).expect("codec should not be none");
-    codec.encode();
-    codec.decode();
+    let encoded = codec_for(&algorithm)?.encode(data)?;
 
-    println!("Algorithm: {:?}", algorithm);
+    // decode with a separate codec so only the payload is shared
+    let decoded = codec_for(&algorithm)?.decode(&encoded)?;
 
-    let metric = CompressionMetric::new(
-        algorithm,
-        codec.compressed(),
-        codec.decompressed(),
-        start_time,
-    );
+    println!("Algorithm: {:?}", algorithm);
 
-    metric
+    Ok(CompressionMetric::new(
+        algorithm, encoded, decoded, start_time,
+    ))
 }
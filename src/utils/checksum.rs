@@ -0,0 +1,244 @@
+/// CRC32_TABLE holds the remainders of every byte for the reflected IEEE polynomial
+const CRC32_TABLE: [u32; 256] = crc32_table(0xedb8_8320);
+
+/// CRC32C_TABLE holds the remainders of every byte for the reflected Castagnoli polynomial
+const CRC32C_TABLE: [u32; 256] = crc32_table(0x82f6_3b78);
+
+const fn crc32_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ polynomial
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// crc32_update feeds bytes into a running CRC computed with the table of its polynomial
+fn crc32_update(table: &[u32; 256], mut value: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        let idx = ((value ^ *byte as u32) & 0xff) as usize;
+        value = (value >> 8) ^ table[idx];
+    }
+    value
+}
+
+/// Crc32 computes the CRC-32 (IEEE 802.3) checksum of data fed in one or more parts
+#[derive(Clone, Copy)]
+pub struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self { value: 0xffff_ffff }
+    }
+
+    /// update feeds more bytes into the checksum
+    pub fn update(&mut self, data: &[u8]) {
+        self.value = crc32_update(&CRC32_TABLE, self.value, data);
+    }
+
+    /// finalize returns the checksum of every byte fed so far
+    pub fn finalize(&self) -> u32 {
+        !self.value
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// crc32 returns the CRC-32 checksum of the data
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finalize()
+}
+
+/// crc32c returns the CRC-32C (Castagnoli) checksum of the data, the checksum of Snappy frames
+pub fn crc32c(data: &[u8]) -> u32 {
+    !crc32_update(&CRC32C_TABLE, 0xffff_ffff, data)
+}
+
+/// ADLER_MODULUS is the largest prime below 2^16
+const ADLER_MODULUS: u32 = 65521;
+
+/// ADLER_BLOCK is the number of bytes summed before the sums can overflow 32 bits
+const ADLER_BLOCK: usize = 5552;
+
+/// Adler32 computes the Adler-32 checksum of zlib streams, fed in one or more parts
+#[derive(Clone, Copy)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    /// update feeds more bytes into the checksum, taking the modulo once per block
+    pub fn update(&mut self, data: &[u8]) {
+        for block in data.chunks(ADLER_BLOCK) {
+            for byte in block {
+                self.a += *byte as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MODULUS;
+            self.b %= ADLER_MODULUS;
+        }
+    }
+
+    /// finalize returns the checksum of every byte fed so far
+    pub fn finalize(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// adler32 returns the Adler-32 checksum of the data
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut adler = Adler32::new();
+    adler.update(data);
+    adler.finalize()
+}
+
+/// the primes of xxHash32
+const XXH_PRIME_1: u32 = 0x9e37_79b1;
+const XXH_PRIME_2: u32 = 0x85eb_ca77;
+const XXH_PRIME_3: u32 = 0xc2b2_ae3d;
+const XXH_PRIME_4: u32 = 0x27d4_eb2f;
+const XXH_PRIME_5: u32 = 0x1656_67b1;
+
+/// xxh32_round mixes 4 bytes of input into one of the accumulators of xxHash32
+fn xxh32_round(accumulator: u32, input: u32) -> u32 {
+    accumulator
+        .wrapping_add(input.wrapping_mul(XXH_PRIME_2))
+        .rotate_left(13)
+        .wrapping_mul(XXH_PRIME_1)
+}
+
+/// xxh32 returns the xxHash32 of the data, the checksum of LZ4 frames
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    let read_u32 = |bytes: &[u8]| u32::from_le_bytes(bytes[..4].try_into().unwrap());
+
+    // stripes of 16 bytes go through four accumulators
+    let stripes = data.chunks_exact(16);
+    let tail = stripes.remainder();
+    let mut hash = match data.len() {
+        0..=15 => seed.wrapping_add(XXH_PRIME_5),
+        _ => {
+            let mut accumulators = [
+                seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+                seed.wrapping_add(XXH_PRIME_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME_1),
+            ];
+            for stripe in stripes {
+                for (i, accumulator) in accumulators.iter_mut().enumerate() {
+                    *accumulator = xxh32_round(*accumulator, read_u32(&stripe[4 * i..]));
+                }
+            }
+            accumulators[0]
+                .rotate_left(1)
+                .wrapping_add(accumulators[1].rotate_left(7))
+                .wrapping_add(accumulators[2].rotate_left(12))
+                .wrapping_add(accumulators[3].rotate_left(18))
+        }
+    };
+    hash = hash.wrapping_add(data.len() as u32);
+
+    // the last bytes are mixed 4 at a time, then one at a time
+    let words = tail.chunks_exact(4);
+    let bytes = words.remainder();
+    for word in words {
+        hash = hash
+            .wrapping_add(read_u32(word).wrapping_mul(XXH_PRIME_3))
+            .rotate_left(17)
+            .wrapping_mul(XXH_PRIME_4);
+    }
+    for byte in bytes {
+        hash = hash
+            .wrapping_add((*byte as u32).wrapping_mul(XXH_PRIME_5))
+            .rotate_left(11)
+            .wrapping_mul(XXH_PRIME_1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(XXH_PRIME_2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(XXH_PRIME_3);
+    hash ^ hash >> 16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc32_works() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn crc32_in_parts_works() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finalize(), crc32(b"123456789"));
+    }
+
+    #[test]
+    fn crc32c_works() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(&[0; 32]), 0x8a91_36aa);
+    }
+
+    #[test]
+    fn adler32_works() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[0xff; 100_000]), 0x149a_302c);
+
+        let mut adler = Adler32::new();
+        adler.update(b"Wiki");
+        adler.update(b"pedia");
+        assert_eq!(adler.finalize(), adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn xxh32_works() {
+        assert_eq!(xxh32(b"", 0), 0x02cc_5d05);
+        assert_eq!(xxh32(b"", 1), 0x0b2c_b792);
+        assert_eq!(xxh32(b"abc", 0), 0x32d1_53ff);
+        assert_eq!(
+            xxh32(b"Nobody inspects the spammish repetition", 0),
+            0xe229_3b2f
+        );
+    }
+}
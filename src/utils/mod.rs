@@ -1 +1,5 @@
+pub mod checksum;
+#[cfg(test)]
+pub mod test_utils;
+#[allow(clippy::module_inception)]
 pub mod utils;
@@ -0,0 +1,39 @@
+/// XorShift is a small deterministic generator so tests can use random looking data
+/// that is the same on every run
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // a zero state would only ever produce zeros
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// bytes returns len random bytes
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+/// binary_samples returns inputs every codec must round-trip: random bytes,
+/// every byte value, invalid UTF-8 and UTF-8 text with emoji
+pub fn binary_samples() -> Vec<Vec<u8>> {
+    let mut rng = XorShift::new(0x5eed);
+    vec![
+        rng.bytes(1),
+        rng.bytes(100),
+        rng.bytes(5000),
+        (0..=255).collect(),
+        (0..=255).rev().cycle().take(1000).collect(),
+        vec![0xff, 0xfe, 0xfd, 0x00, 0x80, 0xc3, 0x28],
+        "héllo wörld 😀🚀 ünïcödé ✓ ".repeat(20).into_bytes(),
+    ]
+}
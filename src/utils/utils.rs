@@ -1,32 +1,104 @@
 use crate::models::part::Part;
 
-pub fn split_into_parts(text: String) -> Vec<Part> {
-    let n = text.len();
+/// split_into_parts splits the data into numbered parts of at most 16 KiB.
+/// The split works on bytes, so a part may end in the middle of a UTF-8 character.
+pub fn split_into_parts(data: &[u8]) -> Vec<Part> {
+    const INDEX: usize = 16384; // INDEX is 2^14
 
-    const INDEX: usize = 16385; // INDEX is 2^14
+    if data.len() < INDEX {
+        return vec![Part(0, data.to_vec())];
+    }
+
+    data.chunks(INDEX)
+        .enumerate()
+        .map(|(ptr, chunk)| Part(ptr, chunk.to_vec()))
+        .collect()
+}
 
-    if n < INDEX {
-        return vec![Part(0, text)];
+/// shannon_entropy returns the order-0 entropy of the data in bits per byte, the smallest
+/// average size a coder looking at each byte alone can reach
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0u64; 256];
+    for byte in data {
+        counts[*byte as usize] += 1;
     }
 
-    if n < INDEX {
-        return vec![Part(0, text)];
+    let n = data.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// to_hex renders bytes as a lowercase hexadecimal string
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// from_hex parses a hexadecimal string in either case, returning None if it is not one
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    // from_str_radix alone would also take a sign
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
     }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
 
-    let mut parts = Vec::new();
-    let mut ptr = 0usize;
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    let (mut l, mut r) = (0usize, INDEX);
-    while l < n && r <= n {
-        parts.push(Part(ptr, text[l..r].to_string()));
-        ptr += 1;
-        l = r;
-        r += INDEX;
+    #[test]
+    fn split_into_parts_works() {
+        // a four byte emoji straddles the part boundary
+        let data = "😀".repeat(5000).into_bytes();
+        let parts = split_into_parts(&data);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].1.len(), 16384);
+        assert_eq!(parts.iter().map(|part| part.0).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(
+            parts
+                .into_iter()
+                .flat_map(|part| part.1)
+                .collect::<Vec<u8>>(),
+            data
+        );
+
+        assert_eq!(split_into_parts(b"").len(), 1);
     }
 
-    if l < n {
-        parts.push(Part(ptr, text[l..].to_string()));
+    #[test]
+    fn shannon_entropy_works() {
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(shannon_entropy(b"aaaa"), 0.0);
+        assert_eq!(shannon_entropy(b"abab"), 1.0);
+        assert_eq!(shannon_entropy(b"abcdabcd"), 2.0);
+
+        let every_byte = (0..=255u8).collect::<Vec<u8>>();
+        assert_eq!(shannon_entropy(&every_byte), 8.0);
     }
 
-    parts
+    #[test]
+    fn to_hex_works() {
+        assert_eq!(to_hex(&[0x00, 0xab, 0xff]), "00abff");
+    }
+
+    #[test]
+    fn from_hex_works() {
+        assert_eq!(from_hex("00abFF"), Some(vec![0x00, 0xab, 0xff]));
+        assert_eq!(from_hex(""), Some(vec![]));
+        assert_eq!(from_hex(&to_hex(b"snappy")).unwrap(), b"snappy");
+
+        for invalid in ["abc", "0g", "+1", "é0"] {
+            assert_eq!(from_hex(invalid), None);
+        }
+    }
 }